@@ -0,0 +1,163 @@
+// Source: https://wiki.nesdev.com/w/index.php/APU_DMC
+
+use cartridge::Region;
+
+// Timer periods in CPU cycles
+const NTSC_RATES: [u16; 16] = [
+  428, 380, 340, 320, 286, 254, 226, 214, 190, 160, 142, 128, 106, 84, 72, 54,
+];
+const PAL_RATES: [u16; 16] = [
+  398, 354, 316, 298, 276, 236, 210, 198, 176, 148, 132, 118, 98, 78, 66, 50,
+];
+const SAMPLE_ADDR: u16 = 0xC000;
+const SAMPLE_WRAP_ADDR: u16 = 0x8000; // Reading continues here after 0xFFFF
+
+// Plays 1 bit delta encoded samples that it fetches from CPU memory by DMA
+pub struct Dmc {
+  irq_enabled: bool,
+  irq_flag: bool,
+  looping: bool,
+  period: u16, // Timer period in APU cycles
+  timer: u16,
+  // Memory reader
+  sample_addr: u16,
+  sample_length: u16,
+  current_addr: u16,
+  bytes_remaining: u16,
+  sample_buffer: Option<u8>, // Empty until the DMA refills it
+  // Output unit
+  shift: u8,
+  bits_remaining: u8,
+  silence: bool,
+  level: u8, // 7 bits
+}
+
+impl Dmc {
+  pub fn new() -> Dmc {
+    Dmc {
+      irq_enabled: false,
+      irq_flag: false,
+      looping: false,
+      period: NTSC_RATES[0] / 2,
+      timer: 0,
+      sample_addr: SAMPLE_ADDR,
+      sample_length: 1,
+      current_addr: SAMPLE_ADDR,
+      bytes_remaining: 0,
+      sample_buffer: None,
+      shift: 0,
+      bits_remaining: 8,
+      silence: true,
+      level: 0,
+    }
+  }
+
+  // One of the four registers at $4010
+  pub fn write_register(&mut self, register: u16, data: u8, region: Region) {
+    match register {
+      // IL-- RRRR, clearing I acknowledges the interrupt
+      0 => {
+        self.irq_enabled = data & 0x80 != 0;
+        if !self.irq_enabled {
+          self.irq_flag = false;
+        }
+        self.looping = data & 0x40 != 0;
+        let rate = match region {
+          Region::Pal => PAL_RATES[(data & 0x0F) as usize],
+          Region::Ntsc | Region::Multi | Region::Dendy => NTSC_RATES[(data & 0x0F) as usize],
+        };
+        self.period = rate / 2;
+      }
+      // -DDD DDDD
+      1 => self.level = data & 0x7F,
+      // AAAA AAAA, sample address 11AA AAAA AA00 0000
+      2 => self.sample_addr = SAMPLE_ADDR | (data as u16) << 6,
+      // LLLL LLLL, sample length LLLL LLLL 0001
+      _ => self.sample_length = ((data as u16) << 4) + 1,
+    }
+  }
+
+  // From $4015, which also acknowledges the interrupt. Enabling starts the sample unless it is
+  // still playing
+  pub fn set_enabled(&mut self, enabled: bool) {
+    self.irq_flag = false;
+    if !enabled {
+      self.bytes_remaining = 0;
+    } else if self.bytes_remaining == 0 {
+      self.restart();
+    }
+  }
+
+  fn restart(&mut self) {
+    self.current_addr = self.sample_addr;
+    self.bytes_remaining = self.sample_length;
+  }
+
+  pub fn is_active(&self) -> bool {
+    self.bytes_remaining > 0
+  }
+
+  pub fn is_irq_asserted(&self) -> bool {
+    self.irq_flag
+  }
+
+  // The address to fetch the next sample byte from while the buffer is empty
+  pub fn get_dma_addr(&self) -> Option<u16> {
+    match self.sample_buffer.is_none() && self.bytes_remaining > 0 {
+      true => Some(self.current_addr),
+      false => None,
+    }
+  }
+
+  // The byte the DMA fetched from the address it asked for
+  pub fn load_sample(&mut self, data: u8) {
+    self.sample_buffer = Some(data);
+    self.current_addr = match self.current_addr {
+      0xFFFF => SAMPLE_WRAP_ADDR,
+      addr => addr + 1,
+    };
+    self.bytes_remaining -= 1;
+    if self.bytes_remaining == 0 {
+      if self.looping {
+        self.restart();
+      } else if self.irq_enabled {
+        self.irq_flag = true;
+      }
+    }
+  }
+
+  // Every APU cycle, i.e. every other CPU cycle
+  pub fn clock_timer(&mut self) {
+    if self.timer > 0 {
+      self.timer -= 1;
+      return;
+    }
+    self.timer = self.period - 1;
+
+    // Each bit moves the level up or down by 2, unless that would leave 0-127
+    if !self.silence {
+      match self.shift & 0x01 != 0 {
+        true if self.level <= 125 => self.level += 2,
+        false if self.level >= 2 => self.level -= 2,
+        _ => {}
+      }
+    }
+    self.shift >>= 1;
+    self.bits_remaining -= 1;
+    if self.bits_remaining == 0 {
+      self.bits_remaining = 8;
+      match self.sample_buffer.take() {
+        Some(sample) => {
+          self.shift = sample;
+          self.silence = false;
+        }
+        None => self.silence = true,
+      }
+    }
+  }
+
+  // 0-127
+  pub fn get_output(&self) -> u8 {
+    self.level
+  }
+}
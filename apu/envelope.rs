@@ -0,0 +1,66 @@
+// Source: https://wiki.nesdev.com/w/index.php/APU_Envelope
+
+// Volume of the pulse and noise channels, either constant or a sawtooth decaying from 15
+pub struct Envelope {
+  start: bool,    // Restart the decay on the next quarter frame
+  looping: bool,  // Decay wraps from 0 to 15, also halts the length counter
+  constant: bool, // Output volume instead of the decay level
+  volume: u8,     // Constant volume, or the divider period
+  divider: u8,
+  decay: u8,
+}
+
+impl Envelope {
+  pub fn new() -> Envelope {
+    Envelope {
+      start: false,
+      looping: false,
+      constant: false,
+      volume: 0,
+      divider: 0,
+      decay: 0,
+    }
+  }
+
+  // --LC VVVV
+  pub fn write_control(&mut self, data: u8) {
+    self.looping = data & 0x20 != 0;
+    self.constant = data & 0x10 != 0;
+    self.volume = data & 0x0F;
+  }
+
+  pub fn restart(&mut self) {
+    self.start = true;
+  }
+
+  pub fn is_looping(&self) -> bool {
+    self.looping
+  }
+
+  // Quarter frame
+  pub fn clock(&mut self) {
+    if self.start {
+      self.start = false;
+      self.decay = 15;
+      self.divider = self.volume;
+      return;
+    }
+    if self.divider > 0 {
+      self.divider -= 1;
+      return;
+    }
+    self.divider = self.volume;
+    if self.decay > 0 {
+      self.decay -= 1;
+    } else if self.looping {
+      self.decay = 15;
+    }
+  }
+
+  pub fn get_volume(&self) -> u8 {
+    match self.constant {
+      true => self.volume,
+      false => self.decay,
+    }
+  }
+}
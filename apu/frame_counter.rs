@@ -0,0 +1,110 @@
+// Source: https://wiki.nesdev.com/w/index.php/APU_Frame_Counter
+
+use cartridge::Region;
+
+// CPU cycles of the quarter frames, the fourth ends the 4-step sequence and the fifth the 5-step
+const NTSC_STEPS: [u16; 5] = [7457, 14913, 22371, 29829, 37281];
+const PAL_STEPS: [u16; 5] = [8313, 16627, 24939, 33253, 41565];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FrameClock {
+  None,
+  Quarter, // Envelopes, triangle linear counter
+  Half,    // A quarter frame as well as length counters and sweeps
+}
+
+pub struct FrameCounter {
+  five_step: bool,
+  irq_inhibit: bool,
+  irq_flag: bool,
+  cycle: u16,                        // CPU cycles into the sequence
+  pending_write: Option<(u8, bool)>, // Cycles until a $4017 write restarts the sequence, and its mode
+}
+
+impl FrameCounter {
+  pub fn new() -> FrameCounter {
+    FrameCounter {
+      five_step: false,
+      irq_inhibit: false,
+      irq_flag: false,
+      cycle: 0,
+      pending_write: None,
+    }
+  }
+
+  // MI-- ----, the sequence restarts 3 or 4 cycles later depending on the APU cycle it lands in
+  pub fn write(&mut self, data: u8, odd_cycle: bool) {
+    self.irq_inhibit = data & 0x40 != 0;
+    if self.irq_inhibit {
+      self.irq_flag = false;
+    }
+    let delay = match odd_cycle {
+      true => 4,
+      false => 3,
+    };
+    self.pending_write = Some((delay, data & 0x80 != 0));
+  }
+
+  pub fn is_irq_asserted(&self) -> bool {
+    self.irq_flag
+  }
+
+  // Reading $4015 acknowledges the interrupt
+  pub fn clear_irq(&mut self) {
+    self.irq_flag = false;
+  }
+
+  fn set_irq(&mut self) {
+    if !self.irq_inhibit {
+      self.irq_flag = true;
+    }
+  }
+
+  // Every CPU cycle
+  pub fn clock(&mut self, region: Region) -> FrameClock {
+    if let Some((delay, five_step)) = self.pending_write {
+      if delay > 1 {
+        self.pending_write = Some((delay - 1, five_step));
+      } else {
+        // Entering 5-step mode clocks everything immediately
+        self.pending_write = None;
+        self.five_step = five_step;
+        self.cycle = 0;
+        return match five_step {
+          true => FrameClock::Half,
+          false => FrameClock::None,
+        };
+      }
+    }
+
+    let steps = match region {
+      Region::Pal => PAL_STEPS,
+      Region::Ntsc | Region::Multi | Region::Dendy => NTSC_STEPS,
+    };
+    self.cycle += 1;
+    match self.cycle {
+      cycle if cycle == steps[0] || cycle == steps[2] => FrameClock::Quarter,
+      cycle if cycle == steps[1] => FrameClock::Half,
+      // The interrupt flag is set over three cycles around the end of the 4-step sequence
+      cycle if !self.five_step && cycle == steps[3] - 1 => {
+        self.set_irq();
+        FrameClock::None
+      }
+      cycle if !self.five_step && cycle == steps[3] => {
+        self.set_irq();
+        FrameClock::Half
+      }
+      cycle if !self.five_step && cycle == steps[3] + 1 => {
+        self.set_irq();
+        self.cycle = 0;
+        FrameClock::None
+      }
+      cycle if self.five_step && cycle == steps[4] => FrameClock::Half,
+      cycle if self.five_step && cycle == steps[4] + 1 => {
+        self.cycle = 0;
+        FrameClock::None
+      }
+      _ => FrameClock::None,
+    }
+  }
+}
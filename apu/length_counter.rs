@@ -0,0 +1,52 @@
+// Source: https://wiki.nesdev.com/w/index.php/APU_Length_Counter
+
+const LENGTHS: [u8; 32] = [
+  10, 254, 20, 2, 40, 4, 80, 6, 160, 8, 60, 10, 14, 12, 26, 14, 12, 16, 24, 18, 48, 20, 96, 22,
+  192, 24, 72, 26, 16, 28, 32, 30,
+];
+
+// Silences a channel once it counts down to 0, unless halted
+pub struct LengthCounter {
+  enabled: bool, // From $4015, a disabled counter is held at 0
+  halted: bool,
+  counter: u8,
+}
+
+impl LengthCounter {
+  pub fn new() -> LengthCounter {
+    LengthCounter {
+      enabled: false,
+      halted: false,
+      counter: 0,
+    }
+  }
+
+  pub fn set_enabled(&mut self, enabled: bool) {
+    self.enabled = enabled;
+    if !enabled {
+      self.counter = 0;
+    }
+  }
+
+  pub fn set_halted(&mut self, halted: bool) {
+    self.halted = halted;
+  }
+
+  // Index from the top five bits of a channel's last register
+  pub fn load(&mut self, data: u8) {
+    if self.enabled {
+      self.counter = LENGTHS[(data >> 3) as usize];
+    }
+  }
+
+  // Half frame
+  pub fn clock(&mut self) {
+    if self.counter > 0 && !self.halted {
+      self.counter -= 1;
+    }
+  }
+
+  pub fn is_active(&self) -> bool {
+    self.counter > 0
+  }
+}
@@ -0,0 +1,32 @@
+// Source: https://wiki.nesdev.com/w/index.php/APU_Mixer
+
+// The channels are mixed by resistor networks that do not add linearly. Pulses share one
+// output, the triangle, noise and DMC another, each approximated by a lookup table
+pub struct Mixer {
+  pulse_table: [f32; 31],
+  tnd_table: [f32; 203],
+}
+
+impl Mixer {
+  pub fn new() -> Mixer {
+    let mut pulse_table = [0.0; 31];
+    for (n, level) in pulse_table.iter_mut().enumerate().skip(1) {
+      *level = 95.52 / (8128.0 / n as f32 + 100.0);
+    }
+    let mut tnd_table = [0.0; 203];
+    for (n, level) in tnd_table.iter_mut().enumerate().skip(1) {
+      *level = 163.67 / (24329.0 / n as f32 + 100.0);
+    }
+    Mixer {
+      pulse_table,
+      tnd_table,
+    }
+  }
+
+  // Pulse, triangle and noise levels are 0-15 and DMC 0-127, the output is 0.0-1.0
+  pub fn mix(&self, pulse_1: u8, pulse_2: u8, triangle: u8, noise: u8, dmc: u8) -> f32 {
+    let pulse = self.pulse_table[(pulse_1 + pulse_2) as usize];
+    let tnd = self.tnd_table[3 * triangle as usize + 2 * noise as usize + dmc as usize];
+    pulse + tnd
+  }
+}
@@ -0,0 +1,202 @@
+// Source: https://wiki.nesdev.com/w/index.php/APU
+
+mod dmc;
+mod envelope;
+mod frame_counter;
+mod length_counter;
+mod mixer;
+mod noise;
+mod pulse;
+mod tests;
+mod triangle;
+
+use apu::dmc::Dmc;
+use apu::frame_counter::{FrameClock, FrameCounter};
+use apu::mixer::Mixer;
+use apu::noise::Noise;
+use apu::pulse::Pulse;
+use apu::triangle::Triangle;
+use cartridge::Region;
+
+// $4015
+const STATUS_PULSE_1: u8 = 0x01;
+const STATUS_PULSE_2: u8 = 0x02;
+const STATUS_TRIANGLE: u8 = 0x04;
+const STATUS_NOISE: u8 = 0x08;
+const STATUS_DMC: u8 = 0x10;
+const STATUS_FRAME_IRQ: u8 = 0x40;
+const STATUS_DMC_IRQ: u8 = 0x80;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Channel {
+  Pulse1,
+  Pulse2,
+  Triangle,
+  Noise,
+  Dmc,
+}
+
+pub struct Apu {
+  region: Region,
+  cycle: u64, // CPU cycles since power on, pulse and DMC timers tick on every other one
+  pulse_1: Pulse,
+  pulse_2: Pulse,
+  triangle: Triangle,
+  noise: Noise,
+  dmc: Dmc,
+  frame_counter: FrameCounter,
+  mixer: Mixer,
+}
+
+impl Apu {
+  pub fn new() -> Apu {
+    Apu {
+      region: Region::Ntsc,
+      cycle: 0,
+      pulse_1: Pulse::new(true),
+      pulse_2: Pulse::new(false),
+      triangle: Triangle::new(),
+      noise: Noise::new(),
+      dmc: Dmc::new(),
+      frame_counter: FrameCounter::new(),
+      mixer: Mixer::new(),
+    }
+  }
+
+  // PAL consoles have their own frame sequencer timing and noise periods
+  pub fn set_region(&mut self, region: Region) {
+    self.region = region;
+  }
+
+  // $4000-$4013, $4015 and $4017
+  pub fn write_register(&mut self, addr: u16, data: u8) {
+    match addr {
+      0x4000..=0x4003 => self.pulse_1.write_register(addr & 0x03, data),
+      0x4004..=0x4007 => self.pulse_2.write_register(addr & 0x03, data),
+      0x4008..=0x400B => self.triangle.write_register(addr & 0x03, data),
+      0x400C..=0x400F => self.noise.write_register(addr & 0x03, data, self.region),
+      0x4010..=0x4013 => self.dmc.write_register(addr & 0x03, data, self.region),
+      // ---D NT21, disabling a channel silences it at once
+      0x4015 => {
+        self.pulse_1.set_enabled(data & STATUS_PULSE_1 != 0);
+        self.pulse_2.set_enabled(data & STATUS_PULSE_2 != 0);
+        self.triangle.set_enabled(data & STATUS_TRIANGLE != 0);
+        self.noise.set_enabled(data & STATUS_NOISE != 0);
+        self.dmc.set_enabled(data & STATUS_DMC != 0);
+      }
+      0x4017 => self.frame_counter.write(data, self.cycle % 2 == 1),
+      _ => {}
+    }
+  }
+
+  // IF-D NT21, the CPU reading it acknowledges the frame interrupt
+  pub fn read_status(&mut self) -> u8 {
+    let status = self.peek_status();
+    self.frame_counter.clear_irq();
+    status
+  }
+
+  // The status without the side effect of a CPU read
+  pub fn peek_status(&self) -> u8 {
+    let channels = [
+      (self.pulse_1.is_active(), STATUS_PULSE_1),
+      (self.pulse_2.is_active(), STATUS_PULSE_2),
+      (self.triangle.is_active(), STATUS_TRIANGLE),
+      (self.noise.is_active(), STATUS_NOISE),
+      (self.dmc.is_active(), STATUS_DMC),
+      (self.frame_counter.is_irq_asserted(), STATUS_FRAME_IRQ),
+      (self.dmc.is_irq_asserted(), STATUS_DMC_IRQ),
+    ];
+    channels
+      .iter()
+      .filter(|channel| channel.0)
+      .fold(0, |status, channel| status | channel.1)
+  }
+
+  pub fn is_irq_asserted(&self) -> bool {
+    self.frame_counter.is_irq_asserted() || self.dmc.is_irq_asserted()
+  }
+
+  // The DMC asks for a sample byte by DMA, the CPU halts to let it read one
+  pub fn get_dma_addr(&self) -> Option<u16> {
+    self.dmc.get_dma_addr()
+  }
+
+  pub fn load_sample(&mut self, data: u8) {
+    self.dmc.load_sample(data);
+  }
+
+  // DMA reads happen on the cycles the APU ticks, the others are spent waiting
+  pub fn is_dma_get_cycle(&self) -> bool {
+    self.cycle % 2 == 1
+  }
+
+  // One CPU cycle
+  pub fn clock(&mut self) {
+    self.triangle.clock_timer();
+    self.noise.clock_timer();
+    if self.cycle % 2 == 1 {
+      self.pulse_1.clock_timer();
+      self.pulse_2.clock_timer();
+      self.dmc.clock_timer();
+    }
+
+    match self.frame_counter.clock(self.region) {
+      FrameClock::None => {}
+      FrameClock::Quarter => self.clock_quarter_frame(),
+      FrameClock::Half => {
+        self.clock_quarter_frame();
+        self.clock_half_frame();
+      }
+    }
+    self.cycle += 1;
+  }
+
+  fn clock_quarter_frame(&mut self) {
+    self.pulse_1.clock_quarter_frame();
+    self.pulse_2.clock_quarter_frame();
+    self.triangle.clock_quarter_frame();
+    self.noise.clock_quarter_frame();
+  }
+
+  fn clock_half_frame(&mut self) {
+    self.pulse_1.clock_half_frame();
+    self.pulse_2.clock_half_frame();
+    self.triangle.clock_half_frame();
+    self.noise.clock_half_frame();
+  }
+
+  // Level of one channel before mixing
+  pub fn get_channel_output(&self, channel: Channel) -> u8 {
+    match channel {
+      Channel::Pulse1 => self.pulse_1.get_output(),
+      Channel::Pulse2 => self.pulse_2.get_output(),
+      Channel::Triangle => self.triangle.get_output(),
+      Channel::Noise => self.noise.get_output(),
+      Channel::Dmc => self.dmc.get_output(),
+    }
+  }
+
+  // One channel through the mixer with the others silent, on the same scale as get_output
+  pub fn get_solo_output(&self, channel: Channel) -> f32 {
+    let level = self.get_channel_output(channel);
+    match channel {
+      Channel::Pulse1 => self.mixer.mix(level, 0, 0, 0, 0),
+      Channel::Pulse2 => self.mixer.mix(0, level, 0, 0, 0),
+      Channel::Triangle => self.mixer.mix(0, 0, level, 0, 0),
+      Channel::Noise => self.mixer.mix(0, 0, 0, level, 0),
+      Channel::Dmc => self.mixer.mix(0, 0, 0, 0, level),
+    }
+  }
+
+  // The mixed output this cycle, 0.0-1.0. Sampled every CPU cycle this is the APU's sample stream
+  pub fn get_output(&self) -> f32 {
+    self.mixer.mix(
+      self.pulse_1.get_output(),
+      self.pulse_2.get_output(),
+      self.triangle.get_output(),
+      self.noise.get_output(),
+      self.dmc.get_output(),
+    )
+  }
+}
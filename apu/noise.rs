@@ -0,0 +1,104 @@
+// Source: https://wiki.nesdev.com/w/index.php/APU_Noise
+
+use apu::envelope::Envelope;
+use apu::length_counter::LengthCounter;
+use cartridge::Region;
+
+// Timer periods in CPU cycles
+const NTSC_PERIODS: [u16; 16] = [
+  4, 8, 16, 32, 64, 96, 128, 160, 202, 254, 380, 508, 762, 1016, 2034, 4068,
+];
+const PAL_PERIODS: [u16; 16] = [
+  4, 8, 14, 30, 60, 88, 118, 148, 188, 236, 354, 472, 708, 944, 1890, 3778,
+];
+
+pub struct Noise {
+  short_mode: bool, // Feedback from bit 6 instead of bit 1, a 93 step sequence
+  period: u16,
+  timer: u16,
+  shift: u16, // 15 bit linear feedback shift register
+  envelope: Envelope,
+  length: LengthCounter,
+}
+
+impl Noise {
+  pub fn new() -> Noise {
+    Noise {
+      short_mode: false,
+      period: NTSC_PERIODS[0],
+      timer: 0,
+      shift: 1,
+      envelope: Envelope::new(),
+      length: LengthCounter::new(),
+    }
+  }
+
+  // One of the registers at $400C, $400D is unused
+  pub fn write_register(&mut self, register: u16, data: u8, region: Region) {
+    match register {
+      // --LC VVVV
+      0 => {
+        self.envelope.write_control(data);
+        self.length.set_halted(self.envelope.is_looping());
+      }
+      1 => {}
+      // M--- PPPP
+      2 => {
+        self.short_mode = data & 0x80 != 0;
+        self.period = match region {
+          Region::Pal => PAL_PERIODS[(data & 0x0F) as usize],
+          Region::Ntsc | Region::Multi | Region::Dendy => NTSC_PERIODS[(data & 0x0F) as usize],
+        };
+      }
+      // LLLL L---
+      _ => {
+        self.length.load(data);
+        self.envelope.restart();
+      }
+    }
+  }
+
+  pub fn set_enabled(&mut self, enabled: bool) {
+    self.length.set_enabled(enabled);
+  }
+
+  pub fn is_active(&self) -> bool {
+    self.length.is_active()
+  }
+
+  // Every CPU cycle
+  pub fn clock_timer(&mut self) {
+    if self.timer > 0 {
+      self.timer -= 1;
+      return;
+    }
+    self.timer = self.period - 1;
+    let tap = match self.short_mode {
+      true => 6,
+      false => 1,
+    };
+    let feedback = (self.shift ^ (self.shift >> tap)) & 0x01;
+    self.shift = (self.shift >> 1) | (feedback << 14);
+  }
+
+  #[cfg(test)]
+  pub fn get_shift(&self) -> u16 {
+    self.shift
+  }
+
+  pub fn clock_quarter_frame(&mut self) {
+    self.envelope.clock();
+  }
+
+  pub fn clock_half_frame(&mut self) {
+    self.length.clock();
+  }
+
+  // 0-15
+  pub fn get_output(&self) -> u8 {
+    match self.shift & 0x01 != 0 || !self.length.is_active() {
+      true => 0,
+      false => self.envelope.get_volume(),
+    }
+  }
+}
@@ -0,0 +1,143 @@
+// Source: https://wiki.nesdev.com/w/index.php/APU_Pulse
+// Source: https://wiki.nesdev.com/w/index.php/APU_Sweep
+
+use apu::envelope::Envelope;
+use apu::length_counter::LengthCounter;
+
+const DUTY_CYCLES: [[u8; 8]; 4] = [
+  [0, 1, 0, 0, 0, 0, 0, 0], // 12.5%
+  [0, 1, 1, 0, 0, 0, 0, 0], // 25%
+  [0, 1, 1, 1, 1, 0, 0, 0], // 50%
+  [1, 0, 0, 1, 1, 1, 1, 1], // 25% negated
+];
+const MIN_PERIOD: u16 = 8; // Shorter periods are muted
+const MAX_PERIOD: u16 = 0x7FF;
+
+pub struct Pulse {
+  ones_complement: bool, // Pulse 1's sweep subtracts one more when negating
+  duty: usize,
+  step: usize, // Position in the duty cycle, counts down
+  period: u16, // Timer period in APU cycles, 11 bits
+  timer: u16,
+  envelope: Envelope,
+  length: LengthCounter,
+  // Sweep unit
+  sweep_enabled: bool,
+  sweep_period: u8,
+  sweep_negate: bool,
+  sweep_shift: u8,
+  sweep_divider: u8,
+  sweep_reload: bool,
+}
+
+impl Pulse {
+  pub fn new(ones_complement: bool) -> Pulse {
+    Pulse {
+      ones_complement,
+      duty: 0,
+      step: 0,
+      period: 0,
+      timer: 0,
+      envelope: Envelope::new(),
+      length: LengthCounter::new(),
+      sweep_enabled: false,
+      sweep_period: 0,
+      sweep_negate: false,
+      sweep_shift: 0,
+      sweep_divider: 0,
+      sweep_reload: false,
+    }
+  }
+
+  // One of the four registers at $4000 or $4004
+  pub fn write_register(&mut self, register: u16, data: u8) {
+    match register {
+      // DDLC VVVV
+      0 => {
+        self.duty = (data >> 6) as usize;
+        self.envelope.write_control(data);
+        self.length.set_halted(self.envelope.is_looping());
+      }
+      // EPPP NSSS
+      1 => {
+        self.sweep_enabled = data & 0x80 != 0;
+        self.sweep_period = (data >> 4) & 0x07;
+        self.sweep_negate = data & 0x08 != 0;
+        self.sweep_shift = data & 0x07;
+        self.sweep_reload = true;
+      }
+      // TTTT TTTT
+      2 => self.period = (self.period & 0x0700) | data as u16,
+      // LLLL LTTT, restarts the duty cycle and envelope
+      _ => {
+        self.period = (self.period & 0x00FF) | ((data as u16 & 0x07) << 8);
+        self.length.load(data);
+        self.step = 0;
+        self.envelope.restart();
+      }
+    }
+  }
+
+  pub fn set_enabled(&mut self, enabled: bool) {
+    self.length.set_enabled(enabled);
+  }
+
+  pub fn is_active(&self) -> bool {
+    self.length.is_active()
+  }
+
+  // Every APU cycle, i.e. every other CPU cycle
+  pub fn clock_timer(&mut self) {
+    if self.timer == 0 {
+      self.timer = self.period;
+      self.step = (self.step + 7) % 8;
+    } else {
+      self.timer -= 1;
+    }
+  }
+
+  pub fn clock_quarter_frame(&mut self) {
+    self.envelope.clock();
+  }
+
+  pub fn clock_half_frame(&mut self) {
+    self.length.clock();
+
+    if self.sweep_divider == 0 && self.sweep_enabled && self.sweep_shift > 0 && !self.is_muted() {
+      self.period = self.get_target_period();
+    }
+    if self.sweep_divider == 0 || self.sweep_reload {
+      self.sweep_divider = self.sweep_period;
+      self.sweep_reload = false;
+    } else {
+      self.sweep_divider -= 1;
+    }
+  }
+
+  #[cfg(test)]
+  pub fn get_period(&self) -> u16 {
+    self.period
+  }
+
+  // The sweep computes this continuously, whether or not it is enabled
+  fn get_target_period(&self) -> u16 {
+    let change = self.period >> self.sweep_shift;
+    match (self.sweep_negate, self.ones_complement) {
+      (false, _) => self.period + change,
+      (true, false) => self.period.saturating_sub(change),
+      (true, true) => self.period.saturating_sub(change + 1),
+    }
+  }
+
+  fn is_muted(&self) -> bool {
+    self.period < MIN_PERIOD || self.get_target_period() > MAX_PERIOD
+  }
+
+  // 0-15
+  pub fn get_output(&self) -> u8 {
+    if self.is_muted() || !self.length.is_active() || DUTY_CYCLES[self.duty][self.step] == 0 {
+      return 0;
+    }
+    self.envelope.get_volume()
+  }
+}
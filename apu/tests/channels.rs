@@ -0,0 +1,310 @@
+#[cfg(test)]
+use apu::noise::Noise;
+
+#[cfg(test)]
+use apu::pulse::Pulse;
+
+#[cfg(test)]
+use apu::tests::{run, setup};
+
+#[cfg(test)]
+use apu::{Apu, Channel};
+
+#[cfg(test)]
+use cartridge::Region;
+
+// Cycles the channel's output was not silent for
+#[cfg(test)]
+fn count_audible(apu: &mut Apu, channel: Channel, cycles: usize) -> usize {
+  (0..cycles)
+    .filter(|_| {
+      apu.clock();
+      apu.get_channel_output(channel) > 0
+    })
+    .count()
+}
+
+#[cfg(test)]
+fn get_loudest(apu: &mut Apu, channel: Channel, cycles: usize) -> u8 {
+  (0..cycles)
+    .map(|_| {
+      apu.clock();
+      apu.get_channel_output(channel)
+    })
+    .max()
+    .unwrap()
+}
+
+#[cfg(test)]
+mod pulse {
+  use super::*;
+  #[test]
+  fn plays_duty_cycle() {
+    // A period of 8 steps the duty cycle every 18 CPU cycles
+    let mut apu = setup();
+    apu.write_register(0x4002, 0x08);
+    assert_eq!(count_audible(&mut apu, Channel::Pulse1, 8 * 18), 4 * 18);
+    apu.write_register(0x4000, 0x1F);
+    assert_eq!(count_audible(&mut apu, Channel::Pulse1, 8 * 18), 18);
+    apu.write_register(0x4000, 0xDF);
+    assert_eq!(count_audible(&mut apu, Channel::Pulse1, 8 * 18), 6 * 18);
+  }
+  #[test]
+  fn short_period_is_muted() {
+    let mut apu = setup();
+    apu.write_register(0x4002, 0x07);
+    assert_eq!(count_audible(&mut apu, Channel::Pulse1, 1000), 0);
+  }
+  #[test]
+  fn sweep_overflow_mutes_even_when_disabled() {
+    // Shift 0 doubles the period, past 0x7FF from 0x400 up
+    let mut apu = setup();
+    apu.write_register(0x4003, 0x1C);
+    assert_eq!(count_audible(&mut apu, Channel::Pulse1, 20_000), 0);
+    apu.write_register(0x4003, 0x1B);
+    assert!(count_audible(&mut apu, Channel::Pulse1, 20_000) > 0);
+  }
+  #[test]
+  fn envelope_decays_from_fifteen() {
+    let mut apu = setup();
+    apu.write_register(0x4000, 0x80);
+    apu.write_register(0x4002, 0x08);
+    // The first quarter frame starts the decay at 15, each one after takes off 1
+    run(&mut apu, 7457);
+    assert_eq!(get_loudest(&mut apu, Channel::Pulse1, 8 * 18), 15);
+    run(&mut apu, 14_913 - 7457 - 8 * 18);
+    assert_eq!(get_loudest(&mut apu, Channel::Pulse1, 8 * 18), 14);
+  }
+  #[test]
+  fn sweep_negates_with_ones_complement_on_pulse_1() {
+    let mut pulse_1 = Pulse::new(true);
+    let mut pulse_2 = Pulse::new(false);
+    for pulse in [&mut pulse_1, &mut pulse_2].iter_mut() {
+      pulse.write_register(2, 0x00);
+      pulse.write_register(3, 0x01);
+      pulse.write_register(1, 0x89);
+      pulse.clock_half_frame();
+    }
+    assert_eq!(pulse_1.get_period(), 0x7F);
+    assert_eq!(pulse_2.get_period(), 0x80);
+  }
+  #[test]
+  fn sweep_adds_shifted_period() {
+    let mut pulse = Pulse::new(true);
+    pulse.write_register(2, 0x00);
+    pulse.write_register(3, 0x01);
+    // Divider period 1, so the period changes every other half frame
+    pulse.write_register(1, 0x92);
+    pulse.clock_half_frame();
+    assert_eq!(pulse.get_period(), 0x140);
+    pulse.clock_half_frame();
+    assert_eq!(pulse.get_period(), 0x140);
+    pulse.clock_half_frame();
+    assert_eq!(pulse.get_period(), 0x190);
+  }
+}
+
+#[cfg(test)]
+mod triangle {
+  use super::*;
+  #[test]
+  fn waits_for_linear_counter() {
+    let mut apu = Apu::new();
+    apu.write_register(0x4015, 0x04);
+    apu.write_register(0x4008, 0x81);
+    apu.write_register(0x400A, 0x00);
+    apu.write_register(0x400B, 0x08);
+    run(&mut apu, 7456);
+    assert_eq!(apu.get_channel_output(Channel::Triangle), 15);
+    run(&mut apu, 3);
+    assert_eq!(apu.get_channel_output(Channel::Triangle), 13);
+  }
+  #[test]
+  fn linear_counter_stops_sequence_without_silencing() {
+    let mut apu = Apu::new();
+    apu.write_register(0x4015, 0x04);
+    apu.write_register(0x4008, 0x01);
+    apu.write_register(0x400A, 0x00);
+    apu.write_register(0x400B, 0x08);
+    // Loaded on the first quarter frame and counted to 0 on the second
+    run(&mut apu, 14_913);
+    let level = apu.get_channel_output(Channel::Triangle);
+    run(&mut apu, 100);
+    assert_eq!(apu.get_channel_output(Channel::Triangle), level);
+    assert_eq!(apu.peek_status() & 0x04, 0x04);
+  }
+  #[test]
+  fn steps_through_thirty_two_levels() {
+    let mut apu = Apu::new();
+    apu.write_register(0x4015, 0x04);
+    apu.write_register(0x4008, 0xFF);
+    apu.write_register(0x400A, 0x00);
+    apu.write_register(0x400B, 0x08);
+    run(&mut apu, 7457);
+    let levels: Vec<u8> = (0..32)
+      .map(|_| {
+        apu.clock();
+        apu.get_channel_output(Channel::Triangle)
+      })
+      .collect();
+    let mut sorted = levels.clone();
+    sorted.sort();
+    assert_eq!(sorted, (0..32).map(|level| level / 2).collect::<Vec<u8>>());
+  }
+}
+
+#[cfg(test)]
+mod noise {
+  use super::*;
+  fn sequence_length(short_mode: bool) -> usize {
+    let mut noise = Noise::new();
+    noise.write_register(2, if short_mode { 0x80 } else { 0x00 }, Region::Ntsc);
+    // Leave the initial state, which is outside the short sequence
+    for _ in 0..4 * 100 {
+      noise.clock_timer();
+    }
+    let start = noise.get_shift();
+    (1..)
+      .find(|_| {
+        for _ in 0..4 {
+          noise.clock_timer();
+        }
+        noise.get_shift() == start
+      })
+      .unwrap()
+  }
+  #[test]
+  fn long_mode_repeats_after_32767_steps() {
+    assert_eq!(sequence_length(false), 32_767);
+  }
+  #[test]
+  fn short_mode_repeats_after_93_steps() {
+    assert_eq!(sequence_length(true), 93);
+  }
+  #[test]
+  fn is_silent_while_bit_0_is_set() {
+    let mut apu = Apu::new();
+    apu.write_register(0x4015, 0x08);
+    apu.write_register(0x400C, 0x1F);
+    apu.write_register(0x400F, 0x08);
+    let audible = count_audible(&mut apu, Channel::Noise, 4 * 32_767);
+    assert!(audible > 4 * 16_000 && audible < 4 * 17_000, "{}", audible);
+  }
+  #[test]
+  fn pal_uses_its_own_periods() {
+    let mut ntsc = Noise::new();
+    let mut pal = Noise::new();
+    ntsc.write_register(2, 0x02, Region::Ntsc);
+    pal.write_register(2, 0x02, Region::Pal);
+    for _ in 0..16 * 14 {
+      ntsc.clock_timer();
+      pal.clock_timer();
+    }
+    assert_ne!(ntsc.get_shift(), pal.get_shift());
+  }
+}
+
+#[cfg(test)]
+mod dmc {
+  use super::*;
+  // Does the DMA's job, answering every fetch with the same byte. Returns the addresses read
+  fn serve_dma(apu: &mut Apu, sample: u8, cycles: usize) -> Vec<u16> {
+    let mut addrs = Vec::new();
+    for _ in 0..cycles {
+      if let Some(addr) = apu.get_dma_addr() {
+        addrs.push(addr);
+        apu.load_sample(sample);
+      }
+      apu.clock();
+    }
+    addrs
+  }
+  #[test]
+  fn direct_load_sets_level() {
+    let mut apu = Apu::new();
+    apu.write_register(0x4011, 0xC5);
+    assert_eq!(apu.get_channel_output(Channel::Dmc), 0x45);
+  }
+  #[test]
+  fn plays_sample_bits_as_steps_of_two() {
+    let mut apu = Apu::new();
+    apu.write_register(0x4010, 0x0F);
+    apu.write_register(0x4011, 0x40);
+    apu.write_register(0x4013, 0x00);
+    apu.write_register(0x4015, 0x10);
+    // The first output cycle is silent, then the byte's eight 1 bits each add 2
+    serve_dma(&mut apu, 0xFF, 8 * 54);
+    assert_eq!(apu.get_channel_output(Channel::Dmc), 0x40);
+    serve_dma(&mut apu, 0xFF, 8 * 54);
+    assert_eq!(apu.get_channel_output(Channel::Dmc), 0x40 + 16);
+  }
+  #[test]
+  fn level_stays_in_range() {
+    let mut apu = Apu::new();
+    apu.write_register(0x4010, 0x4F);
+    apu.write_register(0x4011, 0x7E);
+    apu.write_register(0x4015, 0x10);
+    serve_dma(&mut apu, 0xFF, 4 * 8 * 54);
+    assert_eq!(apu.get_channel_output(Channel::Dmc), 0x7E);
+    apu.write_register(0x4011, 0x01);
+    serve_dma(&mut apu, 0x00, 4 * 8 * 54);
+    assert_eq!(apu.get_channel_output(Channel::Dmc), 0x01);
+  }
+  #[test]
+  fn reads_sample_from_its_address() {
+    let mut apu = Apu::new();
+    apu.write_register(0x4012, 0x01);
+    apu.write_register(0x4013, 0x00);
+    assert_eq!(apu.get_dma_addr(), None);
+    apu.write_register(0x4015, 0x10);
+    assert_eq!(apu.peek_status(), 0x10);
+    assert_eq!(serve_dma(&mut apu, 0, 2000), vec![0xC040]);
+    assert_eq!(apu.peek_status(), 0x00);
+  }
+  #[test]
+  fn address_wraps_to_8000() {
+    let mut apu = Apu::new();
+    apu.write_register(0x4010, 0x0F);
+    apu.write_register(0x4012, 0xFF);
+    apu.write_register(0x4013, 0x04);
+    apu.write_register(0x4015, 0x10);
+    let addrs = serve_dma(&mut apu, 0, 65 * 8 * 54);
+    assert_eq!(addrs.len(), 65);
+    assert_eq!(addrs[63..], [0xFFFF, 0x8000]);
+  }
+  #[test]
+  fn interrupts_at_end_of_sample() {
+    let mut apu = Apu::new();
+    apu.write_register(0x4017, 0x40);
+    apu.write_register(0x4010, 0x8F);
+    apu.write_register(0x4015, 0x10);
+    serve_dma(&mut apu, 0, 1);
+    assert!(apu.is_irq_asserted());
+    assert_eq!(apu.read_status(), 0x80);
+    // Reading does not acknowledge it, writing $4015 or clearing the enable bit does
+    assert_eq!(apu.read_status(), 0x80);
+    apu.write_register(0x4015, 0x00);
+    assert!(!apu.is_irq_asserted());
+  }
+  #[test]
+  fn loops_without_interrupting() {
+    let mut apu = Apu::new();
+    apu.write_register(0x4017, 0x40);
+    apu.write_register(0x4010, 0xCF);
+    apu.write_register(0x4013, 0x00);
+    apu.write_register(0x4015, 0x10);
+    // The buffer fills at once, then each byte played is fetched again
+    let addrs = serve_dma(&mut apu, 0, 4 * 8 * 54);
+    assert_eq!(addrs, vec![0xC000; 5]);
+    assert!(!apu.is_irq_asserted());
+    assert_eq!(apu.peek_status(), 0x10);
+  }
+  #[test]
+  fn fetches_at_its_rate() {
+    let mut apu = Apu::new();
+    apu.write_register(0x4010, 0x40);
+    apu.write_register(0x4015, 0x10);
+    // Each byte lasts eight output clocks of 428 CPU cycles
+    assert_eq!(serve_dma(&mut apu, 0, 10 * 8 * 428).len(), 11);
+  }
+}
@@ -0,0 +1,148 @@
+mod channels;
+
+#[cfg(test)]
+use apu::mixer::Mixer;
+
+#[cfg(test)]
+use apu::{Apu, Channel};
+
+#[cfg(test)]
+fn run(apu: &mut Apu, cycles: usize) {
+  for _ in 0..cycles {
+    apu.clock();
+  }
+}
+
+// Pulse 1 with its length counter loaded with 2, at constant volume 15 and a 50% duty cycle
+#[cfg(test)]
+fn setup() -> Apu {
+  let mut apu = Apu::new();
+  apu.write_register(0x4015, 0x01);
+  apu.write_register(0x4000, 0x9F);
+  apu.write_register(0x4002, 0x00);
+  apu.write_register(0x4003, 0x18);
+  apu
+}
+
+#[cfg(test)]
+mod status {
+  use super::*;
+  #[test]
+  fn reports_active_length_counters() {
+    let mut apu = setup();
+    assert_eq!(apu.peek_status(), 0x01);
+    apu.write_register(0x4015, 0x0F);
+    apu.write_register(0x400B, 0x08);
+    apu.write_register(0x400F, 0x08);
+    assert_eq!(apu.peek_status(), 0x0D);
+  }
+  #[test]
+  fn disabled_channels_do_not_load() {
+    let mut apu = Apu::new();
+    apu.write_register(0x4003, 0x08);
+    apu.write_register(0x4007, 0x08);
+    assert_eq!(apu.peek_status(), 0x00);
+  }
+  #[test]
+  fn disabling_clears_length_counter() {
+    let mut apu = setup();
+    apu.write_register(0x4015, 0x00);
+    apu.write_register(0x4015, 0x01);
+    assert_eq!(apu.peek_status(), 0x00);
+    assert_eq!(apu.get_channel_output(Channel::Pulse1), 0);
+  }
+  #[test]
+  fn read_acknowledges_frame_interrupt() {
+    let mut apu = Apu::new();
+    run(&mut apu, 29_830);
+    assert_eq!(apu.peek_status(), 0x40);
+    assert_eq!(apu.read_status(), 0x40);
+    assert_eq!(apu.read_status(), 0x00);
+    assert!(!apu.is_irq_asserted());
+  }
+}
+
+#[cfg(test)]
+mod frame_counter {
+  use super::*;
+  #[test]
+  fn four_step_sets_interrupt_at_end_of_sequence() {
+    let mut apu = Apu::new();
+    run(&mut apu, 29_827);
+    assert!(!apu.is_irq_asserted());
+    run(&mut apu, 1);
+    assert!(apu.is_irq_asserted());
+  }
+  #[test]
+  fn inhibit_clears_and_prevents_interrupt() {
+    let mut apu = Apu::new();
+    run(&mut apu, 29_830);
+    apu.write_register(0x4017, 0x40);
+    assert!(!apu.is_irq_asserted());
+    run(&mut apu, 2 * 29_830);
+    assert!(!apu.is_irq_asserted());
+  }
+  #[test]
+  fn five_step_never_interrupts() {
+    let mut apu = Apu::new();
+    apu.write_register(0x4017, 0x80);
+    run(&mut apu, 2 * 37_282);
+    assert!(!apu.is_irq_asserted());
+  }
+  #[test]
+  fn clocks_length_counters_twice_per_sequence() {
+    let mut apu = setup();
+    run(&mut apu, 14_913);
+    assert_eq!(apu.peek_status() & 0x01, 0x01);
+    run(&mut apu, 29_829 - 14_913);
+    assert_eq!(apu.peek_status() & 0x01, 0x00);
+  }
+  #[test]
+  fn five_step_write_clocks_half_frame_after_delay() {
+    let mut apu = setup();
+    apu.write_register(0x4017, 0x80);
+    run(&mut apu, 4);
+    // Written on an even cycle, the sequence restarts 3 cycles later
+    apu.write_register(0x4017, 0x80);
+    run(&mut apu, 2);
+    assert_eq!(apu.peek_status() & 0x01, 0x01);
+    run(&mut apu, 1);
+    assert_eq!(apu.peek_status() & 0x01, 0x00);
+  }
+  #[test]
+  fn write_on_odd_cycle_waits_one_more_cycle() {
+    let mut apu = setup();
+    apu.write_register(0x4017, 0x80);
+    run(&mut apu, 3);
+    apu.write_register(0x4017, 0x80);
+    run(&mut apu, 3);
+    assert_eq!(apu.peek_status() & 0x01, 0x01);
+    run(&mut apu, 1);
+    assert_eq!(apu.peek_status() & 0x01, 0x00);
+  }
+}
+
+#[cfg(test)]
+mod mixer {
+  use super::*;
+  #[test]
+  fn silence_is_zero() {
+    assert_eq!(Mixer::new().mix(0, 0, 0, 0, 0), 0.0);
+  }
+  #[test]
+  fn full_scale_is_about_one() {
+    let output = Mixer::new().mix(15, 15, 15, 15, 127);
+    assert!((output - 1.0).abs() < 0.01, "{}", output);
+  }
+  #[test]
+  fn is_not_linear() {
+    let mixer = Mixer::new();
+    assert!(mixer.mix(15, 15, 0, 0, 0) < 2.0 * mixer.mix(15, 0, 0, 0, 0));
+    assert!(mixer.mix(0, 0, 15, 15, 0) < mixer.mix(0, 0, 15, 0, 0) + mixer.mix(0, 0, 0, 15, 0));
+  }
+  #[test]
+  fn weighs_triangle_over_noise() {
+    let mixer = Mixer::new();
+    assert!(mixer.mix(0, 0, 15, 0, 0) > mixer.mix(0, 0, 0, 15, 0));
+  }
+}
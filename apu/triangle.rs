@@ -0,0 +1,95 @@
+// Source: https://wiki.nesdev.com/w/index.php/APU_Triangle
+
+use apu::length_counter::LengthCounter;
+
+const SEQUENCE: [u8; 32] = [
+  15, 14, 13, 12, 11, 10, 9, 8, 7, 6, 5, 4, 3, 2, 1, 0, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12,
+  13, 14, 15,
+];
+
+pub struct Triangle {
+  step: usize,
+  period: u16, // Timer period in CPU cycles, 11 bits
+  timer: u16,
+  length: LengthCounter,
+  control: bool, // Halts the length counter and keeps reloading the linear counter
+  linear_reload_value: u8,
+  linear_counter: u8,
+  linear_reload: bool,
+}
+
+impl Triangle {
+  pub fn new() -> Triangle {
+    Triangle {
+      step: 0,
+      period: 0,
+      timer: 0,
+      length: LengthCounter::new(),
+      control: false,
+      linear_reload_value: 0,
+      linear_counter: 0,
+      linear_reload: false,
+    }
+  }
+
+  // One of the registers at $4008, $4009 is unused
+  pub fn write_register(&mut self, register: u16, data: u8) {
+    match register {
+      // CRRR RRRR
+      0 => {
+        self.control = data & 0x80 != 0;
+        self.linear_reload_value = data & 0x7F;
+        self.length.set_halted(self.control);
+      }
+      1 => {}
+      // TTTT TTTT
+      2 => self.period = (self.period & 0x0700) | data as u16,
+      // LLLL LTTT
+      _ => {
+        self.period = (self.period & 0x00FF) | ((data as u16 & 0x07) << 8);
+        self.length.load(data);
+        self.linear_reload = true;
+      }
+    }
+  }
+
+  pub fn set_enabled(&mut self, enabled: bool) {
+    self.length.set_enabled(enabled);
+  }
+
+  pub fn is_active(&self) -> bool {
+    self.length.is_active()
+  }
+
+  // Every CPU cycle. When either counter is 0 the sequence stops where it is
+  pub fn clock_timer(&mut self) {
+    if self.timer == 0 {
+      self.timer = self.period;
+      if self.linear_counter > 0 && self.length.is_active() {
+        self.step = (self.step + 1) % SEQUENCE.len();
+      }
+    } else {
+      self.timer -= 1;
+    }
+  }
+
+  pub fn clock_quarter_frame(&mut self) {
+    if self.linear_reload {
+      self.linear_counter = self.linear_reload_value;
+    } else if self.linear_counter > 0 {
+      self.linear_counter -= 1;
+    }
+    if !self.control {
+      self.linear_reload = false;
+    }
+  }
+
+  pub fn clock_half_frame(&mut self) {
+    self.length.clock();
+  }
+
+  // 0-15, a stopped triangle keeps its level rather than dropping to 0
+  pub fn get_output(&self) -> u8 {
+    SEQUENCE[self.step]
+  }
+}
@@ -0,0 +1,160 @@
+// Records the APU's output from a headless run, so sound changes can be caught by diffing WAVs
+
+mod resampler;
+mod tests;
+pub mod wav;
+
+use apu::{Apu, Channel};
+use audio::resampler::Resampler;
+use cartridge::Region;
+use nes::Nes;
+use std::error;
+use std::fmt;
+
+const CHANNELS: [Channel; 5] = [
+  Channel::Pulse1,
+  Channel::Pulse2,
+  Channel::Triangle,
+  Channel::Noise,
+  Channel::Dmc,
+];
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AudioError {
+  SampleRate(u32), // Zero, or too close to the CPU clock to resample down to
+}
+
+impl fmt::Display for AudioError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      AudioError::SampleRate(rate) => write!(f, "Unsupported sample rate of {} Hz", rate),
+    }
+  }
+}
+
+impl error::Error for AudioError {}
+
+// CPU cycles per second, the rate the APU produces a level at
+pub fn get_clock_rate(region: Region) -> f64 {
+  match region {
+    Region::Ntsc | Region::Multi => 236_250_000.0 / 11.0 / 12.0,
+    Region::Pal => 26_601_712.5 / 16.0,
+    Region::Dendy => 26_601_712.5 / 15.0,
+  }
+}
+
+// File names use these, e.g. out-pulse1.wav
+pub fn get_channel_name(channel: Channel) -> &'static str {
+  match channel {
+    Channel::Pulse1 => "pulse1",
+    Channel::Pulse2 => "pulse2",
+    Channel::Triangle => "triangle",
+    Channel::Noise => "noise",
+    Channel::Dmc => "dmc",
+  }
+}
+
+// 16-bit samples of the mix, and of each channel on its own when asked for
+pub struct Recording {
+  pub sample_rate: u32,
+  pub mix: Vec<i16>,
+  pub channels: Vec<(Channel, Vec<i16>)>,
+}
+
+impl Recording {
+  pub fn to_wav(&self) -> Vec<u8> {
+    wav::encode(&self.mix, self.sample_rate)
+  }
+}
+
+pub struct Recorder {
+  sample_rate: u32,
+  mix: (Resampler, Vec<i16>),
+  channels: Vec<(Channel, Resampler, Vec<i16>)>,
+}
+
+impl Recorder {
+  pub fn new(region: Region, sample_rate: u32, per_channel: bool) -> Result<Recorder, AudioError> {
+    let clock_rate = get_clock_rate(region);
+    if sample_rate == 0 || sample_rate as f64 >= clock_rate / 2.0 {
+      return Err(AudioError::SampleRate(sample_rate));
+    }
+    let channels = match per_channel {
+      true => CHANNELS
+        .iter()
+        .map(|channel| {
+          (
+            *channel,
+            Resampler::new(clock_rate, sample_rate),
+            Vec::new(),
+          )
+        })
+        .collect(),
+      false => Vec::new(),
+    };
+    Ok(Recorder {
+      sample_rate,
+      mix: (Resampler::new(clock_rate, sample_rate), Vec::new()),
+      channels,
+    })
+  }
+
+  // Takes the APU's output after it ran one CPU cycle
+  pub fn clock(&mut self, apu: &Apu) {
+    self.mix.0.push(apu.get_output());
+    for (channel, resampler, _) in self.channels.iter_mut() {
+      resampler.push(apu.get_solo_output(*channel));
+    }
+  }
+
+  // Converts what is ready so far, keeping memory to the 16-bit samples
+  pub fn flush(&mut self) {
+    convert(&mut self.mix.0, &mut self.mix.1);
+    for (_, resampler, samples) in self.channels.iter_mut() {
+      convert(resampler, samples);
+    }
+  }
+
+  pub fn finish(mut self) -> Recording {
+    self.flush();
+    Recording {
+      sample_rate: self.sample_rate,
+      mix: self.mix.1,
+      channels: self
+        .channels
+        .into_iter()
+        .map(|(channel, _, samples)| (channel, samples))
+        .collect(),
+    }
+  }
+}
+
+// The mixer's 0.0-1.0 spans the positive half of a 16-bit sample
+fn convert(resampler: &mut Resampler, samples: &mut Vec<i16>) {
+  samples.extend(resampler.read_samples().iter().map(|level| {
+    (level * i16::MAX as f32)
+      .round()
+      .max(0.0)
+      .min(i16::MAX as f32) as i16
+  }));
+}
+
+// Runs the console for a number of frames, recording every CPU cycle of APU output
+pub fn record(
+  nes: &mut Nes,
+  frames: u64,
+  sample_rate: u32,
+  per_channel: bool,
+) -> Result<Recording, AudioError> {
+  let mut recorder = Recorder::new(nes.get_region(), sample_rate, per_channel)?;
+  let last_frame = nes.get_ppu().get_frame() + frames;
+  while nes.get_ppu().get_frame() < last_frame {
+    let frame = nes.get_ppu().get_frame();
+    while nes.get_ppu().get_frame() == frame {
+      nes.step_cycle();
+      recorder.clock(nes.get_apu());
+    }
+    recorder.flush();
+  }
+  Ok(recorder.finish())
+}
@@ -0,0 +1,92 @@
+// Source: http://www.slack.net/~ant/bl-synth/
+// The APU output is a step function at the CPU rate that only changes now and then. Each change
+// is added to the output as a band-limited step, a windowed sinc integrated over time, so no
+// frequency above the output's Nyquist rate aliases back into the audible range
+
+use std::f64::consts::PI;
+
+const PHASES: usize = 32; // Sub-sample positions a step can start at
+const WIDTH: usize = 16; // Output samples each step is spread over, also the delay in samples
+const CUTOFF: f64 = 0.9; // Of the output Nyquist rate, the window rolls off the rest
+
+pub struct Resampler {
+  step: f64,        // Output samples per input clock
+  time: f64,        // Position of the next clock in output samples, from the start of deltas
+  level: f32,       // Input level of the last clock
+  output: f32,      // Running sum of the deltas read so far
+  kernel: Vec<f32>, // WIDTH taps for each phase, and one more phase a whole sample later
+  deltas: Vec<f32>, // Changes of the output level, still waiting for later steps to add to them
+}
+
+impl Resampler {
+  pub fn new(clock_rate: f64, sample_rate: u32) -> Resampler {
+    Resampler {
+      step: sample_rate as f64 / clock_rate,
+      time: 0.0,
+      level: 0.0,
+      output: 0.0,
+      kernel: build_kernel(),
+      deltas: vec![0.0; WIDTH],
+    }
+  }
+
+  // The input level for one clock
+  pub fn push(&mut self, level: f32) {
+    if level != self.level {
+      let delta = level - self.level;
+      let start = self.time as usize;
+      let position = (self.time - start as f64) as f32 * PHASES as f32;
+      let phase = position as usize;
+      let fraction = position - phase as f32;
+      if self.deltas.len() < start + WIDTH {
+        self.deltas.resize(start + WIDTH, 0.0);
+      }
+      // Interpolated between the two nearest phases
+      let before = &self.kernel[phase * WIDTH..(phase + 1) * WIDTH];
+      let after = &self.kernel[(phase + 1) * WIDTH..(phase + 2) * WIDTH];
+      for (tap, sample) in self.deltas[start..start + WIDTH].iter_mut().enumerate() {
+        *sample += delta * (before[tap] + (after[tap] - before[tap]) * fraction);
+      }
+      self.level = level;
+    }
+    self.time += self.step;
+  }
+
+  // Samples no later step can change any more, removed from the resampler
+  pub fn read_samples(&mut self) -> Vec<f32> {
+    let count = self.time as usize;
+    if self.deltas.len() < count + WIDTH {
+      self.deltas.resize(count + WIDTH, 0.0);
+    }
+    let mut samples = Vec::with_capacity(count);
+    for delta in self.deltas.drain(..count) {
+      self.output += delta;
+      samples.push(self.output);
+    }
+    self.time -= count as f64;
+    samples
+  }
+}
+
+// Blackman windowed sinc, each phase scaled to add up to 1 so that steps settle on their level
+fn build_kernel() -> Vec<f32> {
+  let half = (WIDTH / 2) as f64;
+  let mut kernel = Vec::with_capacity((PHASES + 1) * WIDTH);
+  for phase in 0..=PHASES {
+    let offset = phase as f64 / PHASES as f64;
+    let taps: Vec<f64> = (0..WIDTH)
+      .map(|tap| {
+        let x = tap as f64 - offset - half + 1.0;
+        let sinc = match x == 0.0 {
+          true => 1.0,
+          false => (PI * CUTOFF * x).sin() / (PI * CUTOFF * x),
+        };
+        let window = 0.42 + 0.5 * (PI * x / half).cos() + 0.08 * (2.0 * PI * x / half).cos();
+        sinc * window.max(0.0)
+      })
+      .collect();
+    let sum: f64 = taps.iter().sum();
+    kernel.extend(taps.iter().map(|tap| (tap / sum) as f32));
+  }
+  kernel
+}
@@ -0,0 +1,172 @@
+#[cfg(test)]
+use apu::Channel;
+
+#[cfg(test)]
+use audio::resampler::Resampler;
+
+#[cfg(test)]
+use audio::{get_clock_rate, record, wav, AudioError, Recorder, Recording};
+
+#[cfg(test)]
+use cartridge::Region;
+
+#[cfg(test)]
+use data_flow::ReadWrite;
+
+#[cfg(test)]
+use mapper::tests::build_cartridge;
+
+#[cfg(test)]
+use nes::Nes;
+
+#[cfg(test)]
+const CLOCK_RATE: f64 = 1_789_773.0;
+
+#[cfg(test)]
+fn resample(levels: &[f32], sample_rate: u32) -> Vec<f32> {
+  let mut resampler = Resampler::new(CLOCK_RATE, sample_rate);
+  for level in levels {
+    resampler.push(*level);
+  }
+  resampler.read_samples()
+}
+
+#[cfg(test)]
+fn setup() -> Nes {
+  let mut nes = Nes::from_cartridge(build_cartridge(0, 2, 1)).unwrap();
+  nes.reset();
+  nes
+}
+
+#[cfg(test)]
+mod resampler {
+  use super::*;
+  #[test]
+  fn produces_samples_at_the_output_rate() {
+    let samples = resample(&vec![0.0; CLOCK_RATE as usize], 44_100);
+    assert_eq!(samples.len(), 44_100);
+  }
+  #[test]
+  fn steps_settle_on_their_level() {
+    let samples = resample(&vec![0.5; 10_000], 44_100);
+    assert!(samples.len() > 200);
+    for sample in samples.iter().skip(20) {
+      assert!((sample - 0.5).abs() < 1e-4, "{}", sample);
+    }
+  }
+  #[test]
+  fn filters_out_frequencies_above_nyquist() {
+    // A square wave at half the CPU rate averages out
+    let levels: Vec<f32> = (0..20_000).map(|clock| (clock % 2) as f32).collect();
+    for sample in resample(&levels, 44_100).iter().skip(20) {
+      assert!((sample - 0.5).abs() < 0.01, "{}", sample);
+    }
+  }
+  #[test]
+  fn keeps_audible_frequencies() {
+    // A 1 kHz square wave still swings between its levels
+    let period = (CLOCK_RATE / 1_000.0) as usize;
+    let levels: Vec<f32> = (0..10 * period)
+      .map(|clock| ((clock / (period / 2)) % 2) as f32)
+      .collect();
+    let samples = resample(&levels, 44_100);
+    let highest = samples.iter().cloned().fold(0.0, f32::max);
+    let lowest = samples.iter().skip(20).cloned().fold(1.0, f32::min);
+    assert!(highest > 0.95 && highest < 1.15, "{}", highest);
+    assert!(lowest < 0.05 && lowest > -0.15, "{}", lowest);
+  }
+  #[test]
+  fn keeps_samples_later_steps_can_change() {
+    let mut resampler = Resampler::new(CLOCK_RATE, 44_100);
+    resampler.push(1.0);
+    assert!(resampler.read_samples().is_empty());
+    let mut samples = Vec::new();
+    for _ in 0..1_000 {
+      resampler.push(1.0);
+      samples.extend(resampler.read_samples());
+    }
+    assert_eq!(samples.len(), 24);
+    assert!((samples[23] - 1.0).abs() < 1e-4);
+  }
+}
+
+#[cfg(test)]
+mod wav_file {
+  use super::*;
+  #[test]
+  fn has_pcm_header() {
+    let bytes = wav::encode(&[0x0102, -2], 44_100);
+    assert_eq!(bytes.len(), 44 + 4);
+    assert_eq!(&bytes[0..4], b"RIFF");
+    assert_eq!(bytes[4..8], [40, 0, 0, 0]);
+    assert_eq!(&bytes[8..16], b"WAVEfmt ");
+    // PCM, mono, 44100 Hz, 88200 bytes per second, 2 byte blocks of 16 bits
+    assert_eq!(
+      bytes[16..36],
+      [16, 0, 0, 0, 1, 0, 1, 0, 0x44, 0xAC, 0, 0, 0x88, 0x58, 1, 0, 2, 0, 16, 0]
+    );
+    assert_eq!(&bytes[36..40], b"data");
+    assert_eq!(bytes[40..44], [4, 0, 0, 0]);
+    assert_eq!(bytes[44..], [0x02, 0x01, 0xFE, 0xFF]);
+  }
+}
+
+#[cfg(test)]
+mod recording {
+  use super::*;
+  #[test]
+  fn rejects_sample_rates_it_cannot_resample_to() {
+    assert_eq!(
+      Recorder::new(Region::Ntsc, 0, false).err(),
+      Some(AudioError::SampleRate(0))
+    );
+    assert_eq!(
+      Recorder::new(Region::Ntsc, 1_000_000, false).err(),
+      Some(AudioError::SampleRate(1_000_000))
+    );
+  }
+  #[test]
+  fn clock_rate_depends_on_region() {
+    assert_eq!(get_clock_rate(Region::Ntsc).round(), 1_789_773.0);
+    assert_eq!(get_clock_rate(Region::Pal).round(), 1_662_607.0);
+  }
+  #[test]
+  fn records_whole_frames() {
+    let mut nes = setup();
+    let recording = record(&mut nes, 2, 44_100, false).unwrap();
+    assert_eq!(nes.get_ppu().get_frame(), 2);
+    // 29781 cycles a frame, less the samples the resampler holds back
+    let expected = 2.0 * 29_781.0 * 44_100.0 / CLOCK_RATE;
+    assert!((recording.mix.len() as f64 - expected).abs() < 10.0);
+    assert!(recording.channels.is_empty());
+  }
+  #[test]
+  fn records_each_channel_on_its_own() {
+    let mut nes = setup();
+    // Pulse 1 at constant volume 15
+    let bus = nes.get_mut_bus();
+    bus.write_addr(0x4015, 0x01);
+    bus.write_addr(0x4000, 0xBF);
+    bus.write_addr(0x4002, 0xFD);
+    bus.write_addr(0x4003, 0x00);
+    let recording: Recording = record(&mut nes, 1, 44_100, true).unwrap();
+    let channels: Vec<Channel> = recording.channels.iter().map(|channel| channel.0).collect();
+    assert_eq!(
+      channels,
+      vec![
+        Channel::Pulse1,
+        Channel::Pulse2,
+        Channel::Triangle,
+        Channel::Noise,
+        Channel::Dmc
+      ]
+    );
+    let pulse_1 = &recording.channels[0].1;
+    assert_eq!(pulse_1.len(), recording.mix.len());
+    // The mixer puts one pulse at 15 at 0.149, band limiting overshoots it a little
+    let highest = *pulse_1.iter().max().unwrap();
+    assert!(highest > 4_700 && highest < 5_600, "{}", highest);
+    assert!(*pulse_1.iter().skip(20).min().unwrap() < 200);
+    assert!(recording.channels[1].1.iter().all(|sample| *sample == 0));
+  }
+}
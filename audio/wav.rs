@@ -0,0 +1,30 @@
+// Source: http://soundfile.sapp.org/doc/WaveFormat/
+
+const HEADER_SIZE: u32 = 44;
+const FORMAT_PCM: u16 = 1;
+const CHANNELS: u16 = 1;
+const BITS_PER_SAMPLE: u16 = 16;
+
+// A mono 16-bit PCM WAV file, little endian like every field of the format
+pub fn encode(samples: &[i16], sample_rate: u32) -> Vec<u8> {
+  let block_align = CHANNELS * BITS_PER_SAMPLE / 8;
+  let data_size = samples.len() as u32 * block_align as u32;
+  let mut bytes = Vec::with_capacity((HEADER_SIZE + data_size) as usize);
+  bytes.extend_from_slice(b"RIFF");
+  bytes.extend_from_slice(&(HEADER_SIZE - 8 + data_size).to_le_bytes());
+  bytes.extend_from_slice(b"WAVE");
+  bytes.extend_from_slice(b"fmt ");
+  bytes.extend_from_slice(&16u32.to_le_bytes());
+  bytes.extend_from_slice(&FORMAT_PCM.to_le_bytes());
+  bytes.extend_from_slice(&CHANNELS.to_le_bytes());
+  bytes.extend_from_slice(&sample_rate.to_le_bytes());
+  bytes.extend_from_slice(&(sample_rate * block_align as u32).to_le_bytes());
+  bytes.extend_from_slice(&block_align.to_le_bytes());
+  bytes.extend_from_slice(&BITS_PER_SAMPLE.to_le_bytes());
+  bytes.extend_from_slice(b"data");
+  bytes.extend_from_slice(&data_size.to_le_bytes());
+  for sample in samples {
+    bytes.extend_from_slice(&sample.to_le_bytes());
+  }
+  bytes
+}
@@ -1,39 +1,373 @@
+// Source: https://wiki.nesdev.com/w/index.php/CPU_memory_map
+
+mod tests;
+
+use apu::Apu;
+use cartridge::Region;
+use connection::Connection;
 use data_flow::{ReadRange, ReadWrite};
+use device::{DeviceError, Peripheral};
+use mapper::{Mapper, CARTRIDGE_ADDR};
+use ppu::Ppu;
+
+use std::error;
+use std::fmt;
+
+const RAM_SIZE: usize = 2 * 1024; // 2 KB
+const FLAT_MEMORY_SIZE: usize = 64 * 1024; // 64 KB
+const IO_REGISTER_COUNT: usize = 0x18;
+const OAM_DMA_ADDR: u16 = 0x4014;
+const IO_REGISTERS_ADDR: u16 = 0x4000;
 
-const MAX_MEMORY: usize = 64 * 1024; // 64 KB
+pub enum MemoryMap {
+  Nes,  // 2 KB RAM, PPU and APU/IO registers, cartridge space
+  Flat, // 64 KB of RAM for generic 6502 programs
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Access {
+  Read,
+  Write,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum BusError {
+  DeviceOutOfRange { start: u16, size: usize }, // Device would extend past 0xFFFF or is empty
+  Overlap { start: u16, end: u16 },             // Range taken by a device of the same priority
+}
+
+impl fmt::Display for BusError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      BusError::DeviceOutOfRange { start, size } => {
+        write!(
+          f,
+          "Device of {:#X} bytes does not fit at {:#06X}",
+          size, start
+        )
+      }
+      BusError::Overlap { start, end } => {
+        write!(
+          f,
+          "Device overlaps {:#06X}-{:#06X} at the same priority",
+          start, end
+        )
+      }
+    }
+  }
+}
+
+impl error::Error for BusError {}
+
+// Receives the failed access, the CPU address and the reason
+pub type ErrorHandler = Box<dyn Fn(Access, u16, &DeviceError)>;
+
+// Receives every CPU access with its address and the data read or written
+pub type AccessHandler = Box<dyn Fn(Access, u16, u8)>;
+
+struct Registration {
+  priority: u8,
+  connection: Connection<Box<dyn Peripheral>>,
+}
 
 pub struct Bus {
-  ram: [u8; MAX_MEMORY],
+  memory_map: MemoryMap,
+  devices: Vec<Registration>, // Sorted by priority, highest first
+  error_handler: Option<ErrorHandler>,
+  access_handler: Option<AccessHandler>,
+  ram: Box<[u8]>,
+  ppu: Ppu,                              // Registers at 0x2000-0x2007
+  apu: Apu,                              // Registers at 0x4000-0x4013, 0x4015 and 0x4017
+  io_registers: [u8; IO_REGISTER_COUNT], // 0x4000-0x4017
+  oam_dma_page: Option<u8>,              // Requested by a write to 0x4014, run by the CPU
+  cartridge: Option<Connection<Box<dyn Mapper>>>,
 }
 
 impl Bus {
   pub fn new() -> Bus {
     Bus {
-      ram: [0; MAX_MEMORY],
+      memory_map: MemoryMap::Nes,
+      devices: Vec::new(),
+      error_handler: None,
+      access_handler: None,
+      ram: vec![0; RAM_SIZE].into_boxed_slice(),
+      ppu: Ppu::new(),
+      apu: Apu::new(),
+      io_registers: [0; IO_REGISTER_COUNT],
+      oam_dma_page: None,
+      cartridge: None,
     }
   }
-}
 
-impl ReadWrite for Bus {
-  fn write_addr(&mut self, addr: u16, data: u8) {
+  pub fn flat() -> Bus {
+    Bus {
+      memory_map: MemoryMap::Flat,
+      ram: vec![0; FLAT_MEMORY_SIZE].into_boxed_slice(),
+      ..Bus::new()
+    }
+  }
+
+  // Attach a device at addr_start. Devices take precedence over the built-in memory map and,
+  // where they overlap each other, the one with the higher priority answers
+  pub fn connect(
+    &mut self,
+    device: Box<dyn Peripheral>,
+    addr_start: u16,
+    priority: u8,
+  ) -> Result<(), BusError> {
+    let size = device.mem_size();
+    if size == 0 || addr_start as usize + size > 0x10000 {
+      return Err(BusError::DeviceOutOfRange {
+        start: addr_start,
+        size,
+      });
+    }
+
+    let connection = Connection::new(device, addr_start);
+    let overlap = self.devices.iter().find(|registration| {
+      registration.priority == priority
+        && registration.connection.get_start() <= connection.get_end()
+        && connection.get_start() <= registration.connection.get_end()
+    });
+    if let Some(registration) = overlap {
+      return Err(BusError::Overlap {
+        start: registration.connection.get_start(),
+        end: registration.connection.get_end(),
+      });
+    }
+
+    let index = self
+      .devices
+      .iter()
+      .position(|registration| registration.priority < priority)
+      .unwrap_or(self.devices.len());
+    self.devices.insert(
+      index,
+      Registration {
+        priority,
+        connection,
+      },
+    );
+    Ok(())
+  }
+
+  // Called with every CPU access that fails, and why
+  pub fn set_error_handler(&mut self, handler: ErrorHandler) {
+    self.error_handler = Some(handler);
+  }
+
+  fn report_error(&self, access: Access, addr: u16, error: &DeviceError) {
+    if let Some(ref handler) = self.error_handler {
+      handler(access, addr, error);
+    }
+  }
+
+  // Called with every read and write the CPU makes, in the order it makes them
+  pub fn set_access_handler(&mut self, handler: AccessHandler) {
+    self.access_handler = Some(handler);
+  }
+
+  fn report_access(&self, access: Access, addr: u16, data: u8) {
+    if let Some(ref handler) = self.access_handler {
+      handler(access, addr, data);
+    }
+  }
+
+  pub fn try_write(&mut self, addr: u16, data: u8) -> Result<(), DeviceError> {
+    let device = self
+      .devices
+      .iter_mut()
+      .find(|registration| registration.connection.is_addr_in_range(addr));
+    if let Some(registration) = device {
+      return registration.connection.write_addr(addr, data);
+    }
+
+    if let MemoryMap::Flat = self.memory_map {
+      self.ram[addr as usize] = data;
+      return Ok(());
+    }
+
     match addr {
-      0x0000..=0xFFFF => {
-        self.ram[addr as usize] = data;
+      // Internal RAM, mirrored four times
+      0x0000..=0x1FFF => self.ram[addr as usize & (RAM_SIZE - 1)] = data,
+      // PPU registers, mirrored every 8 bytes
+      0x2000..=0x3FFF => {
+        let mapper = as_mapper(&mut self.cartridge);
+        self.ppu.write_register(addr, data, mapper)
       }
+      // APU and I/O registers, the write-only ones read back what was last written
+      0x4000..=0x4017 => {
+        self.io_registers[(addr - IO_REGISTERS_ADDR) as usize] = data;
+        match addr {
+          OAM_DMA_ADDR => self.oam_dma_page = Some(data),
+          _ => self.apu.write_register(addr, data),
+        }
+      }
+      // APU and I/O test mode, disabled on retail units
+      0x4018..=0x401F => return Err(DeviceError::Unmapped(addr)),
+      0x4020..=0xFFFF => match self.cartridge {
+        Some(ref mut cartridge) => return cartridge.write_addr(addr, data),
+        None => return Err(DeviceError::Unmapped(addr)),
+      },
     }
+    Ok(())
   }
 
-  fn read_addr(&self, addr: u16) -> u8 {
+  pub fn try_read(&self, addr: u16) -> Result<u8, DeviceError> {
+    let device = self
+      .devices
+      .iter()
+      .find(|registration| registration.connection.is_addr_in_range(addr));
+    if let Some(registration) = device {
+      return registration.connection.read_addr(addr);
+    }
+
+    if let MemoryMap::Flat = self.memory_map {
+      return Ok(self.ram[addr as usize]);
+    }
+
     match addr {
-      0x0000..=0xFFFF => self.ram[addr as usize],
+      0x0000..=0x1FFF => Ok(self.ram[addr as usize & (RAM_SIZE - 1)]),
+      0x2000..=0x3FFF => Ok(self.ppu.peek_register(addr)),
+      0x4015 => Ok(self.apu.peek_status()),
+      0x4000..=0x4017 => Ok(self.io_registers[(addr - IO_REGISTERS_ADDR) as usize]),
+      0x4018..=0x401F => Err(DeviceError::Unmapped(addr)),
+      0x4020..=0xFFFF => match self.cartridge {
+        Some(ref cartridge) => cartridge.read_addr(addr),
+        None => Err(DeviceError::Unmapped(addr)),
+      },
     }
   }
+
+  // Connect a cartridge to the cartridge space, 0x4020-0xFFFF
+  pub fn insert_cartridge(&mut self, mapper: Box<dyn Mapper>) {
+    self.cartridge = Some(Connection::new(mapper, CARTRIDGE_ADDR));
+  }
+
+  // Advance devices that count CPU cycles
+  pub fn clock(&mut self) {
+    self.apu.clock();
+    if let Some(mapper) = self.get_mut_mapper() {
+      mapper.cpu_clock();
+    }
+  }
+
+  // One PPU dot, the ratio to CPU cycles depends on the region
+  pub fn clock_ppu(&mut self) {
+    let mapper = as_mapper(&mut self.cartridge);
+    self.ppu.clock(mapper);
+  }
+
+  // State of the shared, active low IRQ line, any source can hold it down
+  pub fn is_irq_asserted(&self) -> bool {
+    let mapper = match self.get_mapper() {
+      Some(mapper) => mapper.is_irq_asserted(),
+      None => false,
+    };
+    mapper || self.apu.is_irq_asserted()
+  }
+
+  pub fn get_mapper(&self) -> Option<&dyn Mapper> {
+    self
+      .cartridge
+      .as_ref()
+      .map(|cartridge| &**cartridge.get_device())
+  }
+
+  pub fn get_mut_mapper(&mut self) -> Option<&mut dyn Mapper> {
+    as_mapper(&mut self.cartridge)
+  }
+
+  pub fn get_ppu(&self) -> &Ppu {
+    &self.ppu
+  }
+
+  pub fn get_apu(&self) -> &Apu {
+    &self.apu
+  }
+
+  // Frame length and APU timing differ between regions
+  pub fn set_region(&mut self, region: Region) {
+    self.ppu.set_region(region);
+    self.apu.set_region(region);
+  }
+
+  // The address the DMC wants a sample byte from, the CPU halts on its next read to fetch it
+  pub fn get_dmc_dma_addr(&self) -> Option<u16> {
+    self.apu.get_dma_addr()
+  }
+
+  pub fn load_dmc_sample(&mut self, data: u8) {
+    self.apu.load_sample(data);
+  }
+
+  pub fn is_dma_get_cycle(&self) -> bool {
+    self.apu.is_dma_get_cycle()
+  }
+
+  // The page a write to 0x4014 asked to copy to OAM, the CPU halts on its next read to copy it
+  pub fn take_oam_dma_page(&mut self) -> Option<u8> {
+    self.oam_dma_page.take()
+  }
+
+  // State of the NMI line, only the PPU drives it
+  pub fn is_nmi_asserted(&self) -> bool {
+    self.ppu.is_nmi_asserted()
+  }
+
+  // A CPU read. Unlike read_addr, registers see it and can react, e.g. PPUSTATUS clears vblank
+  // and the APU status acknowledges the frame interrupt
+  pub fn read(&mut self, addr: u16) -> u8 {
+    let is_device = self
+      .devices
+      .iter()
+      .any(|registration| registration.connection.is_addr_in_range(addr));
+    let data = match self.memory_map {
+      MemoryMap::Nes if !is_device && (0x2000..=0x3FFF).contains(&addr) => {
+        let mapper = as_mapper(&mut self.cartridge);
+        self.ppu.read_register(addr, mapper)
+      }
+      MemoryMap::Nes if !is_device && addr == 0x4015 => self.apu.read_status(),
+      _ => self.read_addr(addr),
+    };
+    self.report_access(Access::Read, addr, data);
+    data
+  }
+
+  // A CPU write, as opposed to poking memory with write_addr
+  pub fn write(&mut self, addr: u16, data: u8) {
+    self.write_addr(addr, data);
+    self.report_access(Access::Write, addr, data);
+  }
+}
+
+fn as_mapper(cartridge: &mut Option<Connection<Box<dyn Mapper>>>) -> Option<&mut dyn Mapper> {
+  match *cartridge {
+    Some(ref mut cartridge) => Some(&mut **cartridge.get_mut_device()),
+    None => None,
+  }
+}
+
+impl ReadWrite for Bus {
+  fn write_addr(&mut self, addr: u16, data: u8) {
+    if let Err(error) = self.try_write(addr, data) {
+      self.report_error(Access::Write, addr, &error);
+    }
+  }
+
+  fn read_addr(&self, addr: u16) -> u8 {
+    self.try_read(addr).unwrap_or_else(|error| {
+      self.report_error(Access::Read, addr, &error);
+      0
+    })
+  }
 }
 
 impl ReadRange for Bus {
-  fn read_range(&self, addr_start: u16, addr_end: u16) -> &[u8] {
-    let start = addr_start as usize;
-    let end = addr_end as usize;
-    &self.ram[start..=end]
+  fn read_range(&self, addr_start: u16, addr_end: u16) -> Vec<u8> {
+    // Inspecting memory is not an access, failures are not reported
+    (addr_start..=addr_end)
+      .map(|addr| self.try_read(addr).unwrap_or(0))
+      .collect()
   }
 }
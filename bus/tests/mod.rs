@@ -0,0 +1,451 @@
+#[cfg(test)]
+use bus::{Access, Bus, BusError};
+
+#[cfg(test)]
+use device::{Device, DeviceError, ExactSize, Readable, Writable};
+
+#[cfg(test)]
+use std::cell::RefCell;
+
+#[cfg(test)]
+use std::rc::Rc;
+
+#[cfg(test)]
+use data_flow::{ReadRange, ReadWrite};
+
+#[cfg(test)]
+use mapper::from_cartridge;
+
+#[cfg(test)]
+use mapper::tests::build_cartridge;
+
+#[cfg(test)]
+mod ram {
+  use super::*;
+  #[test]
+  fn mirrors_every_2kb() {
+    let mut bus = Bus::new();
+    bus.write_addr(0x0012, 0xAB);
+    for base in [0x0000, 0x0800, 0x1000, 0x1800].iter() {
+      assert_eq!(bus.read_addr(base + 0x0012), 0xAB);
+    }
+  }
+  #[test]
+  fn writes_through_mirror_reach_ram() {
+    let mut bus = Bus::new();
+    bus.write_addr(0x1FFF, 0xCD);
+    assert_eq!(bus.read_addr(0x07FF), 0xCD);
+    assert_eq!(bus.read_addr(0x0FFF), 0xCD);
+    assert_eq!(bus.read_addr(0x17FF), 0xCD);
+  }
+  #[test]
+  fn mirror_boundaries() {
+    let mut bus = Bus::new();
+    bus.write_addr(0x07FF, 0x01);
+    bus.write_addr(0x0800, 0x02);
+    assert_eq!(bus.read_addr(0x07FF), 0x01);
+    assert_eq!(bus.read_addr(0x0000), 0x02);
+    assert_eq!(bus.read_addr(0x0FFF), 0x01);
+    assert_eq!(bus.read_addr(0x1000), 0x02);
+    assert_eq!(bus.read_addr(0x17FF), 0x01);
+    assert_eq!(bus.read_addr(0x1800), 0x02);
+    assert_eq!(bus.read_addr(0x1FFF), 0x01);
+  }
+  #[test]
+  fn ends_at_0x1fff() {
+    let mut bus = Bus::new();
+    bus.write_addr(0x0000, 0xEE);
+    assert_ne!(bus.read_addr(0x2000), 0xEE);
+  }
+}
+
+#[cfg(test)]
+mod ppu_registers {
+  use super::*;
+  #[test]
+  fn mirror_every_8_bytes() {
+    let mut bus = Bus::new();
+    for base in (0x2000..0x4000).step_by(8) {
+      let tag = (base >> 3) as u8;
+      // OAMADDR and OAMDATA through the mirror, read back through the first copy. Sprite Y
+      // bytes are used as every bit of them is stored
+      let oam_addr = tag & 0xFC;
+      bus.write_addr(base + 3, oam_addr);
+      bus.write_addr(base + 4, tag);
+      bus.write_addr(0x2003, oam_addr);
+      assert_eq!(bus.read_addr(0x2004), tag);
+      bus.write_addr(0x2003, oam_addr);
+      assert_eq!(bus.read_addr(base + 4), tag);
+    }
+  }
+  #[test]
+  fn mirror_boundaries() {
+    let mut bus = Bus::new();
+    // 0x2007 is PPUDATA, 0x2008 is PPUCTRL again
+    bus.write_addr(0x2006, 0x3F);
+    bus.write_addr(0x2006, 0x00);
+    bus.write_addr(0x2007, 0x11);
+    bus.write_addr(0x2008, 0x00);
+    bus.write_addr(0x3FFE, 0x3F);
+    bus.write_addr(0x3FFE, 0x00);
+    assert_eq!(bus.read(0x3FFF), 0x11);
+    assert_eq!(bus.get_ppu().get_vram_addr(), 0x3F01);
+    bus.write_addr(0x3FF8, 0x04);
+    bus.write_addr(0x200E, 0x3F);
+    bus.write_addr(0x200E, 0x00);
+    bus.read(0x200F);
+    assert_eq!(bus.get_ppu().get_vram_addr(), 0x3F20);
+  }
+  #[test]
+  fn reads_reach_ppu_through_mirrors() {
+    let mut bus = Bus::new();
+    bus.write_addr(0x2006, 0x3F);
+    assert_eq!(bus.read(0x3FFA), 0x3F & 0x1F);
+    // The PPUSTATUS read reset the write toggle, so this is a first write again
+    bus.write_addr(0x3FFE, 0x21);
+    bus.write_addr(0x3FFE, 0x00);
+    assert_eq!(bus.get_ppu().get_vram_addr(), 0x2100);
+  }
+  #[test]
+  fn do_not_reach_ram_or_io() {
+    let mut bus = Bus::new();
+    bus.write_addr(0x3FFF, 0x33);
+    assert_eq!(bus.read_addr(0x1FFF), 0x00);
+    assert_eq!(bus.read_addr(0x4000), 0x00);
+    assert_eq!(bus.read_addr(0x4007), 0x00);
+  }
+}
+
+#[cfg(test)]
+mod io_registers {
+  use super::*;
+  #[test]
+  fn are_not_mirrored() {
+    let mut bus = Bus::new();
+    for addr in 0x4000..0x4018 {
+      bus.write_addr(addr, addr as u8);
+    }
+    // Except for the APU status, which reports rather than stores
+    for addr in (0x4000..0x4018).filter(|addr| *addr != 0x4015) {
+      assert_eq!(bus.read_addr(addr), addr as u8);
+    }
+  }
+  #[test]
+  fn reach_the_apu() {
+    let mut bus = Bus::new();
+    bus.write_addr(0x4015, 0x01);
+    bus.write_addr(0x4003, 0x08);
+    assert_eq!(bus.read_addr(0x4015), 0x01);
+    bus.write_addr(0x4015, 0x00);
+    assert_eq!(bus.read_addr(0x4015), 0x00);
+  }
+  #[test]
+  fn request_oam_dma() {
+    let mut bus = Bus::new();
+    assert_eq!(bus.take_oam_dma_page(), None);
+    bus.write_addr(0x4014, 0x02);
+    assert_eq!(bus.take_oam_dma_page(), Some(0x02));
+    assert_eq!(bus.take_oam_dma_page(), None);
+  }
+  #[test]
+  fn cpu_status_read_acknowledges_frame_interrupt() {
+    let mut bus = Bus::new();
+    for _ in 0..29_830 {
+      bus.clock();
+    }
+    assert_eq!(bus.read_addr(0x4015), 0x40);
+    assert_eq!(bus.read(0x4015), 0x40);
+    assert_eq!(bus.read_addr(0x4015), 0x00);
+  }
+  #[test]
+  fn test_mode_range_is_unmapped() {
+    let mut bus = Bus::new();
+    for addr in 0x4018..0x4020 {
+      bus.write_addr(addr, 0xFF);
+      assert_eq!(bus.read_addr(addr), 0x00);
+    }
+    assert_eq!(bus.read_addr(0x4017), 0x00);
+  }
+}
+
+#[cfg(test)]
+mod cartridge_space {
+  use super::*;
+  #[test]
+  fn is_unmapped_without_cartridge() {
+    let mut bus = Bus::new();
+    bus.write_addr(0x8000, 0xAB);
+    assert_eq!(bus.read_addr(0x8000), 0x00);
+    assert_eq!(bus.read_addr(0xFFFF), 0x00);
+  }
+  #[test]
+  fn starts_at_0x4020() {
+    let mut bus = Bus::new();
+    bus.insert_cartridge(from_cartridge(build_cartridge(0, 2, 1)).unwrap());
+    bus.write_addr(0x6000, 0xAB);
+    assert_eq!(bus.read_addr(0x6000), 0xAB);
+    assert_eq!(bus.read_addr(0x7FFF), 0x00);
+    assert_eq!(bus.read_addr(0x8000), 0x00);
+    assert_eq!(bus.read_addr(0xC000), 0x01);
+    assert_eq!(bus.read_addr(0xFFFF), 0x01);
+  }
+}
+
+#[test]
+fn flat_memory_map_is_all_ram() {
+  let mut bus = Bus::flat();
+  for addr in [
+    0x0000, 0x0800, 0x2000, 0x2008, 0x4000, 0x4018, 0x4020, 0x8000, 0xFFFF,
+  ]
+  .iter()
+  {
+    bus.write_addr(*addr, (*addr >> 8) as u8 ^ 0x5A);
+  }
+  for addr in [
+    0x0000, 0x0800, 0x2000, 0x2008, 0x4000, 0x4018, 0x4020, 0x8000, 0xFFFF,
+  ]
+  .iter()
+  {
+    assert_eq!(bus.read_addr(*addr), (*addr >> 8) as u8 ^ 0x5A);
+  }
+}
+
+// Write-only port that collects the characters a test program prints
+#[cfg(test)]
+struct DebugPort {
+  output: Rc<RefCell<String>>,
+}
+
+#[cfg(test)]
+impl ExactSize for DebugPort {
+  fn mem_size(&self) -> usize {
+    1
+  }
+}
+
+#[cfg(test)]
+impl Readable for DebugPort {
+  fn read_addr(&self, _addr: usize) -> Result<u8, DeviceError> {
+    Err(DeviceError::Fault("debug port is write-only".to_string()))
+  }
+}
+
+#[cfg(test)]
+impl Writable for DebugPort {
+  fn write_addr(&mut self, _addr: usize, data: u8) -> Result<(), DeviceError> {
+    self.output.borrow_mut().push(data as char);
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+fn ram(size: usize, fill: u8) -> Box<Device> {
+  Box::new(Device::new(vec![fill; size].into_boxed_slice()))
+}
+
+#[cfg(test)]
+mod devices {
+  use super::*;
+  #[test]
+  fn debug_port_receives_writes() {
+    let output = Rc::new(RefCell::new(String::new()));
+    let mut bus = Bus::new();
+    let port = DebugPort {
+      output: output.clone(),
+    };
+    bus.connect(Box::new(port), 0x4018, 0).unwrap();
+    for byte in b"OK\n".iter() {
+      bus.write_addr(0x4018, *byte);
+    }
+    assert_eq!(*output.borrow(), "OK\n");
+  }
+  #[test]
+  fn are_addressed_relative_to_their_start() {
+    let mut bus = Bus::new();
+    bus.connect(ram(0x10, 0), 0x5000, 0).unwrap();
+    bus.write_addr(0x5003, 0xAB);
+    assert_eq!(bus.read_addr(0x5003), 0xAB);
+    assert_eq!(bus.read_addr(0x500F), 0x00);
+    assert_eq!(bus.read_addr(0x5010), 0x00);
+  }
+  #[test]
+  fn take_precedence_over_memory_map() {
+    let mut bus = Bus::new();
+    bus.write_addr(0x0100, 0x11);
+    bus.connect(ram(0x100, 0x22), 0x0100, 0).unwrap();
+    assert_eq!(bus.read_addr(0x0100), 0x22);
+    assert_eq!(bus.read_addr(0x0900), 0x11);
+  }
+  #[test]
+  fn higher_priority_answers_overlapping_range() {
+    let mut bus = Bus::new();
+    bus.connect(ram(0x100, 0x11), 0x6000, 0).unwrap();
+    bus.connect(ram(0x10, 0x22), 0x6080, 1).unwrap();
+    assert_eq!(bus.read_addr(0x607F), 0x11);
+    assert_eq!(bus.read_addr(0x6080), 0x22);
+    assert_eq!(bus.read_addr(0x608F), 0x22);
+    assert_eq!(bus.read_addr(0x6090), 0x11);
+  }
+  #[test]
+  fn rejects_overlap_at_same_priority() {
+    let mut bus = Bus::new();
+    bus.connect(ram(0x100, 0), 0x6000, 0).unwrap();
+    let result = bus.connect(ram(0x10, 0), 0x60F0, 0);
+    assert_eq!(
+      result,
+      Err(BusError::Overlap {
+        start: 0x6000,
+        end: 0x60FF
+      })
+    );
+    assert_eq!(bus.connect(ram(0x10, 0), 0x6100, 0), Ok(()));
+  }
+  #[test]
+  fn rejects_device_past_end_of_address_space() {
+    let mut bus = Bus::new();
+    let result = bus.connect(ram(0x10, 0), 0xFFF8, 0);
+    assert_eq!(
+      result,
+      Err(BusError::DeviceOutOfRange {
+        start: 0xFFF8,
+        size: 0x10
+      })
+    );
+    assert_eq!(bus.connect(ram(0x10, 0), 0xFFF0, 0), Ok(()));
+  }
+}
+
+#[cfg(test)]
+mod errors {
+  use super::*;
+  type ErrorLog = Rc<RefCell<Vec<(Access, u16, DeviceError)>>>;
+  fn setup() -> (Bus, ErrorLog) {
+    let errors = Rc::new(RefCell::new(Vec::new()));
+    let log = errors.clone();
+    let mut bus = Bus::new();
+    bus.set_error_handler(Box::new(move |access, addr, error| {
+      log.borrow_mut().push((access, addr, error.clone()))
+    }));
+    (bus, errors)
+  }
+  #[test]
+  fn test_mode_range_is_unmapped() {
+    let (mut bus, errors) = setup();
+    bus.read_addr(0x4018);
+    bus.write_addr(0x401F, 0);
+    assert_eq!(
+      *errors.borrow(),
+      vec![
+        (Access::Read, 0x4018, DeviceError::Unmapped(0x4018)),
+        (Access::Write, 0x401F, DeviceError::Unmapped(0x401F)),
+      ]
+    );
+  }
+  #[test]
+  fn empty_cartridge_space_is_unmapped() {
+    let (mut bus, errors) = setup();
+    bus.read_addr(0x8000);
+    bus.write_addr(0x4020, 0);
+    assert_eq!(
+      *errors.borrow(),
+      vec![
+        (Access::Read, 0x8000, DeviceError::Unmapped(0x8000)),
+        (Access::Write, 0x4020, DeviceError::Unmapped(0x4020)),
+      ]
+    );
+  }
+  #[test]
+  fn reads_mapper_does_not_answer_are_unmapped() {
+    let (mut bus, errors) = setup();
+    bus.insert_cartridge(from_cartridge(build_cartridge(0, 2, 1)).unwrap());
+    bus.read_addr(0x5000);
+    bus.read_addr(0x8000);
+    assert_eq!(
+      *errors.borrow(),
+      vec![(Access::Read, 0x5000, DeviceError::Unmapped(0x5000))]
+    );
+  }
+  #[test]
+  fn device_fault_is_reported() {
+    let (mut bus, errors) = setup();
+    let port = DebugPort {
+      output: Rc::new(RefCell::new(String::new())),
+    };
+    bus.connect(Box::new(port), 0x4018, 0).unwrap();
+    bus.write_addr(0x4018, b'A');
+    bus.read_addr(0x4018);
+    let fault = DeviceError::Fault("debug port is write-only".to_string());
+    assert_eq!(*errors.borrow(), vec![(Access::Read, 0x4018, fault)]);
+  }
+  #[test]
+  fn read_only_write_reports_device_offset() {
+    let (mut bus, errors) = setup();
+    let rom = Device::read_only(vec![0xEA; 0x100].into_boxed_slice());
+    bus.connect(Box::new(rom), 0x5000, 0).unwrap();
+    bus.write_addr(0x5010, 0);
+    assert_eq!(bus.read_addr(0x5010), 0xEA);
+    assert_eq!(
+      *errors.borrow(),
+      vec![(Access::Write, 0x5010, DeviceError::ReadOnly(0x10))]
+    );
+  }
+  #[test]
+  fn try_read_returns_error_without_reporting() {
+    let (bus, errors) = setup();
+    assert_eq!(bus.try_read(0x4018), Err(DeviceError::Unmapped(0x4018)));
+    assert_eq!(bus.read_range(0x4016, 0x4019), vec![0, 0, 0, 0]);
+    assert!(errors.borrow().is_empty());
+  }
+  #[test]
+  fn mapped_memory_does_not_fail() {
+    let (mut bus, errors) = setup();
+    bus.write_addr(0x0000, 0);
+    bus.read_addr(0x2002);
+    bus.read_addr(0x4016);
+    assert!(errors.borrow().is_empty());
+  }
+  #[test]
+  fn out_of_range_keeps_full_device_size() {
+    let device = Device::new(vec![0; 0x10000].into_boxed_slice());
+    assert_eq!(
+      device.read_addr(0x10000),
+      Err(DeviceError::OutOfRange {
+        addr: 0x10000,
+        size: 0x10000
+      })
+    );
+  }
+}
+
+#[cfg(test)]
+mod accesses {
+  use super::*;
+  type AccessLog = Rc<RefCell<Vec<(Access, u16, u8)>>>;
+  fn setup() -> (Bus, AccessLog) {
+    let accesses = Rc::new(RefCell::new(Vec::new()));
+    let log = accesses.clone();
+    let mut bus = Bus::flat();
+    bus.set_access_handler(Box::new(move |access, addr, data| {
+      log.borrow_mut().push((access, addr, data))
+    }));
+    (bus, accesses)
+  }
+  #[test]
+  fn reports_cpu_reads_and_writes_in_order() {
+    let (mut bus, accesses) = setup();
+    bus.write(0x1234, 0x56);
+    bus.read(0x1234);
+    assert_eq!(
+      *accesses.borrow(),
+      vec![(Access::Write, 0x1234, 0x56), (Access::Read, 0x1234, 0x56)]
+    );
+  }
+  #[test]
+  fn ignores_inspecting_and_poking_memory() {
+    let (mut bus, accesses) = setup();
+    bus.write_addr(0x1234, 0x56);
+    bus.read_addr(0x1234);
+    bus.read_range(0x1234, 0x1235);
+    assert!(accesses.borrow().is_empty());
+  }
+}
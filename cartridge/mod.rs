@@ -0,0 +1,354 @@
+// Source: https://wiki.nesdev.com/w/index.php/INES
+// Source: https://wiki.nesdev.com/w/index.php/NES_2.0
+
+mod tests;
+
+use std::error;
+use std::fmt;
+use std::fs::File;
+use std::io::prelude::*;
+
+const HEADER_SIZE: usize = 16;
+const TRAINER_SIZE: usize = 512;
+const PRG_ROM_BANK_SIZE: usize = 16 * 1024; // 16 KB
+const CHR_ROM_BANK_SIZE: usize = 8 * 1024; // 8 KB
+const INES_PRG_RAM_UNIT: usize = 8 * 1024; // 8 KB
+const INES_CHR_RAM_SIZE: usize = 8 * 1024; // 8 KB
+const MAGIC: [u8; 4] = [0x4E, 0x45, 0x53, 0x1A]; // "NES" followed by MS-DOS EOF
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+  INes,
+  Nes2,
+}
+
+impl fmt::Display for Format {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Format::INes => write!(f, "iNES"),
+      Format::Nes2 => write!(f, "NES 2.0"),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mirroring {
+  Horizontal,        // Vertical arrangement of nametables, CIRAM A10 = PPU A11
+  Vertical,          // Horizontal arrangement of nametables, CIRAM A10 = PPU A10
+  FourScreen,        // Cartridge provides the extra 2 KB of nametable memory
+  SingleScreenLower, // All nametables map to the first 1 KB of CIRAM
+  SingleScreenUpper, // All nametables map to the second 1 KB of CIRAM
+}
+
+impl fmt::Display for Mirroring {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Mirroring::Horizontal => write!(f, "Horizontal"),
+      Mirroring::Vertical => write!(f, "Vertical"),
+      Mirroring::FourScreen => write!(f, "Four Screen"),
+      Mirroring::SingleScreenLower => write!(f, "Single Screen (lower)"),
+      Mirroring::SingleScreenUpper => write!(f, "Single Screen (upper)"),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Region {
+  Ntsc,  // RP2C02
+  Pal,   // RP2C07
+  Multi, // Runs on either
+  Dendy, // UMC 6527P
+}
+
+impl fmt::Display for Region {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Region::Ntsc => write!(f, "NTSC"),
+      Region::Pal => write!(f, "PAL"),
+      Region::Multi => write!(f, "Multi-region"),
+      Region::Dendy => write!(f, "Dendy"),
+    }
+  }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CartridgeError {
+  Io(String),             // The file could not be read
+  InvalidMagic,           // File does not start with "NES<EOF>"
+  TruncatedHeader(usize), // Fewer than 16 bytes available
+  Truncated {
+    // Header describes more data than the file holds
+    expected: usize,
+    actual: usize,
+  },
+  MissingPrgRom, // Header declares no PRG ROM
+  InvalidRomSize {
+    // NES 2.0 exponent-multiplier size does not fit in memory
+    exponent: u8,
+    multiplier: u8,
+  },
+}
+
+impl fmt::Display for CartridgeError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      CartridgeError::Io(ref message) => write!(f, "Unable to read cartridge: {}", message),
+      CartridgeError::InvalidMagic => write!(f, "Not an iNES file: missing \"NES\\x1A\" signature"),
+      CartridgeError::TruncatedHeader(len) => {
+        write!(
+          f,
+          "Header is truncated: expected {} bytes, found {}",
+          HEADER_SIZE, len
+        )
+      }
+      CartridgeError::Truncated { expected, actual } => write!(
+        f,
+        "Cartridge is truncated: header describes {} bytes, file holds {}",
+        expected, actual
+      ),
+      CartridgeError::MissingPrgRom => write!(f, "Header declares no PRG ROM"),
+      CartridgeError::InvalidRomSize {
+        exponent,
+        multiplier,
+      } => write!(
+        f,
+        "ROM size 2^{} * {} is too large",
+        exponent,
+        multiplier * 2 + 1
+      ),
+    }
+  }
+}
+
+impl error::Error for CartridgeError {}
+
+pub struct Cartridge {
+  format: Format,
+  mapper: u16,
+  submapper: u8,
+  mirroring: Mirroring,
+  battery: bool,
+  region: Region,
+  trainer: Option<Vec<u8>>,
+  prg_rom: Vec<u8>,
+  chr_rom: Vec<u8>,
+  prg_ram_size: usize,
+  prg_nvram_size: usize,
+  chr_ram_size: usize,
+  chr_nvram_size: usize,
+}
+
+impl Cartridge {
+  pub fn from_file(filename: &str) -> Result<Cartridge, CartridgeError> {
+    let mut file = File::open(filename).map_err(|err| CartridgeError::Io(err.to_string()))?;
+    let mut data = Vec::new();
+    file
+      .read_to_end(&mut data)
+      .map_err(|err| CartridgeError::Io(err.to_string()))?;
+
+    Cartridge::from_bytes(&data)
+  }
+
+  pub fn from_bytes(data: &[u8]) -> Result<Cartridge, CartridgeError> {
+    if data.len() < MAGIC.len() || data[0..4] != MAGIC {
+      return Err(CartridgeError::InvalidMagic);
+    }
+    if data.len() < HEADER_SIZE {
+      return Err(CartridgeError::TruncatedHeader(data.len()));
+    }
+    let header = &data[0..HEADER_SIZE];
+
+    let format = match header[7] & 0x0C {
+      0x08 => Format::Nes2,
+      _ => Format::INes,
+    };
+
+    let mirroring = if header[6] & 0x08 > 0 {
+      Mirroring::FourScreen
+    } else if header[6] & 0x01 > 0 {
+      Mirroring::Vertical
+    } else {
+      Mirroring::Horizontal
+    };
+    let battery = header[6] & 0x02 > 0;
+    let has_trainer = header[6] & 0x04 > 0;
+
+    let mapper_lo = (header[6] >> 4) as u16;
+    let mapper_mid = (header[7] & 0xF0) as u16;
+
+    let (mapper, submapper, prg_rom_size, chr_rom_size, region) = match format {
+      Format::Nes2 => {
+        let mapper = ((header[8] & 0x0F) as u16) << 8 | mapper_mid | mapper_lo;
+        let submapper = header[8] >> 4;
+        let prg_rom_size = nes2_rom_size(header[4], header[9] & 0x0F, PRG_ROM_BANK_SIZE)?;
+        let chr_rom_size = nes2_rom_size(header[5], header[9] >> 4, CHR_ROM_BANK_SIZE)?;
+        let region = match header[12] & 0x03 {
+          0 => Region::Ntsc,
+          1 => Region::Pal,
+          2 => Region::Multi,
+          _ => Region::Dendy,
+        };
+        (mapper, submapper, prg_rom_size, chr_rom_size, region)
+      }
+      Format::INes => {
+        // Headers written by old tools (e.g. "DiskDude!") fill bytes 7-15 with garbage,
+        // in which case the upper mapper nibble cannot be trusted
+        let mapper = if header[12..16].iter().any(|byte| *byte != 0) {
+          mapper_lo
+        } else {
+          mapper_mid | mapper_lo
+        };
+        let region = match header[9] & 0x01 {
+          0 => Region::Ntsc,
+          _ => Region::Pal,
+        };
+        let prg_rom_size = header[4] as usize * PRG_ROM_BANK_SIZE;
+        let chr_rom_size = header[5] as usize * CHR_ROM_BANK_SIZE;
+        (mapper, 0, prg_rom_size, chr_rom_size, region)
+      }
+    };
+
+    if prg_rom_size == 0 {
+      return Err(CartridgeError::MissingPrgRom);
+    }
+
+    let (prg_ram_size, prg_nvram_size, chr_ram_size, chr_nvram_size) = match format {
+      Format::Nes2 => (
+        nes2_ram_size(header[10] & 0x0F),
+        nes2_ram_size(header[10] >> 4),
+        nes2_ram_size(header[11] & 0x0F),
+        nes2_ram_size(header[11] >> 4),
+      ),
+      Format::INes => {
+        // A value of 0 infers 8 KB for compatibility
+        let ram_size = match header[8] {
+          0 => INES_PRG_RAM_UNIT,
+          units => units as usize * INES_PRG_RAM_UNIT,
+        };
+        let chr_ram_size = match chr_rom_size {
+          0 => INES_CHR_RAM_SIZE,
+          _ => 0,
+        };
+        match battery {
+          true => (0, ram_size, chr_ram_size, 0),
+          false => (ram_size, 0, chr_ram_size, 0),
+        }
+      }
+    };
+
+    let trainer_size = if has_trainer { TRAINER_SIZE } else { 0 };
+    // NES 2.0 sizes can add up past usize, more than any file holds
+    let expected = HEADER_SIZE
+      .checked_add(trainer_size)
+      .and_then(|size| size.checked_add(prg_rom_size))
+      .and_then(|size| size.checked_add(chr_rom_size))
+      .unwrap_or(usize::MAX);
+    if data.len() < expected {
+      return Err(CartridgeError::Truncated {
+        expected,
+        actual: data.len(),
+      });
+    }
+
+    let prg_start = HEADER_SIZE + trainer_size;
+    let chr_start = prg_start + prg_rom_size;
+    let trainer = match has_trainer {
+      true => Some(data[HEADER_SIZE..prg_start].to_vec()),
+      false => None,
+    };
+
+    Ok(Cartridge {
+      format,
+      mapper,
+      submapper,
+      mirroring,
+      battery,
+      region,
+      trainer,
+      prg_rom: data[prg_start..chr_start].to_vec(),
+      chr_rom: data[chr_start..expected].to_vec(),
+      prg_ram_size,
+      prg_nvram_size,
+      chr_ram_size,
+      chr_nvram_size,
+    })
+  }
+
+  pub fn get_format(&self) -> Format {
+    self.format
+  }
+
+  pub fn get_mapper(&self) -> u16 {
+    self.mapper
+  }
+
+  pub fn get_submapper(&self) -> u8 {
+    self.submapper
+  }
+
+  pub fn get_mirroring(&self) -> Mirroring {
+    self.mirroring
+  }
+
+  pub fn has_battery(&self) -> bool {
+    self.battery
+  }
+
+  pub fn get_region(&self) -> Region {
+    self.region
+  }
+
+  pub fn get_trainer(&self) -> Option<&[u8]> {
+    self.trainer.as_deref()
+  }
+
+  pub fn get_prg_rom(&self) -> &[u8] {
+    &self.prg_rom
+  }
+
+  pub fn get_chr_rom(&self) -> &[u8] {
+    &self.chr_rom
+  }
+
+  pub fn get_prg_ram_size(&self) -> usize {
+    self.prg_ram_size
+  }
+
+  pub fn get_prg_nvram_size(&self) -> usize {
+    self.prg_nvram_size
+  }
+
+  pub fn get_chr_ram_size(&self) -> usize {
+    self.chr_ram_size
+  }
+
+  pub fn get_chr_nvram_size(&self) -> usize {
+    self.chr_nvram_size
+  }
+}
+
+// Size in bytes of a NES 2.0 ROM area given its LSB (header byte 4 or 5) and MSB nibble (byte 9)
+fn nes2_rom_size(lsb: u8, msb: u8, bank_size: usize) -> Result<usize, CartridgeError> {
+  if msb == 0x0F {
+    // Exponent-multiplier notation: EEEEEEMM -> 2^E * (MM * 2 + 1)
+    let exponent = lsb >> 2;
+    let multiplier = lsb & 0x03;
+    1usize
+      .checked_shl(exponent as u32)
+      .and_then(|size| size.checked_mul(multiplier as usize * 2 + 1))
+      .ok_or(CartridgeError::InvalidRomSize {
+        exponent,
+        multiplier,
+      })
+  } else {
+    Ok((((msb as usize) << 8) | lsb as usize) * bank_size)
+  }
+}
+
+// Size in bytes of a NES 2.0 RAM area given its shift count
+fn nes2_ram_size(shift: u8) -> usize {
+  match shift {
+    0 => 0,
+    _ => 64 << shift,
+  }
+}
@@ -0,0 +1,247 @@
+#[cfg(test)]
+use cartridge::{Cartridge, CartridgeError, Format, Mirroring, Region};
+
+#[cfg(test)]
+fn build_rom(header: [u8; 16], trainer: usize, prg: usize, chr: usize) -> Vec<u8> {
+  let mut rom = header.to_vec();
+  rom.extend(vec![0x11; trainer]);
+  rom.extend(vec![0x22; prg]);
+  rom.extend(vec![0x33; chr]);
+  rom
+}
+
+#[cfg(test)]
+mod ines {
+  use super::*;
+  #[test]
+  fn parses_rom_sizes() {
+    let header = [0x4E, 0x45, 0x53, 0x1A, 2, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    let cartridge = Cartridge::from_bytes(&build_rom(header, 0, 0x8000, 0x2000)).unwrap();
+    assert_eq!(cartridge.get_format(), Format::INes);
+    assert_eq!(cartridge.get_prg_rom().len(), 0x8000);
+    assert_eq!(cartridge.get_chr_rom().len(), 0x2000);
+    assert_eq!(cartridge.get_chr_ram_size(), 0);
+  }
+  #[test]
+  fn combines_mapper_nibbles() {
+    let header = [
+      0x4E, 0x45, 0x53, 0x1A, 1, 1, 0x40, 0x10, 0, 0, 0, 0, 0, 0, 0, 0,
+    ];
+    let cartridge = Cartridge::from_bytes(&build_rom(header, 0, 0x4000, 0x2000)).unwrap();
+    assert_eq!(cartridge.get_mapper(), 0x14);
+    assert_eq!(cartridge.get_submapper(), 0);
+  }
+  #[test]
+  fn ignores_upper_mapper_nibble_with_garbage_header() {
+    let header = [
+      0x4E, 0x45, 0x53, 0x1A, 1, 1, 0x40, 0x44, 0x69, 0x73, 0x6B, 0x44, 0x75, 0x64, 0x65, 0x21,
+    ];
+    let cartridge = Cartridge::from_bytes(&build_rom(header, 0, 0x4000, 0x2000)).unwrap();
+    assert_eq!(cartridge.get_mapper(), 0x04);
+  }
+  #[test]
+  fn parses_mirroring() {
+    let horizontal = [
+      0x4E, 0x45, 0x53, 0x1A, 1, 0, 0x00, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    ];
+    let vertical = [
+      0x4E, 0x45, 0x53, 0x1A, 1, 0, 0x01, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    ];
+    let four_screen = [
+      0x4E, 0x45, 0x53, 0x1A, 1, 0, 0x09, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    ];
+    let cartridge = Cartridge::from_bytes(&build_rom(horizontal, 0, 0x4000, 0)).unwrap();
+    assert_eq!(cartridge.get_mirroring(), Mirroring::Horizontal);
+    let cartridge = Cartridge::from_bytes(&build_rom(vertical, 0, 0x4000, 0)).unwrap();
+    assert_eq!(cartridge.get_mirroring(), Mirroring::Vertical);
+    let cartridge = Cartridge::from_bytes(&build_rom(four_screen, 0, 0x4000, 0)).unwrap();
+    assert_eq!(cartridge.get_mirroring(), Mirroring::FourScreen);
+  }
+  #[test]
+  fn infers_chr_ram_without_chr_rom() {
+    let header = [0x4E, 0x45, 0x53, 0x1A, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    let cartridge = Cartridge::from_bytes(&build_rom(header, 0, 0x4000, 0)).unwrap();
+    assert_eq!(cartridge.get_chr_rom().len(), 0);
+    assert_eq!(cartridge.get_chr_ram_size(), 0x2000);
+  }
+  #[test]
+  fn infers_8kb_prg_ram_when_zero() {
+    let header = [0x4E, 0x45, 0x53, 0x1A, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    let cartridge = Cartridge::from_bytes(&build_rom(header, 0, 0x4000, 0)).unwrap();
+    assert_eq!(cartridge.get_prg_ram_size(), 0x2000);
+    assert_eq!(cartridge.get_prg_nvram_size(), 0);
+  }
+  #[test]
+  fn battery_backs_prg_ram() {
+    let header = [
+      0x4E, 0x45, 0x53, 0x1A, 1, 0, 0x02, 0, 2, 0, 0, 0, 0, 0, 0, 0,
+    ];
+    let cartridge = Cartridge::from_bytes(&build_rom(header, 0, 0x4000, 0)).unwrap();
+    assert!(cartridge.has_battery());
+    assert_eq!(cartridge.get_prg_ram_size(), 0);
+    assert_eq!(cartridge.get_prg_nvram_size(), 0x4000);
+  }
+  #[test]
+  fn reads_pal_flag() {
+    let header = [
+      0x4E, 0x45, 0x53, 0x1A, 1, 0, 0, 0, 0, 0x01, 0, 0, 0, 0, 0, 0,
+    ];
+    let cartridge = Cartridge::from_bytes(&build_rom(header, 0, 0x4000, 0)).unwrap();
+    assert_eq!(cartridge.get_region(), Region::Pal);
+  }
+  #[test]
+  fn skips_trainer_before_prg_rom() {
+    let header = [
+      0x4E, 0x45, 0x53, 0x1A, 1, 1, 0x04, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    ];
+    let cartridge = Cartridge::from_bytes(&build_rom(header, 512, 0x4000, 0x2000)).unwrap();
+    assert_eq!(cartridge.get_trainer().unwrap(), &[0x11; 512][..]);
+    assert!(cartridge.get_prg_rom().iter().all(|byte| *byte == 0x22));
+    assert!(cartridge.get_chr_rom().iter().all(|byte| *byte == 0x33));
+  }
+}
+
+#[cfg(test)]
+mod nes2 {
+  use super::*;
+  #[test]
+  fn detects_format() {
+    let header = [
+      0x4E, 0x45, 0x53, 0x1A, 1, 1, 0, 0x08, 0, 0, 0, 0, 0, 0, 0, 0,
+    ];
+    let cartridge = Cartridge::from_bytes(&build_rom(header, 0, 0x4000, 0x2000)).unwrap();
+    assert_eq!(cartridge.get_format(), Format::Nes2);
+  }
+  #[test]
+  fn parses_mapper_and_submapper() {
+    let header = [
+      0x4E, 0x45, 0x53, 0x1A, 1, 1, 0x50, 0x48, 0x31, 0, 0, 0, 0, 0, 0, 0,
+    ];
+    let cartridge = Cartridge::from_bytes(&build_rom(header, 0, 0x4000, 0x2000)).unwrap();
+    assert_eq!(cartridge.get_mapper(), 0x145);
+    assert_eq!(cartridge.get_submapper(), 3);
+  }
+  #[test]
+  fn parses_rom_size_msb() {
+    let header = [
+      0x4E, 0x45, 0x53, 0x1A, 0x00, 0x00, 0, 0x08, 0, 0x11, 0, 0, 0, 0, 0, 0,
+    ];
+    let cartridge = Cartridge::from_bytes(&build_rom(header, 0, 0x40_0000, 0x20_0000)).unwrap();
+    assert_eq!(cartridge.get_prg_rom().len(), 0x40_0000);
+    assert_eq!(cartridge.get_chr_rom().len(), 0x20_0000);
+  }
+  #[test]
+  fn parses_exponent_multiplier_rom_size() {
+    // 2^10 * 3 = 3 KB of PRG ROM
+    let header = [
+      0x4E,
+      0x45,
+      0x53,
+      0x1A,
+      0b0010_1001,
+      0,
+      0,
+      0x08,
+      0,
+      0x0F,
+      0,
+      0,
+      0,
+      0,
+      0,
+      0,
+    ];
+    let cartridge = Cartridge::from_bytes(&build_rom(header, 0, 3 * 1024, 0)).unwrap();
+    assert_eq!(cartridge.get_prg_rom().len(), 3 * 1024);
+  }
+  #[test]
+  fn parses_ram_shift_counts() {
+    let header = [
+      0x4E, 0x45, 0x53, 0x1A, 1, 0, 0, 0x08, 0, 0, 0x70, 0x07, 0, 0, 0, 0,
+    ];
+    let cartridge = Cartridge::from_bytes(&build_rom(header, 0, 0x4000, 0)).unwrap();
+    assert_eq!(cartridge.get_prg_ram_size(), 0);
+    assert_eq!(cartridge.get_prg_nvram_size(), 0x2000);
+    assert_eq!(cartridge.get_chr_ram_size(), 0x2000);
+    assert_eq!(cartridge.get_chr_nvram_size(), 0);
+  }
+  #[test]
+  fn parses_region() {
+    let header = [
+      0x4E, 0x45, 0x53, 0x1A, 1, 0, 0, 0x08, 0, 0, 0, 0, 0x03, 0, 0, 0,
+    ];
+    let cartridge = Cartridge::from_bytes(&build_rom(header, 0, 0x4000, 0)).unwrap();
+    assert_eq!(cartridge.get_region(), Region::Dendy);
+  }
+  #[test]
+  fn rejects_oversized_exponent() {
+    let header = [
+      0x4E, 0x45, 0x53, 0x1A, 0xFF, 0, 0, 0x08, 0, 0x0F, 0, 0, 0, 0, 0, 0,
+    ];
+    let result = Cartridge::from_bytes(&build_rom(header, 0, 0, 0));
+    assert_eq!(
+      result.err(),
+      Some(CartridgeError::InvalidRomSize {
+        exponent: 63,
+        multiplier: 3
+      })
+    );
+  }
+}
+
+#[cfg(test)]
+mod errors {
+  use super::*;
+  #[test]
+  fn rejects_missing_magic() {
+    let result = Cartridge::from_bytes(&[0xA2, 0x0A, 0x8E, 0x00, 0x00]);
+    assert_eq!(result.err(), Some(CartridgeError::InvalidMagic));
+  }
+  #[test]
+  fn rejects_truncated_header() {
+    let result = Cartridge::from_bytes(&[0x4E, 0x45, 0x53, 0x1A, 1, 1]);
+    assert_eq!(result.err(), Some(CartridgeError::TruncatedHeader(6)));
+  }
+  #[test]
+  fn rejects_truncated_rom() {
+    let header = [0x4E, 0x45, 0x53, 0x1A, 2, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    let result = Cartridge::from_bytes(&build_rom(header, 0, 0x4000, 0));
+    assert_eq!(
+      result.err(),
+      Some(CartridgeError::Truncated {
+        expected: 0xA010,
+        actual: 0x4010
+      })
+    );
+  }
+  #[test]
+  fn rejects_sizes_that_overflow() {
+    // PRG and CHR ROM of 2^63 bytes each
+    let header = [
+      0x4E, 0x45, 0x53, 0x1A, 0xFC, 0xFC, 0, 0x08, 0, 0xFF, 0, 0, 0, 0, 0, 0,
+    ];
+    let result = Cartridge::from_bytes(&build_rom(header, 0, 0, 0));
+    assert_eq!(
+      result.err(),
+      Some(CartridgeError::Truncated {
+        expected: usize::MAX,
+        actual: 16
+      })
+    );
+  }
+  #[test]
+  fn rejects_missing_prg_rom() {
+    let header = [0x4E, 0x45, 0x53, 0x1A, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    let result = Cartridge::from_bytes(&build_rom(header, 0, 0, 0x2000));
+    assert_eq!(result.err(), Some(CartridgeError::MissingPrgRom));
+  }
+}
+
+#[test]
+fn loads_nestest_rom() {
+  let cartridge = Cartridge::from_file("roms/nestest.nes").unwrap();
+  assert_eq!(cartridge.get_mapper(), 0);
+  assert_eq!(cartridge.get_mirroring(), Mirroring::Horizontal);
+  assert_eq!(cartridge.get_prg_rom().len(), 0x4000);
+  assert_eq!(cartridge.get_chr_rom().len(), 0x2000);
+}
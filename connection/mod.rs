@@ -1,4 +1,4 @@
-use device::{ExactSize, Readable, Writable};
+use device::{DeviceError, ExactSize, Readable, Writable};
 
 pub struct Connection<T: ExactSize + Readable + Writable> {
   addr_start: u16,
@@ -14,6 +14,14 @@ impl<T: ExactSize + Readable + Writable> Connection<T> {
     self.addr_start
   }
 
+  pub fn get_device(&self) -> &T {
+    &self.device
+  }
+
+  pub fn get_mut_device(&mut self) -> &mut T {
+    &mut self.device
+  }
+
   pub fn get_end(&self) -> u16 {
     let end = self.addr_start as usize + self.device.mem_size() - 1;
     end as u16
@@ -23,11 +31,17 @@ impl<T: ExactSize + Readable + Writable> Connection<T> {
     addr >= self.get_start() && addr <= self.get_end()
   }
 
-  pub fn write_addr(&mut self, addr: u16, data: u8) -> Result<u8, u16> {
-    self.device.write_addr(addr as usize, data)
+  // Devices are addressed relative to the start of their connection
+  fn get_offset(&self, addr: u16) -> usize {
+    addr.wrapping_sub(self.addr_start) as usize
+  }
+
+  pub fn write_addr(&mut self, addr: u16, data: u8) -> Result<(), DeviceError> {
+    let offset = self.get_offset(addr);
+    self.device.write_addr(offset, data)
   }
 
-  pub fn read_addr(&self, addr: u16) -> Result<u8, u16> {
-    self.device.read_addr(addr as usize)
+  pub fn read_addr(&self, addr: u16) -> Result<u8, DeviceError> {
+    self.device.read_addr(self.get_offset(addr))
   }
 }
@@ -1,5 +1,7 @@
 // Source: https://www.masswerk.at/6502/6502_instruction_set.html
 
+use cpu::Variant;
+
 use std::fmt;
 
 pub enum Operation {
@@ -59,7 +61,38 @@ pub enum Operation {
   TXA, //  transfer X to accumulator
   TXS, //  transfer X to stack pointer
   TYA, //  transfer Y to accumulator
-  XXX, //  invalid operation
+  // Unofficial
+  ALR, //  and, then logical shift right
+  ANC, //  and, then copy negative into carry
+  ARR, //  and, then rotate right
+  AXS, //  X = (A and X) - operand, without borrow
+  DCP, //  decrement, then compare
+  ISC, //  increment, then subtract with carry
+  LAS, //  A, X and stack pointer = memory and stack pointer
+  LAX, //  load accumulator and X
+  RLA, //  rotate left, then and
+  RRA, //  rotate right, then add with carry
+  SAX, //  store A and X
+  SLO, //  shift left, then or
+  SRE, //  shift right, then exclusive or
+  // Unofficial and unstable on real hardware, see Cpu::operate
+  ANE, //  A = (A or magic) and X and operand
+  LXA, //  A, X = (A or magic) and operand
+  SHA, //  store A and X and (high byte + 1)
+  SHX, //  store X and (high byte + 1)
+  SHY, //  store Y and (high byte + 1)
+  TAS, //  stack pointer = A and X, store it and (high byte + 1)
+  // Unofficial, locks up the CPU until reset
+  JAM, //  halt
+  // 65C02
+  BRA, //  branch always
+  PHX, //  push X
+  PHY, //  push Y
+  PLX, //  pull X
+  PLY, //  pull Y
+  STZ, //  store zero
+  TRB, //  test and reset bits
+  TSB, //  test and set bits
 }
 
 impl fmt::Display for Operation {
@@ -121,7 +154,34 @@ impl fmt::Display for Operation {
       Operation::TXA => write!(f, "TXA"),
       Operation::TXS => write!(f, "TXS"),
       Operation::TYA => write!(f, "TYA"),
-      Operation::XXX => write!(f, "XXX"),
+      Operation::ALR => write!(f, "ALR"),
+      Operation::ANC => write!(f, "ANC"),
+      Operation::ARR => write!(f, "ARR"),
+      Operation::AXS => write!(f, "AXS"),
+      Operation::DCP => write!(f, "DCP"),
+      Operation::ISC => write!(f, "ISC"),
+      Operation::LAS => write!(f, "LAS"),
+      Operation::LAX => write!(f, "LAX"),
+      Operation::RLA => write!(f, "RLA"),
+      Operation::RRA => write!(f, "RRA"),
+      Operation::SAX => write!(f, "SAX"),
+      Operation::SLO => write!(f, "SLO"),
+      Operation::SRE => write!(f, "SRE"),
+      Operation::ANE => write!(f, "ANE"),
+      Operation::LXA => write!(f, "LXA"),
+      Operation::SHA => write!(f, "SHA"),
+      Operation::SHX => write!(f, "SHX"),
+      Operation::SHY => write!(f, "SHY"),
+      Operation::TAS => write!(f, "TAS"),
+      Operation::JAM => write!(f, "JAM"),
+      Operation::BRA => write!(f, "BRA"),
+      Operation::PHX => write!(f, "PHX"),
+      Operation::PHY => write!(f, "PHY"),
+      Operation::PLX => write!(f, "PLX"),
+      Operation::PLY => write!(f, "PLY"),
+      Operation::STZ => write!(f, "STZ"),
+      Operation::TRB => write!(f, "TRB"),
+      Operation::TSB => write!(f, "TSB"),
     }
   }
 }
@@ -140,7 +200,9 @@ pub enum AddressMode {
   IndirectX, // OPC $LL	 	  operand is zeropage address (hi-byte is zero, address = $00LL)
   IndirectY, // OPC $LL,X	 	operand is zeropage address; effective address is address incremented by X without carry **
   AbsoluteIndirect, // OPC $LL,Y	 	operand is zeropage address; effective address is address incremented by Y without carry **
-  XXX,              // Address mode for invalid operation
+  // 65C02
+  ZeroPageIndirect, // OPC ($LL)     operand is zeropage address; effective address is word in (LL, LL + 1)
+  AbsoluteIndexedIndirect, // OPC ($LLHH,X) effective address is word at address incremented by X: C.w($HHLL + X)
 }
 
 impl fmt::Display for AddressMode {
@@ -159,7 +221,8 @@ impl fmt::Display for AddressMode {
       AddressMode::IndirectX => write!(f, "INX"),
       AddressMode::IndirectY => write!(f, "INY"),
       AddressMode::AbsoluteIndirect => write!(f, "ABI"),
-      AddressMode::XXX => write!(f, "XXX"),
+      AddressMode::ZeroPageIndirect => write!(f, "ZPI"),
+      AddressMode::AbsoluteIndexedIndirect => write!(f, "AXI"),
     }
   }
 }
@@ -169,6 +232,7 @@ pub struct Instruction {
   address_mode: AddressMode,
   instruction_bytes: u8,
   cycles: u8,
+  official: bool, // Documented by MOS
 }
 
 impl Instruction {
@@ -183,15 +247,19 @@ impl Instruction {
       address_mode,
       instruction_bytes,
       cycles,
+      official: true,
     }
   }
 
-  pub fn invalid() -> Instruction {
+  pub fn unofficial(
+    operation: Operation,
+    address_mode: AddressMode,
+    instruction_bytes: u8,
+    cycles: u8,
+  ) -> Instruction {
     Instruction {
-      operation: Operation::XXX,
-      address_mode: AddressMode::XXX,
-      instruction_bytes: 0,
-      cycles: 0,
+      official: false,
+      ..Instruction::new(operation, address_mode, instruction_bytes, cycles)
     }
   }
 
@@ -210,164 +278,338 @@ impl Instruction {
   pub fn get_cycles(&self) -> u8 {
     self.cycles
   }
+
+  pub fn is_official(&self) -> bool {
+    self.official
+  }
 }
 
 pub fn get_instruction(opcode: u8) -> Instruction {
   // From http://archive.6502.org/datasheets/rockwell_r650x_r651x.pdf
   // *1 - Add 1 to cycles if page boundary is crossed
   // *2 - Add 1 to cycles if branch occurs to same page; add 2 if branch occurs to different page
+  // Unofficial opcodes from http://www.oxyron.de/html/opcodes02.html
   match opcode {
     0x00 => Instruction::new(Operation::BRK, AddressMode::Implied, 1, 7),
     0x01 => Instruction::new(Operation::ORA, AddressMode::IndirectX, 2, 6),
+    0x02 => Instruction::unofficial(Operation::JAM, AddressMode::Implied, 1, 2),
+    0x03 => Instruction::unofficial(Operation::SLO, AddressMode::IndirectX, 2, 8),
+    0x04 => Instruction::unofficial(Operation::NOP, AddressMode::ZeroPage, 2, 3),
     0x05 => Instruction::new(Operation::ORA, AddressMode::ZeroPage, 2, 3),
     0x06 => Instruction::new(Operation::ASL, AddressMode::ZeroPage, 2, 5),
+    0x07 => Instruction::unofficial(Operation::SLO, AddressMode::ZeroPage, 2, 5),
     0x08 => Instruction::new(Operation::PHP, AddressMode::Implied, 1, 3),
     0x09 => Instruction::new(Operation::ORA, AddressMode::Immediate, 2, 2),
     0x0A => Instruction::new(Operation::ASL, AddressMode::Accumulator, 1, 2),
+    0x0B => Instruction::unofficial(Operation::ANC, AddressMode::Immediate, 2, 2),
+    0x0C => Instruction::unofficial(Operation::NOP, AddressMode::Absolute, 3, 4),
     0x0D => Instruction::new(Operation::ORA, AddressMode::Absolute, 3, 4),
     0x0E => Instruction::new(Operation::ASL, AddressMode::Absolute, 3, 6),
+    0x0F => Instruction::unofficial(Operation::SLO, AddressMode::Absolute, 3, 6),
     0x10 => Instruction::new(Operation::BPL, AddressMode::Relative, 2, 2), // *2
     0x11 => Instruction::new(Operation::ORA, AddressMode::IndirectY, 2, 5), // *1
+    0x12 => Instruction::unofficial(Operation::JAM, AddressMode::Implied, 1, 2),
+    0x13 => Instruction::unofficial(Operation::SLO, AddressMode::IndirectY, 2, 8),
+    0x14 => Instruction::unofficial(Operation::NOP, AddressMode::ZeroPageX, 2, 4),
     0x15 => Instruction::new(Operation::ORA, AddressMode::ZeroPageX, 2, 4),
     0x16 => Instruction::new(Operation::ASL, AddressMode::ZeroPageX, 2, 6),
+    0x17 => Instruction::unofficial(Operation::SLO, AddressMode::ZeroPageX, 2, 6),
     0x18 => Instruction::new(Operation::CLC, AddressMode::Implied, 1, 2),
     0x19 => Instruction::new(Operation::ORA, AddressMode::AbsoluteY, 3, 4), // *1
-    0x1D => Instruction::new(Operation::ORA, AddressMode::AbsoluteX, 3, 4), // *1
+    0x1A => Instruction::unofficial(Operation::NOP, AddressMode::Implied, 1, 2),
+    0x1B => Instruction::unofficial(Operation::SLO, AddressMode::AbsoluteY, 3, 7),
+    0x1C => Instruction::unofficial(Operation::NOP, AddressMode::AbsoluteX, 3, 4), // *1
+    0x1D => Instruction::new(Operation::ORA, AddressMode::AbsoluteX, 3, 4),        // *1
     0x1E => Instruction::new(Operation::ASL, AddressMode::AbsoluteX, 3, 7),
+    0x1F => Instruction::unofficial(Operation::SLO, AddressMode::AbsoluteX, 3, 7),
     0x20 => Instruction::new(Operation::JSR, AddressMode::Absolute, 3, 6),
     0x21 => Instruction::new(Operation::AND, AddressMode::IndirectX, 2, 6),
+    0x22 => Instruction::unofficial(Operation::JAM, AddressMode::Implied, 1, 2),
+    0x23 => Instruction::unofficial(Operation::RLA, AddressMode::IndirectX, 2, 8),
     0x24 => Instruction::new(Operation::BIT, AddressMode::ZeroPage, 2, 3),
     0x25 => Instruction::new(Operation::AND, AddressMode::ZeroPage, 2, 3),
     0x26 => Instruction::new(Operation::ROL, AddressMode::ZeroPage, 2, 5),
+    0x27 => Instruction::unofficial(Operation::RLA, AddressMode::ZeroPage, 2, 5),
     0x28 => Instruction::new(Operation::PLP, AddressMode::Implied, 1, 4),
     0x29 => Instruction::new(Operation::AND, AddressMode::Immediate, 2, 2),
     0x2A => Instruction::new(Operation::ROL, AddressMode::Accumulator, 1, 2),
+    0x2B => Instruction::unofficial(Operation::ANC, AddressMode::Immediate, 2, 2),
     0x2C => Instruction::new(Operation::BIT, AddressMode::Absolute, 3, 4),
     0x2D => Instruction::new(Operation::AND, AddressMode::Absolute, 3, 4),
     0x2E => Instruction::new(Operation::ROL, AddressMode::Absolute, 3, 6),
+    0x2F => Instruction::unofficial(Operation::RLA, AddressMode::Absolute, 3, 6),
     0x30 => Instruction::new(Operation::BMI, AddressMode::Relative, 2, 2), // *2
     0x31 => Instruction::new(Operation::AND, AddressMode::IndirectY, 2, 5), // *1
+    0x32 => Instruction::unofficial(Operation::JAM, AddressMode::Implied, 1, 2),
+    0x33 => Instruction::unofficial(Operation::RLA, AddressMode::IndirectY, 2, 8),
+    0x34 => Instruction::unofficial(Operation::NOP, AddressMode::ZeroPageX, 2, 4),
     0x35 => Instruction::new(Operation::AND, AddressMode::ZeroPageX, 2, 4),
     0x36 => Instruction::new(Operation::ROL, AddressMode::ZeroPageX, 2, 6),
+    0x37 => Instruction::unofficial(Operation::RLA, AddressMode::ZeroPageX, 2, 6),
     0x38 => Instruction::new(Operation::SEC, AddressMode::Implied, 1, 2),
     0x39 => Instruction::new(Operation::AND, AddressMode::AbsoluteY, 3, 4), // *1
-    0x3D => Instruction::new(Operation::AND, AddressMode::AbsoluteX, 3, 4), // *1
+    0x3A => Instruction::unofficial(Operation::NOP, AddressMode::Implied, 1, 2),
+    0x3B => Instruction::unofficial(Operation::RLA, AddressMode::AbsoluteY, 3, 7),
+    0x3C => Instruction::unofficial(Operation::NOP, AddressMode::AbsoluteX, 3, 4), // *1
+    0x3D => Instruction::new(Operation::AND, AddressMode::AbsoluteX, 3, 4),        // *1
     0x3E => Instruction::new(Operation::ROL, AddressMode::AbsoluteX, 3, 7),
+    0x3F => Instruction::unofficial(Operation::RLA, AddressMode::AbsoluteX, 3, 7),
     0x40 => Instruction::new(Operation::RTI, AddressMode::Implied, 1, 6),
     0x41 => Instruction::new(Operation::EOR, AddressMode::IndirectX, 2, 6),
+    0x42 => Instruction::unofficial(Operation::JAM, AddressMode::Implied, 1, 2),
+    0x43 => Instruction::unofficial(Operation::SRE, AddressMode::IndirectX, 2, 8),
+    0x44 => Instruction::unofficial(Operation::NOP, AddressMode::ZeroPage, 2, 3),
     0x45 => Instruction::new(Operation::EOR, AddressMode::ZeroPage, 2, 3),
     0x46 => Instruction::new(Operation::LSR, AddressMode::ZeroPage, 2, 5),
+    0x47 => Instruction::unofficial(Operation::SRE, AddressMode::ZeroPage, 2, 5),
     0x48 => Instruction::new(Operation::PHA, AddressMode::Implied, 1, 3),
     0x49 => Instruction::new(Operation::EOR, AddressMode::Immediate, 2, 2),
     0x4A => Instruction::new(Operation::LSR, AddressMode::Accumulator, 1, 2),
+    0x4B => Instruction::unofficial(Operation::ALR, AddressMode::Immediate, 2, 2),
     0x4C => Instruction::new(Operation::JMP, AddressMode::Absolute, 3, 3),
     0x4D => Instruction::new(Operation::EOR, AddressMode::Absolute, 3, 4),
     0x4E => Instruction::new(Operation::LSR, AddressMode::Absolute, 3, 6),
+    0x4F => Instruction::unofficial(Operation::SRE, AddressMode::Absolute, 3, 6),
     0x50 => Instruction::new(Operation::BVC, AddressMode::Relative, 2, 2), // *2
     0x51 => Instruction::new(Operation::EOR, AddressMode::IndirectY, 2, 5), // *1
+    0x52 => Instruction::unofficial(Operation::JAM, AddressMode::Implied, 1, 2),
+    0x53 => Instruction::unofficial(Operation::SRE, AddressMode::IndirectY, 2, 8),
+    0x54 => Instruction::unofficial(Operation::NOP, AddressMode::ZeroPageX, 2, 4),
     0x55 => Instruction::new(Operation::EOR, AddressMode::ZeroPageX, 2, 4),
     0x56 => Instruction::new(Operation::LSR, AddressMode::ZeroPageX, 2, 6),
+    0x57 => Instruction::unofficial(Operation::SRE, AddressMode::ZeroPageX, 2, 6),
     0x58 => Instruction::new(Operation::CLI, AddressMode::Implied, 1, 2),
     0x59 => Instruction::new(Operation::EOR, AddressMode::AbsoluteY, 3, 4), // *1
-    0x5D => Instruction::new(Operation::EOR, AddressMode::AbsoluteX, 3, 4), // *1
+    0x5A => Instruction::unofficial(Operation::NOP, AddressMode::Implied, 1, 2),
+    0x5B => Instruction::unofficial(Operation::SRE, AddressMode::AbsoluteY, 3, 7),
+    0x5C => Instruction::unofficial(Operation::NOP, AddressMode::AbsoluteX, 3, 4), // *1
+    0x5D => Instruction::new(Operation::EOR, AddressMode::AbsoluteX, 3, 4),        // *1
     0x5E => Instruction::new(Operation::LSR, AddressMode::AbsoluteX, 3, 7),
+    0x5F => Instruction::unofficial(Operation::SRE, AddressMode::AbsoluteX, 3, 7),
     0x60 => Instruction::new(Operation::RTS, AddressMode::Implied, 1, 6),
     0x61 => Instruction::new(Operation::ADC, AddressMode::IndirectX, 2, 6),
+    0x62 => Instruction::unofficial(Operation::JAM, AddressMode::Implied, 1, 2),
+    0x63 => Instruction::unofficial(Operation::RRA, AddressMode::IndirectX, 2, 8),
+    0x64 => Instruction::unofficial(Operation::NOP, AddressMode::ZeroPage, 2, 3),
     0x65 => Instruction::new(Operation::ADC, AddressMode::ZeroPage, 2, 3),
     0x66 => Instruction::new(Operation::ROR, AddressMode::ZeroPage, 2, 5),
+    0x67 => Instruction::unofficial(Operation::RRA, AddressMode::ZeroPage, 2, 5),
     0x68 => Instruction::new(Operation::PLA, AddressMode::Implied, 1, 4),
     0x69 => Instruction::new(Operation::ADC, AddressMode::Immediate, 2, 2),
     0x6A => Instruction::new(Operation::ROR, AddressMode::Accumulator, 1, 2),
+    0x6B => Instruction::unofficial(Operation::ARR, AddressMode::Immediate, 2, 2),
     0x6C => Instruction::new(Operation::JMP, AddressMode::AbsoluteIndirect, 3, 5),
     0x6D => Instruction::new(Operation::ADC, AddressMode::Absolute, 3, 4),
     0x6E => Instruction::new(Operation::ROR, AddressMode::Absolute, 3, 6),
+    0x6F => Instruction::unofficial(Operation::RRA, AddressMode::Absolute, 3, 6),
     0x70 => Instruction::new(Operation::BVS, AddressMode::Relative, 2, 2), // *2
     0x71 => Instruction::new(Operation::ADC, AddressMode::IndirectY, 2, 5), // *1
+    0x72 => Instruction::unofficial(Operation::JAM, AddressMode::Implied, 1, 2),
+    0x73 => Instruction::unofficial(Operation::RRA, AddressMode::IndirectY, 2, 8),
+    0x74 => Instruction::unofficial(Operation::NOP, AddressMode::ZeroPageX, 2, 4),
     0x75 => Instruction::new(Operation::ADC, AddressMode::ZeroPageX, 2, 4),
     0x76 => Instruction::new(Operation::ROR, AddressMode::ZeroPageX, 2, 6),
+    0x77 => Instruction::unofficial(Operation::RRA, AddressMode::ZeroPageX, 2, 6),
     0x78 => Instruction::new(Operation::SEI, AddressMode::Implied, 1, 2),
     0x79 => Instruction::new(Operation::ADC, AddressMode::AbsoluteY, 3, 4), // *1
-    0x7D => Instruction::new(Operation::ADC, AddressMode::AbsoluteX, 3, 4), // *1
+    0x7A => Instruction::unofficial(Operation::NOP, AddressMode::Implied, 1, 2),
+    0x7B => Instruction::unofficial(Operation::RRA, AddressMode::AbsoluteY, 3, 7),
+    0x7C => Instruction::unofficial(Operation::NOP, AddressMode::AbsoluteX, 3, 4), // *1
+    0x7D => Instruction::new(Operation::ADC, AddressMode::AbsoluteX, 3, 4),        // *1
     0x7E => Instruction::new(Operation::ROR, AddressMode::AbsoluteX, 3, 7),
+    0x7F => Instruction::unofficial(Operation::RRA, AddressMode::AbsoluteX, 3, 7),
+    0x80 => Instruction::unofficial(Operation::NOP, AddressMode::Immediate, 2, 2),
     0x81 => Instruction::new(Operation::STA, AddressMode::IndirectX, 2, 6),
+    0x82 => Instruction::unofficial(Operation::NOP, AddressMode::Immediate, 2, 2),
+    0x83 => Instruction::unofficial(Operation::SAX, AddressMode::IndirectX, 2, 6),
     0x84 => Instruction::new(Operation::STY, AddressMode::ZeroPage, 2, 3),
     0x85 => Instruction::new(Operation::STA, AddressMode::ZeroPage, 2, 3),
     0x86 => Instruction::new(Operation::STX, AddressMode::ZeroPage, 2, 3),
+    0x87 => Instruction::unofficial(Operation::SAX, AddressMode::ZeroPage, 2, 3),
     0x88 => Instruction::new(Operation::DEY, AddressMode::Implied, 1, 2),
+    0x89 => Instruction::unofficial(Operation::NOP, AddressMode::Immediate, 2, 2),
     0x8A => Instruction::new(Operation::TXA, AddressMode::Implied, 1, 2),
+    0x8B => Instruction::unofficial(Operation::ANE, AddressMode::Immediate, 2, 2),
     0x8C => Instruction::new(Operation::STY, AddressMode::Absolute, 3, 4),
     0x8D => Instruction::new(Operation::STA, AddressMode::Absolute, 3, 4),
     0x8E => Instruction::new(Operation::STX, AddressMode::Absolute, 3, 4),
+    0x8F => Instruction::unofficial(Operation::SAX, AddressMode::Absolute, 3, 4),
     0x90 => Instruction::new(Operation::BCC, AddressMode::Relative, 2, 2), // *2
     0x91 => Instruction::new(Operation::STA, AddressMode::IndirectY, 2, 6),
+    0x92 => Instruction::unofficial(Operation::JAM, AddressMode::Implied, 1, 2),
+    0x93 => Instruction::unofficial(Operation::SHA, AddressMode::IndirectY, 2, 6),
     0x94 => Instruction::new(Operation::STY, AddressMode::ZeroPageX, 2, 4),
     0x95 => Instruction::new(Operation::STA, AddressMode::ZeroPageX, 2, 4),
     0x96 => Instruction::new(Operation::STX, AddressMode::ZeroPageY, 2, 4),
+    0x97 => Instruction::unofficial(Operation::SAX, AddressMode::ZeroPageY, 2, 4),
     0x98 => Instruction::new(Operation::TYA, AddressMode::Implied, 1, 2),
     0x99 => Instruction::new(Operation::STA, AddressMode::AbsoluteY, 3, 5),
     0x9A => Instruction::new(Operation::TXS, AddressMode::Implied, 1, 2),
+    0x9B => Instruction::unofficial(Operation::TAS, AddressMode::AbsoluteY, 3, 5),
+    0x9C => Instruction::unofficial(Operation::SHY, AddressMode::AbsoluteX, 3, 5),
     0x9D => Instruction::new(Operation::STA, AddressMode::AbsoluteX, 3, 5),
+    0x9E => Instruction::unofficial(Operation::SHX, AddressMode::AbsoluteY, 3, 5),
+    0x9F => Instruction::unofficial(Operation::SHA, AddressMode::AbsoluteY, 3, 5),
     0xA0 => Instruction::new(Operation::LDY, AddressMode::Immediate, 2, 2),
     0xA1 => Instruction::new(Operation::LDA, AddressMode::IndirectX, 2, 6),
     0xA2 => Instruction::new(Operation::LDX, AddressMode::Immediate, 2, 2),
+    0xA3 => Instruction::unofficial(Operation::LAX, AddressMode::IndirectX, 2, 6),
     0xA4 => Instruction::new(Operation::LDY, AddressMode::ZeroPage, 2, 3),
     0xA5 => Instruction::new(Operation::LDA, AddressMode::ZeroPage, 2, 3),
     0xA6 => Instruction::new(Operation::LDX, AddressMode::ZeroPage, 2, 3),
+    0xA7 => Instruction::unofficial(Operation::LAX, AddressMode::ZeroPage, 2, 3),
     0xA8 => Instruction::new(Operation::TAY, AddressMode::Implied, 1, 2),
     0xA9 => Instruction::new(Operation::LDA, AddressMode::Immediate, 2, 2),
     0xAA => Instruction::new(Operation::TAX, AddressMode::Implied, 1, 2),
+    0xAB => Instruction::unofficial(Operation::LXA, AddressMode::Immediate, 2, 2),
     0xAC => Instruction::new(Operation::LDY, AddressMode::Absolute, 3, 4),
     0xAD => Instruction::new(Operation::LDA, AddressMode::Absolute, 3, 4),
     0xAE => Instruction::new(Operation::LDX, AddressMode::Absolute, 3, 4),
+    0xAF => Instruction::unofficial(Operation::LAX, AddressMode::Absolute, 3, 4),
     0xB0 => Instruction::new(Operation::BCS, AddressMode::Relative, 2, 2), // *2
     0xB1 => Instruction::new(Operation::LDA, AddressMode::IndirectY, 2, 5), // *1
+    0xB2 => Instruction::unofficial(Operation::JAM, AddressMode::Implied, 1, 2),
+    0xB3 => Instruction::unofficial(Operation::LAX, AddressMode::IndirectY, 2, 5), // *1
     0xB4 => Instruction::new(Operation::LDY, AddressMode::ZeroPageX, 2, 4),
     0xB5 => Instruction::new(Operation::LDA, AddressMode::ZeroPageX, 2, 4),
     0xB6 => Instruction::new(Operation::LDX, AddressMode::ZeroPageY, 2, 4),
+    0xB7 => Instruction::unofficial(Operation::LAX, AddressMode::ZeroPageY, 2, 4),
     0xB8 => Instruction::new(Operation::CLV, AddressMode::Implied, 1, 2),
     0xB9 => Instruction::new(Operation::LDA, AddressMode::AbsoluteY, 3, 4), // *1
     0xBA => Instruction::new(Operation::TSX, AddressMode::Implied, 1, 2),
-    0xBC => Instruction::new(Operation::LDY, AddressMode::AbsoluteX, 3, 4), // *1
-    0xBD => Instruction::new(Operation::LDA, AddressMode::AbsoluteX, 3, 4), // *1
-    0xBE => Instruction::new(Operation::LDX, AddressMode::AbsoluteY, 3, 4), // *1
+    0xBB => Instruction::unofficial(Operation::LAS, AddressMode::AbsoluteY, 3, 4), // *1
+    0xBC => Instruction::new(Operation::LDY, AddressMode::AbsoluteX, 3, 4),        // *1
+    0xBD => Instruction::new(Operation::LDA, AddressMode::AbsoluteX, 3, 4),        // *1
+    0xBE => Instruction::new(Operation::LDX, AddressMode::AbsoluteY, 3, 4),        // *1
+    0xBF => Instruction::unofficial(Operation::LAX, AddressMode::AbsoluteY, 3, 4), // *1
     0xC0 => Instruction::new(Operation::CPY, AddressMode::Immediate, 2, 2),
     0xC1 => Instruction::new(Operation::CMP, AddressMode::IndirectX, 2, 6),
+    0xC2 => Instruction::unofficial(Operation::NOP, AddressMode::Immediate, 2, 2),
+    0xC3 => Instruction::unofficial(Operation::DCP, AddressMode::IndirectX, 2, 8),
     0xC4 => Instruction::new(Operation::CPY, AddressMode::ZeroPage, 2, 3),
     0xC5 => Instruction::new(Operation::CMP, AddressMode::ZeroPage, 2, 3),
     0xC6 => Instruction::new(Operation::DEC, AddressMode::ZeroPage, 2, 5),
+    0xC7 => Instruction::unofficial(Operation::DCP, AddressMode::ZeroPage, 2, 5),
     0xC8 => Instruction::new(Operation::INY, AddressMode::Implied, 1, 2),
     0xC9 => Instruction::new(Operation::CMP, AddressMode::Immediate, 2, 2),
     0xCA => Instruction::new(Operation::DEX, AddressMode::Implied, 1, 2),
+    0xCB => Instruction::unofficial(Operation::AXS, AddressMode::Immediate, 2, 2),
     0xCC => Instruction::new(Operation::CPY, AddressMode::Absolute, 3, 4),
     0xCD => Instruction::new(Operation::CMP, AddressMode::Absolute, 3, 4),
     0xCE => Instruction::new(Operation::DEC, AddressMode::Absolute, 3, 6),
+    0xCF => Instruction::unofficial(Operation::DCP, AddressMode::Absolute, 3, 6),
     0xD0 => Instruction::new(Operation::BNE, AddressMode::Relative, 2, 2), // *2
     0xD1 => Instruction::new(Operation::CMP, AddressMode::IndirectY, 2, 5), // *1
+    0xD2 => Instruction::unofficial(Operation::JAM, AddressMode::Implied, 1, 2),
+    0xD3 => Instruction::unofficial(Operation::DCP, AddressMode::IndirectY, 2, 8),
+    0xD4 => Instruction::unofficial(Operation::NOP, AddressMode::ZeroPageX, 2, 4),
     0xD5 => Instruction::new(Operation::CMP, AddressMode::ZeroPageX, 2, 4),
     0xD6 => Instruction::new(Operation::DEC, AddressMode::ZeroPageX, 2, 6),
+    0xD7 => Instruction::unofficial(Operation::DCP, AddressMode::ZeroPageX, 2, 6),
     0xD8 => Instruction::new(Operation::CLD, AddressMode::Implied, 1, 2),
     0xD9 => Instruction::new(Operation::CMP, AddressMode::AbsoluteY, 3, 4), // *1
-    0xDD => Instruction::new(Operation::CMP, AddressMode::AbsoluteX, 3, 4), // *1
+    0xDA => Instruction::unofficial(Operation::NOP, AddressMode::Implied, 1, 2),
+    0xDB => Instruction::unofficial(Operation::DCP, AddressMode::AbsoluteY, 3, 7),
+    0xDC => Instruction::unofficial(Operation::NOP, AddressMode::AbsoluteX, 3, 4), // *1
+    0xDD => Instruction::new(Operation::CMP, AddressMode::AbsoluteX, 3, 4),        // *1
     0xDE => Instruction::new(Operation::DEC, AddressMode::AbsoluteX, 3, 7),
+    0xDF => Instruction::unofficial(Operation::DCP, AddressMode::AbsoluteX, 3, 7),
     0xE0 => Instruction::new(Operation::CPX, AddressMode::Immediate, 2, 2),
     0xE1 => Instruction::new(Operation::SBC, AddressMode::IndirectX, 2, 6),
+    0xE2 => Instruction::unofficial(Operation::NOP, AddressMode::Immediate, 2, 2),
+    0xE3 => Instruction::unofficial(Operation::ISC, AddressMode::IndirectX, 2, 8),
     0xE4 => Instruction::new(Operation::CPX, AddressMode::ZeroPage, 2, 3),
     0xE5 => Instruction::new(Operation::SBC, AddressMode::ZeroPage, 2, 3),
     0xE6 => Instruction::new(Operation::INC, AddressMode::ZeroPage, 2, 5),
+    0xE7 => Instruction::unofficial(Operation::ISC, AddressMode::ZeroPage, 2, 5),
     0xE8 => Instruction::new(Operation::INX, AddressMode::Implied, 1, 2),
     0xE9 => Instruction::new(Operation::SBC, AddressMode::Immediate, 2, 2),
     0xEA => Instruction::new(Operation::NOP, AddressMode::Implied, 1, 2),
+    0xEB => Instruction::unofficial(Operation::SBC, AddressMode::Immediate, 2, 2),
     0xEC => Instruction::new(Operation::CPX, AddressMode::Absolute, 3, 4),
     0xED => Instruction::new(Operation::SBC, AddressMode::Absolute, 3, 4),
     0xEE => Instruction::new(Operation::INC, AddressMode::Absolute, 3, 6),
+    0xEF => Instruction::unofficial(Operation::ISC, AddressMode::Absolute, 3, 6),
     0xF0 => Instruction::new(Operation::BEQ, AddressMode::Relative, 2, 2), // *2
     0xF1 => Instruction::new(Operation::SBC, AddressMode::IndirectY, 2, 5), // *1
+    0xF2 => Instruction::unofficial(Operation::JAM, AddressMode::Implied, 1, 2),
+    0xF3 => Instruction::unofficial(Operation::ISC, AddressMode::IndirectY, 2, 8),
+    0xF4 => Instruction::unofficial(Operation::NOP, AddressMode::ZeroPageX, 2, 4),
     0xF5 => Instruction::new(Operation::SBC, AddressMode::ZeroPageX, 2, 4),
     0xF6 => Instruction::new(Operation::INC, AddressMode::ZeroPageX, 2, 6),
+    0xF7 => Instruction::unofficial(Operation::ISC, AddressMode::ZeroPageX, 2, 6),
     0xF8 => Instruction::new(Operation::SED, AddressMode::Implied, 1, 2),
     0xF9 => Instruction::new(Operation::SBC, AddressMode::AbsoluteY, 3, 4), // *1
-    0xFD => Instruction::new(Operation::SBC, AddressMode::AbsoluteX, 3, 4), // *1
+    0xFA => Instruction::unofficial(Operation::NOP, AddressMode::Implied, 1, 2),
+    0xFB => Instruction::unofficial(Operation::ISC, AddressMode::AbsoluteY, 3, 7),
+    0xFC => Instruction::unofficial(Operation::NOP, AddressMode::AbsoluteX, 3, 4), // *1
+    0xFD => Instruction::new(Operation::SBC, AddressMode::AbsoluteX, 3, 4),        // *1
     0xFE => Instruction::new(Operation::INC, AddressMode::AbsoluteX, 3, 7),
-    _ => Instruction::invalid(),
+    0xFF => Instruction::unofficial(Operation::ISC, AddressMode::AbsoluteX, 3, 7),
+  }
+}
+
+// Instruction set of the given CPU, the NMOS chips share one table
+pub fn decode(opcode: u8, variant: Variant) -> Instruction {
+  match variant {
+    Variant::Ricoh2A03 | Variant::Nmos6502 => get_instruction(opcode),
+    Variant::Cmos65C02 => get_cmos_instruction(opcode),
+  }
+}
+
+pub fn get_cmos_instruction(opcode: u8) -> Instruction {
+  // From http://www.6502.org/tutorials/65c02opcodes.html
+  // The official NMOS instructions carry over, every other opcode is a new instruction or a NOP
+  match opcode {
+    0x04 => Instruction::new(Operation::TSB, AddressMode::ZeroPage, 2, 5),
+    0x0C => Instruction::new(Operation::TSB, AddressMode::Absolute, 3, 6),
+    0x12 => Instruction::new(Operation::ORA, AddressMode::ZeroPageIndirect, 2, 5),
+    0x14 => Instruction::new(Operation::TRB, AddressMode::ZeroPage, 2, 5),
+    0x1A => Instruction::new(Operation::INC, AddressMode::Accumulator, 1, 2),
+    0x1C => Instruction::new(Operation::TRB, AddressMode::Absolute, 3, 6),
+    0x1E => Instruction::new(Operation::ASL, AddressMode::AbsoluteX, 3, 6), // *1
+    0x32 => Instruction::new(Operation::AND, AddressMode::ZeroPageIndirect, 2, 5),
+    0x34 => Instruction::new(Operation::BIT, AddressMode::ZeroPageX, 2, 4),
+    0x3A => Instruction::new(Operation::DEC, AddressMode::Accumulator, 1, 2),
+    0x3C => Instruction::new(Operation::BIT, AddressMode::AbsoluteX, 3, 4), // *1
+    0x3E => Instruction::new(Operation::ROL, AddressMode::AbsoluteX, 3, 6), // *1
+    0x52 => Instruction::new(Operation::EOR, AddressMode::ZeroPageIndirect, 2, 5),
+    0x5A => Instruction::new(Operation::PHY, AddressMode::Implied, 1, 3),
+    0x5E => Instruction::new(Operation::LSR, AddressMode::AbsoluteX, 3, 6), // *1
+    0x64 => Instruction::new(Operation::STZ, AddressMode::ZeroPage, 2, 3),
+    0x6C => Instruction::new(Operation::JMP, AddressMode::AbsoluteIndirect, 3, 6),
+    0x72 => Instruction::new(Operation::ADC, AddressMode::ZeroPageIndirect, 2, 5),
+    0x74 => Instruction::new(Operation::STZ, AddressMode::ZeroPageX, 2, 4),
+    0x7A => Instruction::new(Operation::PLY, AddressMode::Implied, 1, 4),
+    0x7C => Instruction::new(Operation::JMP, AddressMode::AbsoluteIndexedIndirect, 3, 6),
+    0x7E => Instruction::new(Operation::ROR, AddressMode::AbsoluteX, 3, 6), // *1
+    0x80 => Instruction::new(Operation::BRA, AddressMode::Relative, 2, 2),  // *2
+    0x89 => Instruction::new(Operation::BIT, AddressMode::Immediate, 2, 2),
+    0x92 => Instruction::new(Operation::STA, AddressMode::ZeroPageIndirect, 2, 5),
+    0x9C => Instruction::new(Operation::STZ, AddressMode::Absolute, 3, 4),
+    0x9E => Instruction::new(Operation::STZ, AddressMode::AbsoluteX, 3, 5),
+    0xB2 => Instruction::new(Operation::LDA, AddressMode::ZeroPageIndirect, 2, 5),
+    0xD2 => Instruction::new(Operation::CMP, AddressMode::ZeroPageIndirect, 2, 5),
+    0xDA => Instruction::new(Operation::PHX, AddressMode::Implied, 1, 3),
+    0xF2 => Instruction::new(Operation::SBC, AddressMode::ZeroPageIndirect, 2, 5),
+    0xFA => Instruction::new(Operation::PLX, AddressMode::Implied, 1, 4),
+    _ => match get_instruction(opcode) {
+      instruction if instruction.is_official() => instruction,
+      _ => get_cmos_nop(opcode),
+    },
+  }
+}
+
+// Unused 65C02 opcodes skip their operand bytes, some of them also read memory
+fn get_cmos_nop(opcode: u8) -> Instruction {
+  match opcode {
+    0x02 | 0x22 | 0x42 | 0x62 | 0x82 | 0xC2 | 0xE2 => {
+      Instruction::unofficial(Operation::NOP, AddressMode::Immediate, 2, 2)
+    }
+    0x44 => Instruction::unofficial(Operation::NOP, AddressMode::ZeroPage, 2, 3),
+    0x54 | 0xD4 | 0xF4 => Instruction::unofficial(Operation::NOP, AddressMode::ZeroPageX, 2, 4),
+    0x5C => Instruction::unofficial(Operation::NOP, AddressMode::Absolute, 3, 8),
+    0xDC | 0xFC => Instruction::unofficial(Operation::NOP, AddressMode::Absolute, 3, 4),
+    _ => Instruction::unofficial(Operation::NOP, AddressMode::Implied, 1, 1),
   }
 }
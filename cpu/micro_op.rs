@@ -0,0 +1,55 @@
+// Source: http://nesdev.com/6502_cpu.txt
+
+// One cycle of an instruction or interrupt sequence, each makes exactly one bus access unless
+// noted. The opcode fetch is the first cycle of every instruction and is not listed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MicroOp {
+  // Addressing
+  FetchAddrLo,           // Read the low byte of an address or zero page pointer at pc
+  FetchAddrHi,           // Read the high byte of an address at pc
+  FetchAddrHiX,          // Read the high byte and add X, the high byte is fixed up later
+  FetchAddrHiY,          // Read the high byte and add Y, the high byte is fixed up later
+  IndexZeroPageX,        // Dummy read of the zero page address while adding X, wraps in page
+  IndexZeroPageY,        // Dummy read of the zero page address while adding Y, wraps in page
+  IndexPointerX,         // Dummy read of the last operand byte while adding X to the pointer
+  DummyReadOperand,      // Read the last operand byte again
+  ReadPointerLo,         // Read the low byte of the address the pointer points to
+  ReadPointerHi,         // Read the high byte from the next address
+  ReadPointerHiZeroPage, // Read the high byte, the pointer wraps in zero page
+  ReadPointerHiPageWrap, // Read the high byte, the pointer wraps in its page (JMP bug)
+  ReadPointerHiY,        // Read the high byte from zero page and add Y, fixed up later
+  // Indexed addressing, reads only take the extra cycle when the address crossed a page
+  FixPage,          // Read the address before its high byte was fixed
+  FixPageIfCrossed, // As FixPage, skipped unless a page was crossed
+  // Operand access
+  Execute,      // Perform the operation, reading or writing the operand
+  ReadData,     // Read the operand of a read-modify-write
+  DummyWrite,   // Write the unmodified operand back
+  Modify,       // Perform the operation on the operand read before, writing the result
+  DecimalCycle, // 65C02 decimal ADC and SBC fix up their flags
+  Implied,      // Dummy read of pc and perform the operation
+  Operate,      // Perform a stack operation, which does its own access
+  // Branches
+  FetchOffset, // Read the branch offset at pc
+  BranchTaken, // Dummy read of pc while adding the offset to its low byte
+  BranchFix,   // Dummy read of pc while fixing its high byte, skipped unless a page was crossed
+  // Stack
+  DummyReadPc,      // Read pc without incrementing it
+  DummyFetch,       // Read pc and increment it
+  DummyReadStack,   // Read the top of the stack
+  PushPch,          // Push the high byte of pc
+  PushPcl,          // Push the low byte of pc
+  PushStatus,       // Push status with B clear, then disable interrupts
+  PushStatusBreak,  // Push status with B set, then disable interrupts
+  PullStatus,       // Pull status
+  PullPcl,          // Pull the low byte of pc
+  PullPch,          // Pull the high byte of pc
+  JumpToSubroutine, // Read the high byte of the JSR address at pc and jump
+  ResetStack,       // Dummy read of the stack while decrementing the stack pointer
+  // Interrupt vectors, the handler's first instruction runs before interrupts are polled again
+  FetchVectorLo, // Read the low byte of the vector at pointer
+  FetchVectorHi, // Read the high byte of the vector
+  // No bus access
+  Internal, // Padding for the 65C02's eight cycle NOP
+  Jump,     // Set pc to the address, takes no cycle of its own
+}
@@ -1,16 +1,26 @@
 pub mod instruction;
+mod micro_op;
 mod tests;
 
 use bus::Bus;
-use cpu::instruction::{get_instruction, AddressMode, Operation};
+use cpu::instruction::{decode, AddressMode, Operation};
+use cpu::micro_op::MicroOp;
 use data_flow::ReadWrite;
 
+use std::fmt;
+
 const STACK_BASE_ADDR: u16 = 0x0100;
 const RESET_ADDRESS: u16 = 0xFFFC;
 const LO_BYTE_MASK: u16 = 0x00FF;
 const HI_BYTE_MASK: u16 = 0xFF00;
 const BRK_ADDR_BEGIN: u16 = 0xFFFE;
 const NMI_ADDR_BEGIN: u16 = 0xFFFA;
+const STATUS_BREAK: u8 = 0b0001_0000;
+const STATUS_UNUSED: u8 = 0b0010_0000;
+const OAM_DATA_ADDR: u16 = 0x2004;
+const OAM_DMA_LENGTH: u16 = 256;
+const ANE_MAGIC: u8 = 0xEE;
+const LXA_MAGIC: u8 = 0xFF;
 
 fn flag_from_char(flag: char) -> u8 {
   match flag {
@@ -26,8 +36,68 @@ fn flag_from_char(flag: char) -> u8 {
   }
 }
 
+// Stores never read their target, which matters for registers with read side effects
+fn is_store(operation: &Operation) -> bool {
+  matches!(
+    operation,
+    Operation::STA
+      | Operation::STX
+      | Operation::STY
+      | Operation::SAX
+      | Operation::SHA
+      | Operation::SHX
+      | Operation::SHY
+      | Operation::TAS
+      | Operation::STZ
+  )
+}
+
+// Read the operand, write it back unmodified, then write the result
+fn is_read_modify_write(operation: &Operation) -> bool {
+  matches!(
+    operation,
+    Operation::ASL
+      | Operation::DEC
+      | Operation::INC
+      | Operation::LSR
+      | Operation::ROL
+      | Operation::ROR
+      | Operation::DCP
+      | Operation::ISC
+      | Operation::RLA
+      | Operation::RRA
+      | Operation::SLO
+      | Operation::SRE
+      | Operation::TRB
+      | Operation::TSB
+  )
+}
+
+// Only reads pay for fixing up the high byte, stores and read-modify-write always take it
+fn has_page_cross_penalty(operation: &Operation) -> bool {
+  !is_store(operation) && !is_read_modify_write(operation)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Variant {
+  Ricoh2A03, // NES CPU, decimal mode is wired off
+  Nmos6502,  // MOS 6502 with decimal mode
+  Cmos65C02, // 65C02 without the Rockwell and WDC bit instructions
+}
+
+impl fmt::Display for Variant {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Variant::Ricoh2A03 => write!(f, "2A03"),
+      Variant::Nmos6502 => write!(f, "6502"),
+      Variant::Cmos65C02 => write!(f, "65C02"),
+    }
+  }
+}
+
 pub struct Cpu {
   bus: Bus, // Connected BUS
+  variant: Variant,
   // 6502 simulation registers
   acc: u8,    // Accumulator
   x: u8,      // X Register
@@ -41,12 +111,41 @@ pub struct Cpu {
   opcode: u8,          // Current instruction byte
   addr_of_data: u16,   // Any absolute address value
   ppc: u16,            // Beginning of instruction pc value
+  page_crossed: bool,  // Indexed address of the current instruction is on another page
+  jammed: bool,        // Halted by a JAM opcode, only a reset recovers
+  // Cycle sequencing
+  micro_ops: Vec<MicroOp>, // Cycles of the current instruction or interrupt
+  micro_op_index: usize,   // Next micro op to run
+  pointer: u16,            // Base address before indexing, indirect pointer or branch target
+  data: u8,                // Operand latched between the cycles of a read-modify-write
+  // DMA
+  dma_cycles: u8,           // Cycles the DMC DMA has waited for its sample so far
+  halted: bool,             // DMA took the last cycle
+  oam_dma_page: Option<u8>, // Page OAM DMA is copying from, until all of it is copied
+  oam_dma_started: bool,    // The halt cycle of OAM DMA is done
+  oam_dma_index: u16,       // Bytes of the page copied so far
+  oam_dma_data: Option<u8>, // Byte read on a get cycle, written to OAM on the next put cycle
+  // Interrupts
+  nmi_line: bool, // Level of the NMI input at the last sample, NMI triggers on its edge
+  nmi_pending: bool, // An edge was detected and the NMI has not started yet
+  polling: bool,  // The last cycle polls for interrupts
+  nmi_polled: bool, // The last poll found an NMI to service at the next boundary
+  irq_polled: bool, // The last poll found the IRQ line asserted with interrupts enabled
 }
 
 impl Cpu {
   pub fn new() -> Cpu {
+    Cpu::with_bus(Bus::new())
+  }
+
+  pub fn with_bus(bus: Bus) -> Cpu {
+    Cpu::with_variant(bus, Variant::Ricoh2A03)
+  }
+
+  pub fn with_variant(bus: Bus, variant: Variant) -> Cpu {
     Cpu {
-      bus: Bus::new(),
+      bus,
+      variant,
 
       acc: 0x00,
       x: 0x00,
@@ -60,13 +159,39 @@ impl Cpu {
       opcode: 0x00,
       addr_of_data: 0x0000,
       ppc: 0xFFFC,
+      page_crossed: false,
+      jammed: false,
+
+      micro_ops: Vec::new(),
+      micro_op_index: 0,
+      pointer: 0x0000,
+      data: 0x00,
+      dma_cycles: 0,
+      halted: false,
+      oam_dma_page: None,
+      oam_dma_started: false,
+      oam_dma_index: 0,
+      oam_dma_data: None,
+      nmi_line: false,
+      nmi_pending: false,
+      polling: false,
+      nmi_polled: false,
+      irq_polled: false,
     }
   }
 
+  pub fn get_bus_ref(&self) -> &Bus {
+    &self.bus
+  }
+
   pub fn get_mut_bus_ref(&mut self) -> &mut Bus {
     &mut self.bus
   }
 
+  pub fn get_variant(&self) -> Variant {
+    self.variant
+  }
+
   pub fn get_acc(&self) -> &u8 {
     &self.acc
   }
@@ -90,6 +215,11 @@ impl Cpu {
     &self.pc
   }
 
+  // Start somewhere other than the reset vector, like nestest's automation mode
+  pub fn set_pc(&mut self, pc: u16) {
+    self.pc = pc;
+  }
+
   pub fn get_current_tick(&self) -> &usize {
     &self.current_tick
   }
@@ -111,122 +241,236 @@ impl Cpu {
   }
 
   // Interrupt: reset
-  // Reset all internal values to defaults
+  // Reset all internal values to defaults, the reset sequence then loads pc from its vector
   pub fn reset(&mut self) {
-    let lo = self.read_addr(RESET_ADDRESS + 0) as u16;
-    let hi = self.read_addr(RESET_ADDRESS + 1) as u16;
-
-    self.pc = (hi << 8) | lo;
-
-    self.pc = 0xC000;
-
     self.acc = 0;
     self.x = 0;
     self.y = 0;
-    self.stkp = 0xFD;
-    self.status = match self.get_flag('U') {
-      true => 0b00000100,
-      false => 0b00000000,
-    };
+    // The sequence decrements it three times like an interrupt, with the writes suppressed
+    self.stkp = 0x00;
+    // Interrupts start disabled
+    self.status = STATUS_UNUSED | 0b0000_0100;
 
     self.addr_of_data = 0x0000;
+    self.jammed = false;
+    self.nmi_pending = false;
+    self.nmi_polled = false;
+    self.irq_polled = false;
 
-    self.cycles = 4;
+    self.pointer = RESET_ADDRESS;
+    self.start_sequence(vec![
+      MicroOp::DummyReadPc,
+      MicroOp::DummyReadPc,
+      MicroOp::ResetStack,
+      MicroOp::ResetStack,
+      MicroOp::ResetStack,
+      MicroOp::FetchVectorLo,
+      MicroOp::FetchVectorHi,
+      MicroOp::Jump,
+    ]);
   }
-  
-  // Interrupt: request
-  pub fn irq(&mut self) {
-    if !self.get_flag('I') {
-      self.stack_push(((self.pc >> 8) & 0x00FF) as u8);
-      self.stack_push((self.pc & 0x00FF) as u8);
-
-      self.set_flag('B', 0);
-      self.set_flag('U', 1);
-      self.set_flag('I', 1);
-      self.stack_push(self.status);
-      self.pc = self.read_addr_from(BRK_ADDR_BEGIN);
 
-      self.cycles = 7;
-    }
+  // Interrupt: request
+  fn irq(&mut self) {
+    self.irq_polled = false;
+    self.interrupt(BRK_ADDR_BEGIN);
   }
   // Interrupt: non-maskable
-  pub fn nmi(&mut self) {
-    self.stack_push(((self.pc >> 8) & 0x00FF) as u8);
-    self.stack_push((self.pc & 0x00FF) as u8);
-
-    self.set_flag('B', 0);
-    self.set_flag('U', 1);
-    self.set_flag('I', 1);
-    self.stack_push(self.status);
-    self.pc = self.read_addr_from(NMI_ADDR_BEGIN);
-
-    self.cycles = 8;
+  fn nmi(&mut self) {
+    self.nmi_pending = false;
+    self.nmi_polled = false;
+    self.irq_polled = false;
+    self.interrupt(NMI_ADDR_BEGIN);
+  }
+  // Like BRK, except pc is not incremented and the status is pushed with B clear
+  fn interrupt(&mut self, vector: u16) {
+    self.pointer = vector;
+    self.start_sequence(vec![
+      MicroOp::DummyReadPc,
+      MicroOp::DummyReadPc,
+      MicroOp::PushPch,
+      MicroOp::PushPcl,
+      MicroOp::PushStatus,
+      MicroOp::FetchVectorLo,
+      MicroOp::FetchVectorHi,
+      MicroOp::Jump,
+    ]);
   }
-  // Perform one clock cycle
+
+  // Perform one clock cycle, making at most one bus access
   pub fn clock(&mut self) {
-    if self.is_cycle_complete() {
+    self.sample_interrupt_lines();
+    self.halted = false;
+    if let Some(page) = self.bus.take_oam_dma_page() {
+      self.oam_dma_page = Some(page);
+      self.oam_dma_started = false;
+      self.oam_dma_index = 0;
+      self.oam_dma_data = None;
+    }
+    match self.get_dma_halt() {
+      Some(halt_addr) => {
+        self.run_dma_cycle(halt_addr);
+        self.halted = true;
+        self.current_tick += 1;
+        return;
+      }
+      None => self.dma_cycles = 0,
+    }
+
+    if !self.is_cycle_complete() {
+      self.run_next_micro_op();
+    } else if self.jammed {
+      // A jammed CPU ignores interrupts and stops fetching, time still passes
+    } else if self.nmi_polled {
+      // Interrupts are only serviced between instructions, NMI first
+      self.ppc = self.pc;
+      self.nmi();
+      self.run_next_micro_op();
+    } else if self.irq_polled {
+      self.ppc = self.pc;
+      self.irq();
+      self.run_next_micro_op();
+    } else {
       self.ppc = self.pc;
       // Read the program counter
       self.opcode = self.read_pc_addr();
       // Always set the unused flag to 1
       self.set_flag('U', 1);
-      // Get the instruction for the opcode
-      let instruction = get_instruction(self.opcode);
-
-      // Get Starting number of cycles
-      self.cycles = instruction.get_cycles();
-
-      // Get pointer to the data that will be used in the operation
-      let data_ptr = self.get_data_ptr(instruction.get_address_mode());
-      self.addr_of_data = data_ptr;
-
-      // Perform the operation
-      self.perform_operation(
-        data_ptr,
-        instruction.get_operation(),
-        instruction.get_address_mode(),
-      );
-    } else {
-      self.current_tick += 1;
-      self.cycles -= 1;
+      self.start_instruction();
+    }
+    self.skip_micro_ops();
+    // A taken branch only polls again if it crosses a page, and nothing polls while the vector
+    // is fetched so that the handler's first instruction always runs
+    self.polling = match self.micro_ops.get(self.micro_op_index) {
+      _ if self.is_cycle_complete() => false,
+      Some(MicroOp::BranchTaken) | Some(MicroOp::FetchVectorHi) => false,
+      _ => true,
+    };
+    self.current_tick += 1;
+  }
+
+  // The lines are sampled at the end of every cycle, once the rest of the system has run it too.
+  // The NMI edge is always caught, the poll only on cycles that make it. Instructions do not poll
+  // on their last cycle, so they act on the poll from the one before, which is why the I flag
+  // changes of CLI, SEI and PLP only take effect after the next instruction
+  fn sample_interrupt_lines(&mut self) {
+    let nmi_line = self.bus.is_nmi_asserted();
+    if nmi_line && !self.nmi_line {
+      self.nmi_pending = true;
+    }
+    self.nmi_line = nmi_line;
+    if self.polling {
+      self.nmi_polled = self.nmi_pending;
+      self.irq_polled = self.bus.is_irq_asserted() && !self.get_flag('I');
     }
   }
 
-  // Perform a single step by executing an instruction
+  // An NMI detected before the status is pushed takes over the vector of a BRK or IRQ
+  fn hijack_vector(&mut self) {
+    if self.nmi_pending && self.pointer != NMI_ADDR_BEGIN {
+      self.nmi_pending = false;
+      self.pointer = NMI_ADDR_BEGIN;
+    }
+  }
+
+  // Perform a single step by executing an instruction, along with any DMA that halts it
   pub fn step(&mut self) {
-    if self.is_cycle_complete() {
+    self.clock();
+    while !self.is_cycle_complete() || self.halted {
       self.clock()
     }
-    while !self.is_cycle_complete() {
-      self.clock()
+  }
+
+  // DMA can only halt the CPU on a read, writes go ahead first
+  fn get_dma_halt(&self) -> Option<u16> {
+    if self.oam_dma_page.is_none() && self.bus.get_dmc_dma_addr().is_none() {
+      return None;
     }
+    self.get_next_read_addr()
+  }
+
+  // The halted CPU repeats its read through the halt and dummy cycles and while the DMA waits
+  // for a get cycle to fetch the sample. The DMC's fetch goes first when both DMAs want the same
+  // get cycle, OAM DMA then needs another put cycle to realign, usually costing it two cycles
+  fn run_dma_cycle(&mut self, halt_addr: u16) {
+    let get_cycle = self.bus.is_dma_get_cycle();
+    match self.bus.get_dmc_dma_addr() {
+      Some(sample_addr) if get_cycle && self.dma_cycles >= 2 => {
+        let sample = self.read(sample_addr);
+        self.bus.load_dmc_sample(sample);
+        self.dma_cycles = 0;
+        return;
+      }
+      Some(_) => self.dma_cycles += 1,
+      None => self.dma_cycles = 0,
+    }
+    match self.oam_dma_page {
+      Some(page) => self.run_oam_dma_cycle(page, halt_addr, get_cycle),
+      None => {
+        self.read(halt_addr);
+      }
+    }
+  }
+
+  // After the halt cycle OAM DMA reads a byte on each get cycle and writes it to the PPU on the
+  // put cycle after, 513 cycles in all or 514 when it has to wait one to align with a get cycle
+  fn run_oam_dma_cycle(&mut self, page: u8, halt_addr: u16, get_cycle: bool) {
+    match self.oam_dma_data {
+      _ if !self.oam_dma_started => {
+        self.read(halt_addr);
+        self.oam_dma_started = true;
+      }
+      None if get_cycle => {
+        let addr = (page as u16) << 8 | self.oam_dma_index;
+        self.oam_dma_data = Some(self.read(addr));
+      }
+      Some(data) if !get_cycle => {
+        self.write(OAM_DATA_ADDR, data);
+        self.oam_dma_data = None;
+        self.oam_dma_index += 1;
+        if self.oam_dma_index == OAM_DMA_LENGTH {
+          self.oam_dma_page = None;
+        }
+      }
+      _ => {
+        self.read(halt_addr);
+      }
+    }
+  }
+
+  // Whether DMA took the last cycle
+  pub fn is_halted(&self) -> bool {
+    self.halted
   }
 
   fn perform_operation(&mut self, ptr: u16, operation: &Operation, address_mode: &AddressMode) {
-    let data: u8 = self.read_addr(ptr);
+    let data = match is_store(operation) {
+      true => 0,
+      false => self.read(ptr),
+    };
+    self.operate(ptr, data, operation, address_mode);
+    if let AddressMode::Immediate = address_mode {
+      self.pc = self.pc.wrapping_add(1);
+    }
+  }
 
+  // Carry out the operation on data already read from ptr
+  fn operate(&mut self, ptr: u16, data: u8, operation: &Operation, address_mode: &AddressMode) {
     match operation {
-      Operation::ADC => {
-        let result_16 = (self.acc as u16) + (data as u16) + (self.get_flag('C') as u16);
-        self.set_flag_with_bool('C', result_16 > 0xFF);
-
-        let result = result_16 as u8;
-        self.set_flag_with_bool('Z', result == 0);
-        self.set_flag_with_bool('V', ((self.acc ^ result) & !(self.acc ^ data)) & 0x80 > 0);
-        self.set_flag_with_bool('N', result & 0x80 > 0);
-        self.acc = result;
-      }
+      Operation::ADC => self.add_with_carry(data),
       Operation::AND => {
         let result = self.acc & data;
         self.set_flag_with_bool('N', result & 0x80 > 0);
         self.set_flag_with_bool('Z', result == 0);
+        self.acc = result;
       }
-      Operation::ASL => {
-        let result = data << 1;
-        self.set_flag_with_bool('C', result < data);
-        self.set_flag_with_bool('Z', result == 0);
-        self.set_flag_with_bool('N', result & 0x80 > 0);
-      }
+      Operation::ASL => match address_mode {
+        &AddressMode::Accumulator => self.acc = self.shift_left(self.acc),
+        _ => {
+          let result = self.shift_left(data);
+          self.write(ptr, result);
+        }
+      },
       Operation::BCC => {
         if !self.get_flag('C') {
           self.branch(ptr as i16);
@@ -245,8 +489,11 @@ impl Cpu {
       Operation::BIT => {
         let result = self.acc & data;
         self.set_flag_with_bool('Z', result == 0);
-        self.set_flag_with_bool('N', data & 0x80 > 0);
-        self.set_flag_with_bool('V', data & 0x40 > 0);
+        // The 65C02's immediate BIT has no memory to copy N and V from
+        if !matches!(address_mode, AddressMode::Immediate) {
+          self.set_flag_with_bool('N', data & 0x80 > 0);
+          self.set_flag_with_bool('V', data & 0x40 > 0);
+        }
       }
       Operation::BMI => {
         if self.get_flag('N') {
@@ -263,24 +510,8 @@ impl Cpu {
           self.branch(ptr as i16);
         }
       }
-      Operation::BRK => {
-        // Set flags
-        self.set_flag('I', 1);
-        self.set_flag('B', 1);
-        // Increment pc (so RTI executes correctly)
-        self.pc = self.pc.wrapping_add(1);
-        // Store incremented PC address bytes on stack
-        let pc_hi = (self.pc & 0xFF00) >> 8;
-        let pc_lo = self.pc & 0x00FF;
-        self.stack_push(pc_hi as u8);
-        self.stack_push(pc_lo as u8);
-        // Store status on stack
-        self.stack_push(self.status);
-        // Reset B flag
-        self.set_flag('B', 0);
-        // Read destination into pc
-        self.pc = self.read_addr_from(BRK_ADDR_BEGIN);
-      }
+      // Runs entirely as micro ops
+      Operation::BRK => (),
       Operation::BVC => {
         if !self.get_flag('V') {
           self.branch(ptr as i16);
@@ -295,30 +526,21 @@ impl Cpu {
       Operation::CLD => self.set_flag('D', 0),
       Operation::CLI => self.set_flag('I', 0),
       Operation::CLV => self.set_flag('V', 0),
-      Operation::CMP => {
-        let result = self.acc.wrapping_sub(data);
-        self.set_flag_with_bool('Z', result == 0);
-        self.set_flag_with_bool('C', self.acc >= data);
-        self.set_flag_with_bool('N', result & 0x80 > 0);
-      }
-      Operation::CPX => {
-        let result = (self.x as u16).wrapping_sub(data as u16);
-        self.set_flag_with_bool('Z', result == 0);
-        self.set_flag_with_bool('C', self.x >= data);
-        self.set_flag_with_bool('N', result & 0x80 > 0);
-      }
-      Operation::CPY => {
-        let result = (self.y as u16).wrapping_sub(data as u16);
-        self.set_flag_with_bool('Z', result == 0);
-        self.set_flag_with_bool('C', self.y >= data);
-        self.set_flag_with_bool('N', result & 0x80 > 0);
-      }
-      Operation::DEC => {
-        let result = data.wrapping_sub(1);
-        self.set_flag_with_bool('Z', result == 0);
-        self.set_flag_with_bool('N', (result & 0x80) > 0);
-        self.write_addr(ptr, result & 0x00FF);
-      }
+      Operation::CMP => self.compare(self.acc, data),
+      Operation::CPX => self.compare(self.x, data),
+      Operation::CPY => self.compare(self.y, data),
+      Operation::DEC => match address_mode {
+        &AddressMode::Accumulator => {
+          self.acc = self.acc.wrapping_sub(1);
+          self.set_zero_and_negative(self.acc);
+        }
+        _ => {
+          let result = data.wrapping_sub(1);
+          self.set_flag_with_bool('Z', result == 0);
+          self.set_flag_with_bool('N', (result & 0x80) > 0);
+          self.write(ptr, result & 0x00FF);
+        }
+      },
       Operation::DEX => {
         let result = self.x.wrapping_sub(1);
         self.set_flag_with_bool('Z', result == 0);
@@ -337,12 +559,18 @@ impl Cpu {
         self.set_flag_with_bool('N', (result & 0x80) > 0);
         self.acc = result & 0x00FF;
       }
-      Operation::INC => {
-        let result = data.wrapping_add(1);
-        self.set_flag_with_bool('Z', result == 0);
-        self.set_flag_with_bool('N', (result & 0x80) > 0);
-        self.write_addr(ptr, result & 0x00FF);
-      }
+      Operation::INC => match address_mode {
+        &AddressMode::Accumulator => {
+          self.acc = self.acc.wrapping_add(1);
+          self.set_zero_and_negative(self.acc);
+        }
+        _ => {
+          let result = data.wrapping_add(1);
+          self.set_flag_with_bool('Z', result == 0);
+          self.set_flag_with_bool('N', (result & 0x80) > 0);
+          self.write(ptr, result & 0x00FF);
+        }
+      },
       Operation::INX => {
         let result = self.x.wrapping_add(1);
         self.set_flag_with_bool('Z', result == 0);
@@ -377,27 +605,13 @@ impl Cpu {
         self.y = data
       }
       Operation::LSR => match address_mode {
-        &AddressMode::Accumulator => {
-          let result = self.acc >> 1;
-          self.set_flag_with_bool('C', (self.acc & 0x01) > 0);
-          self.set_flag('N', 0);
-          self.set_flag_with_bool('Z', result == 0);
-          self.acc = result;
-        }
+        &AddressMode::Accumulator => self.acc = self.shift_right(self.acc),
         _ => {
-          let result = data >> 1;
-          self.set_flag_with_bool('C', (data & 0x01) > 0);
-          self.set_flag('N', 0);
-          self.set_flag_with_bool('Z', result == 0);
-          self.write_addr(ptr, result);
+          let result = self.shift_right(data);
+          self.write(ptr, result);
         }
       },
-      Operation::NOP => {
-        match self.opcode {
-          0xFC => self.cycles += 1,
-          _ => (),
-        };
-      }
+      Operation::NOP => (),
       Operation::ORA => {
         let result = self.acc | data;
         self.set_flag_with_bool('Z', result == 0);
@@ -405,101 +619,188 @@ impl Cpu {
         self.acc = result;
       }
       Operation::PHA => self.stack_push(self.acc),
-      Operation::PHP => self.stack_push(self.status),
+      // B only exists on the stack, it is set when pushed by PHP or BRK
+      Operation::PHP => self.stack_push(self.status | STATUS_BREAK | STATUS_UNUSED),
       Operation::PLA => {
         let result = self.stack_pop();
         self.set_flag_with_bool('Z', result == 0);
         self.set_flag_with_bool('N', (result & 0x80) > 0);
         self.acc = result;
       }
-      Operation::PLP => self.status = self.stack_pop(),
+      Operation::PLP => self.status = (self.stack_pop() & !STATUS_BREAK) | STATUS_UNUSED,
       Operation::ROL => match address_mode {
-        &AddressMode::Accumulator => {
-          let result = (self.acc as u16) << 1 | self.get_flag('C') as u16;
-          self.set_flag_with_bool('C', result & 0xFF00 > 0);
-          self.set_flag_with_bool('Z', result == 0);
-          self.set_flag_with_bool('N', result & 0x0080 > 0);
-          self.acc = (result & 0x00FF) as u8
-        }
+        &AddressMode::Accumulator => self.acc = self.rotate_left(self.acc),
         _ => {
-          let result = (data as u16) << 1 | self.get_flag('C') as u16;
-          self.set_flag_with_bool('C', result & 0xFF00 > 0);
-          self.set_flag_with_bool('Z', result == 0);
-          self.set_flag_with_bool('N', result & 0x0080 > 0);
-          self.write_addr(ptr, (result & 0x00FF) as u8)
+          let result = self.rotate_left(data);
+          self.write(ptr, result)
         }
       },
       Operation::ROR => match address_mode {
-        &AddressMode::Accumulator => {
-          let result = ((self.get_flag('C') as u8) << 7) | (self.acc >> 1);
-          self.set_flag_with_bool('C', data & 0x01 > 0);
-          self.set_flag_with_bool('Z', result == 0);
-          self.set_flag_with_bool('N', result & 0x80 > 0);
-          self.acc = result;
-        }
+        &AddressMode::Accumulator => self.acc = self.rotate_right(self.acc),
         _ => {
-          let result = ((self.get_flag('C') as u8) << 7) | (data >> 1);
-          self.set_flag_with_bool('C', data & 0x01 > 0);
-          self.set_flag_with_bool('Z', result == 0);
-          self.set_flag_with_bool('N', result & 0x80 > 0);
-          self.write_addr(ptr, result)
+          let result = self.rotate_right(data);
+          self.write(ptr, result)
         }
       },
       Operation::RTI => {
-        self.status = self.stack_pop();
-        self.set_flag('B', 0);
-        self.set_flag('U', 0);
-        self.set_flag('I', 0);
+        self.status = (self.stack_pop() & !STATUS_BREAK) | STATUS_UNUSED;
         self.pc = self.pop_address_from_stack();
       }
       Operation::RTS => {
         self.pc = self.pop_address_from_stack();
         self.pc = self.pc.wrapping_add(1);
       }
-      Operation::SBC => {
-        let inverted_data = !data;
-        let result_16 = (self.acc as u16) + (inverted_data as u16) + (self.get_flag('C') as u16);
-        self.set_flag_with_bool('C', result_16 > 0xFF);
-
-        let result = result_16 as u8;
-        self.set_flag_with_bool('Z', result == 0);
-        self.set_flag_with_bool(
-          'V',
-          (self.acc ^ result & !(self.acc ^ inverted_data)) & 0x80 > 0,
-        );
-        self.set_flag_with_bool('N', result & 0x80 > 0);
-        self.acc = (result & 0xFF) as u8;
-      }
+      Operation::SBC => self.subtract_with_borrow(data),
       Operation::SEC => self.set_flag('C', 1),
       Operation::SED => self.set_flag('D', 1),
       Operation::SEI => self.set_flag('I', 1),
-      Operation::STA => self.write_addr(ptr, self.acc),
-      Operation::STX => self.write_addr(ptr, self.x),
-      Operation::STY => self.write_addr(ptr, self.y),
-      Operation::TAX => self.x = self.acc,
-      Operation::TAY => self.y = self.acc,
-      Operation::TSX => self.x = self.stkp,
-      Operation::TXA => self.acc = self.x,
+      Operation::STA => self.write(ptr, self.acc),
+      Operation::STX => self.write(ptr, self.x),
+      Operation::STY => self.write(ptr, self.y),
+      Operation::TAX => {
+        self.x = self.acc;
+        self.set_zero_and_negative(self.x);
+      }
+      Operation::TAY => {
+        self.y = self.acc;
+        self.set_zero_and_negative(self.y);
+      }
+      Operation::TSX => {
+        self.x = self.stkp;
+        self.set_zero_and_negative(self.x);
+      }
+      Operation::TXA => {
+        self.acc = self.x;
+        self.set_zero_and_negative(self.acc);
+      }
       Operation::TXS => self.stkp = self.x,
-      Operation::TYA => self.acc = self.y,
-      Operation::XXX => (),
-    }
-    match address_mode {
-      AddressMode::Immediate => {
-        self.pc = self.pc.wrapping_add(1);
+      Operation::TYA => {
+        self.acc = self.y;
+        self.set_zero_and_negative(self.acc);
       }
-      _ => (),
-    };
+      // Unofficial operations combine two official ones, sharing the same data
+      Operation::ALR => {
+        self.acc = self.shift_right(self.acc & data);
+      }
+      Operation::ANC => {
+        self.acc &= data;
+        self.set_zero_and_negative(self.acc);
+        self.set_flag_with_bool('C', self.acc & 0x80 > 0);
+      }
+      Operation::ARR => {
+        let carry = (self.get_flag('C') as u8) << 7;
+        self.acc = ((self.acc & data) >> 1) | carry;
+        self.set_zero_and_negative(self.acc);
+        // C and V come from bits 6 and 5 of the result, as if it had been added to itself
+        self.set_flag_with_bool('C', self.acc & 0x40 > 0);
+        self.set_flag_with_bool('V', ((self.acc >> 6) ^ (self.acc >> 5)) & 0x01 > 0);
+      }
+      Operation::AXS => {
+        let value = self.acc & self.x;
+        self.set_flag_with_bool('C', value >= data);
+        self.x = value.wrapping_sub(data);
+        self.set_zero_and_negative(self.x);
+      }
+      Operation::DCP => {
+        let result = data.wrapping_sub(1);
+        self.write(ptr, result);
+        self.compare(self.acc, result);
+      }
+      Operation::ISC => {
+        let result = data.wrapping_add(1);
+        self.write(ptr, result);
+        self.subtract_with_borrow(result);
+      }
+      Operation::LAS => {
+        let result = data & self.stkp;
+        self.acc = result;
+        self.x = result;
+        self.stkp = result;
+        self.set_zero_and_negative(result);
+      }
+      Operation::LAX => {
+        self.acc = data;
+        self.x = data;
+        self.set_zero_and_negative(data);
+      }
+      Operation::RLA => {
+        let result = self.rotate_left(data);
+        self.write(ptr, result);
+        self.acc &= result;
+        self.set_zero_and_negative(self.acc);
+      }
+      Operation::RRA => {
+        let result = self.rotate_right(data);
+        self.write(ptr, result);
+        self.add_with_carry(result);
+      }
+      Operation::SAX => self.write(ptr, self.acc & self.x),
+      Operation::SLO => {
+        let result = self.shift_left(data);
+        self.write(ptr, result);
+        self.acc |= result;
+        self.set_zero_and_negative(self.acc);
+      }
+      Operation::SRE => {
+        let result = self.shift_right(data);
+        self.write(ptr, result);
+        self.acc ^= result;
+        self.set_zero_and_negative(self.acc);
+      }
+      // The unstable operations depend on analog effects that differ between chips. ANE and LXA
+      // use the magic constants most often measured on NES consoles, 0xEE and 0xFF
+      Operation::ANE => {
+        self.acc = (self.acc | ANE_MAGIC) & self.x & data;
+        self.set_zero_and_negative(self.acc);
+      }
+      Operation::LXA => {
+        let result = (self.acc | LXA_MAGIC) & data;
+        self.acc = result;
+        self.x = result;
+        self.set_zero_and_negative(result);
+      }
+      // The stores AND the value with the high byte of the base address plus one. When indexing
+      // crosses a page, that value also replaces the high byte of the address written to
+      Operation::SHA => self.store_high_byte_and(ptr, self.acc & self.x, self.y),
+      Operation::SHX => self.store_high_byte_and(ptr, self.x, self.y),
+      Operation::SHY => self.store_high_byte_and(ptr, self.y, self.x),
+      Operation::TAS => {
+        self.stkp = self.acc & self.x;
+        self.store_high_byte_and(ptr, self.stkp, self.y);
+      }
+      Operation::JAM => self.jammed = true,
+      // 65C02
+      Operation::BRA => self.branch(ptr as i16),
+      Operation::PHX => self.stack_push(self.x),
+      Operation::PHY => self.stack_push(self.y),
+      Operation::PLX => {
+        self.x = self.stack_pop();
+        self.set_zero_and_negative(self.x);
+      }
+      Operation::PLY => {
+        self.y = self.stack_pop();
+        self.set_zero_and_negative(self.y);
+      }
+      Operation::STZ => self.write(ptr, 0),
+      Operation::TRB => {
+        self.set_flag_with_bool('Z', self.acc & data == 0);
+        self.write(ptr, data & !self.acc);
+      }
+      Operation::TSB => {
+        self.set_flag_with_bool('Z', self.acc & data == 0);
+        self.write(ptr, data | self.acc);
+      }
+    }
   }
 
   fn stack_push(&mut self, data: u8) {
-    self.write_addr(STACK_BASE_ADDR + (self.stkp as u16), data);
-    self.stkp -= 1;
+    self.write(STACK_BASE_ADDR + (self.stkp as u16), data);
+    self.stkp = self.stkp.wrapping_sub(1);
   }
 
   fn stack_pop(&mut self) -> u8 {
-    self.stkp += 1;
-    self.read_addr(STACK_BASE_ADDR + (self.stkp as u16))
+    self.stkp = self.stkp.wrapping_add(1);
+    self.read(STACK_BASE_ADDR + (self.stkp as u16))
   }
 
   fn push_pc_to_stack(&mut self) {
@@ -513,92 +814,486 @@ impl Cpu {
   }
 
   fn branch(&mut self, offset: i16) {
-    self.cycles += 1;
-    let destination = self.pc.wrapping_add(offset as u16);
-    // TODO: Instruction C85A is only incrementing once instead of twice in the nestest.nes tests
-    if destination & 0xFF00 != self.pc & 0xFF00 {
-      self.cycles += 1
-    }
-    self.pc = destination;
-  }
-
-  fn get_data_ptr(&mut self, addressing_mode: &AddressMode) -> u16 {
-    match addressing_mode {
-      AddressMode::Absolute => self.get_byte_at_pc(),
-      AddressMode::AbsoluteIndirect => {
-        // Read contents of next two memory locations
-        // containing lo byte, then hi byte of pointer to data
-        let ptr = self.get_byte_at_pc();
-
-        // SIMULATE PAGE BOUNDARY HARDWARE BUG
-        if (ptr & 0x00FF) == 0xFF {
-          let lo_pc_addr = self.read_addr(ptr) as u16;
-          let hi_pc_addr = self.read_addr(ptr & 0xFF00) as u16;
-          (hi_pc_addr << 8) | lo_pc_addr
-        } else {
-          let lo_pc_addr = self.read_addr(ptr) as u16;
-          let hi_pc_addr = self.read_addr(ptr + 1) as u16;
-          (hi_pc_addr << 8) | lo_pc_addr
+    self.pc = self.pc.wrapping_add(offset as u16);
+  }
+
+  fn is_branch_taken(&self, operation: &Operation) -> bool {
+    match operation {
+      Operation::BCC => !self.get_flag('C'),
+      Operation::BCS => self.get_flag('C'),
+      Operation::BEQ => self.get_flag('Z'),
+      Operation::BMI => self.get_flag('N'),
+      Operation::BNE => !self.get_flag('Z'),
+      Operation::BPL => !self.get_flag('N'),
+      Operation::BVC => !self.get_flag('V'),
+      Operation::BVS => self.get_flag('V'),
+      Operation::BRA => true,
+      _ => false,
+    }
+  }
+
+  // Queue the cycles of the instruction whose opcode was just fetched
+  fn start_instruction(&mut self) {
+    let instruction = decode(self.opcode, self.variant);
+    let operation = instruction.get_operation();
+    let address_mode = instruction.get_address_mode();
+    self.page_crossed = false;
+
+    let mut micro_ops = match operation {
+      Operation::BRK => {
+        self.pointer = BRK_ADDR_BEGIN;
+        vec![
+          MicroOp::DummyFetch,
+          MicroOp::PushPch,
+          MicroOp::PushPcl,
+          MicroOp::PushStatusBreak,
+          MicroOp::FetchVectorLo,
+          MicroOp::FetchVectorHi,
+          MicroOp::Jump,
+        ]
+      }
+      Operation::JSR => vec![
+        MicroOp::FetchAddrLo,
+        MicroOp::DummyReadStack,
+        MicroOp::PushPch,
+        MicroOp::PushPcl,
+        MicroOp::JumpToSubroutine,
+      ],
+      Operation::RTI => vec![
+        MicroOp::DummyReadPc,
+        MicroOp::DummyReadStack,
+        MicroOp::PullStatus,
+        MicroOp::PullPcl,
+        MicroOp::PullPch,
+      ],
+      Operation::RTS => vec![
+        MicroOp::DummyReadPc,
+        MicroOp::DummyReadStack,
+        MicroOp::PullPcl,
+        MicroOp::PullPch,
+        MicroOp::DummyFetch,
+      ],
+      Operation::PHA | Operation::PHP | Operation::PHX | Operation::PHY => {
+        vec![MicroOp::DummyReadPc, MicroOp::Operate]
+      }
+      Operation::PLA | Operation::PLP | Operation::PLX | Operation::PLY => vec![
+        MicroOp::DummyReadPc,
+        MicroOp::DummyReadStack,
+        MicroOp::Operate,
+      ],
+      Operation::JMP => {
+        let mut micro_ops = self.addressing_micro_ops(address_mode);
+        micro_ops.push(MicroOp::Jump);
+        micro_ops
+      }
+      _ => {
+        let mut micro_ops = self.addressing_micro_ops(address_mode);
+        match address_mode {
+          AddressMode::Implied | AddressMode::Accumulator => micro_ops.push(MicroOp::Implied),
+          AddressMode::Relative => {
+            if self.is_branch_taken(operation) {
+              micro_ops.extend(&[MicroOp::BranchTaken, MicroOp::BranchFix]);
+            }
+          }
+          _ => micro_ops.extend(self.access_micro_ops(operation, address_mode)),
         }
+        micro_ops
       }
-      AddressMode::AbsoluteX => {
-        let addr = self.get_byte_at_pc();
-        let offset_addr = addr.wrapping_add(self.x as u16);
+    };
+    // The 65C02 turns every unused opcode into a NOP, some of them longer than their addressing
+    if self.variant == Variant::Cmos65C02 && matches!(operation, Operation::NOP) {
+      micro_ops.resize(instruction.get_cycles() as usize - 1, MicroOp::Internal);
+    }
+    self.start_sequence(micro_ops);
+  }
 
-        if (offset_addr & HI_BYTE_MASK) != (addr & HI_BYTE_MASK) {
-          // TODO: Need tests for cycle increment
-          self.cycles += 1; // TODO: Is this the right way to do this? Or is it dependant on how the operation is executed
-        }
-        offset_addr
+  // The cycles that work out the address of the operand, leaving it in addr_of_data
+  fn addressing_micro_ops(&mut self, address_mode: &AddressMode) -> Vec<MicroOp> {
+    self.addr_of_data = match address_mode {
+      AddressMode::Immediate => self.pc,
+      _ => 0x0000,
+    };
+    match address_mode {
+      AddressMode::Absolute => vec![MicroOp::FetchAddrLo, MicroOp::FetchAddrHi],
+      AddressMode::AbsoluteIndirect => match self.variant {
+        // The 65C02 spends a cycle fixing the page boundary bug
+        Variant::Cmos65C02 => vec![
+          MicroOp::FetchAddrLo,
+          MicroOp::FetchAddrHi,
+          MicroOp::DummyReadOperand,
+          MicroOp::ReadPointerLo,
+          MicroOp::ReadPointerHi,
+        ],
+        _ => vec![
+          MicroOp::FetchAddrLo,
+          MicroOp::FetchAddrHi,
+          MicroOp::ReadPointerLo,
+          MicroOp::ReadPointerHiPageWrap,
+        ],
+      },
+      AddressMode::AbsoluteIndexedIndirect => vec![
+        MicroOp::FetchAddrLo,
+        MicroOp::FetchAddrHi,
+        MicroOp::IndexPointerX,
+        MicroOp::ReadPointerLo,
+        MicroOp::ReadPointerHi,
+      ],
+      AddressMode::AbsoluteX => vec![MicroOp::FetchAddrLo, MicroOp::FetchAddrHiX],
+      AddressMode::AbsoluteY => vec![MicroOp::FetchAddrLo, MicroOp::FetchAddrHiY],
+      // The operand is in a register, at pc or there is none
+      AddressMode::Accumulator | AddressMode::Immediate | AddressMode::Implied => vec![],
+      AddressMode::IndirectX => vec![
+        MicroOp::FetchAddrLo,
+        MicroOp::IndexZeroPageX,
+        MicroOp::ReadPointerLo,
+        MicroOp::ReadPointerHiZeroPage,
+      ],
+      AddressMode::IndirectY => vec![
+        MicroOp::FetchAddrLo,
+        MicroOp::ReadPointerLo,
+        MicroOp::ReadPointerHiY,
+      ],
+      AddressMode::Relative => vec![MicroOp::FetchOffset],
+      AddressMode::ZeroPage => vec![MicroOp::FetchAddrLo],
+      AddressMode::ZeroPageIndirect => vec![
+        MicroOp::FetchAddrLo,
+        MicroOp::ReadPointerLo,
+        MicroOp::ReadPointerHiZeroPage,
+      ],
+      AddressMode::ZeroPageX => vec![MicroOp::FetchAddrLo, MicroOp::IndexZeroPageX],
+      AddressMode::ZeroPageY => vec![MicroOp::FetchAddrLo, MicroOp::IndexZeroPageY],
+    }
+  }
+
+  // The cycles that read, write or read-modify-write the operand at addr_of_data
+  fn access_micro_ops(&self, operation: &Operation, address_mode: &AddressMode) -> Vec<MicroOp> {
+    let mut micro_ops = Vec::new();
+    if matches!(
+      address_mode,
+      AddressMode::AbsoluteX | AddressMode::AbsoluteY | AddressMode::IndirectY
+    ) {
+      // The 65C02 only fixes the page of shifts and rotates on abs,X when the index crossed it
+      let skips_fix = self.variant == Variant::Cmos65C02
+        && matches!(address_mode, AddressMode::AbsoluteX)
+        && matches!(
+          operation,
+          Operation::ASL | Operation::LSR | Operation::ROL | Operation::ROR
+        );
+      micro_ops.push(match has_page_cross_penalty(operation) || skips_fix {
+        true => MicroOp::FixPageIfCrossed,
+        false => MicroOp::FixPage,
+      });
+    }
+    if is_read_modify_write(operation) {
+      micro_ops.extend(&[MicroOp::ReadData, MicroOp::DummyWrite, MicroOp::Modify]);
+    } else {
+      micro_ops.push(MicroOp::Execute);
+    }
+    if self.variant == Variant::Cmos65C02
+      && self.is_decimal_mode()
+      && matches!(operation, Operation::ADC | Operation::SBC)
+    {
+      micro_ops.push(MicroOp::DecimalCycle);
+    }
+    micro_ops
+  }
+
+  fn start_sequence(&mut self, micro_ops: Vec<MicroOp>) {
+    self.micro_ops = micro_ops;
+    self.micro_op_index = 0;
+    self.skip_micro_ops();
+  }
+
+  fn run_next_micro_op(&mut self) {
+    if let Some(&micro_op) = self.micro_ops.get(self.micro_op_index) {
+      self.micro_op_index += 1;
+      self.run_micro_op(micro_op);
+    }
+  }
+
+  // Run the free micro ops and pass over the ones that are not needed, so the next one takes a
+  // cycle, then count the cycles left
+  fn skip_micro_ops(&mut self) {
+    while let Some(&micro_op) = self.micro_ops.get(self.micro_op_index) {
+      if micro_op == MicroOp::Jump {
+        self.run_micro_op(micro_op);
+      } else if self.is_micro_op_needed(micro_op) {
+        break;
       }
-      AddressMode::AbsoluteY => {
-        let addr = self.get_byte_at_pc();
-        let offset_addr = addr.wrapping_add(self.y as u16);
+      self.micro_op_index += 1;
+    }
+    self.cycles = self.micro_ops[self.micro_op_index..]
+      .iter()
+      .filter(|&&micro_op| micro_op != MicroOp::Jump && self.is_micro_op_needed(micro_op))
+      .count() as u8;
+  }
 
-        if (offset_addr & HI_BYTE_MASK) != (addr & HI_BYTE_MASK) {
-          self.cycles += 1;
-        }
-        offset_addr
+  fn is_micro_op_needed(&self, micro_op: MicroOp) -> bool {
+    match micro_op {
+      MicroOp::FixPageIfCrossed | MicroOp::BranchFix => self.page_crossed,
+      _ => true,
+    }
+  }
+
+  fn run_micro_op(&mut self, micro_op: MicroOp) {
+    match micro_op {
+      MicroOp::FetchAddrLo => {
+        self.pointer = self.read_pc_addr() as u16;
+        self.addr_of_data = self.pointer;
       }
-      // If the address mode is accumulator, it doesn't need an address
-      AddressMode::Accumulator => 0x0000,
-      AddressMode::Immediate => self.pc,
-      // If the address mode is implied, it doesn't need an address
-      AddressMode::Implied => 0x0000,
-      AddressMode::IndirectX => {
-        let offset = self.read_pc_addr();
-        let zero_ptr = self.x.wrapping_add(offset);
-
-        let lo = self.read_addr(zero_ptr as u16) as u16;
-        let hi = self.read_addr(zero_ptr.wrapping_add(1) as u16) as u16;
-        (hi << 8) | lo
-      }
-      AddressMode::IndirectY => {
-        let zero_ptr = self.read_pc_addr();
-        let lo = self.read_addr(zero_ptr as u16) as u16;
-        let hi = self.read_addr(zero_ptr.wrapping_add(1) as u16) as u16;
-        let addr = (hi << 8) | lo;
-        addr.wrapping_add(self.y as u16)
-        // TODO: How to figure out clock cycles for crossing pages here
-        // TODO: Need tests for cycle increment once written
-      }
-      AddressMode::Relative => {
-        let offset = self.read_pc_addr() as u16;
-
-        if offset & 0x80 > 0 {
-          // If offset is negative, make it start 0xFF for binary addition to work
-          offset | 0xFF00
-        } else {
-          // If offset is positive
-          offset
+      MicroOp::FetchAddrHi => {
+        self.pointer |= (self.read_pc_addr() as u16) << 8;
+        self.addr_of_data = self.pointer;
+      }
+      MicroOp::FetchAddrHiX => {
+        self.pointer |= (self.read_pc_addr() as u16) << 8;
+        self.index_pointer(self.x);
+      }
+      MicroOp::FetchAddrHiY => {
+        self.pointer |= (self.read_pc_addr() as u16) << 8;
+        self.index_pointer(self.y);
+      }
+      MicroOp::IndexZeroPageX => {
+        self.read(self.pointer);
+        self.pointer = (self.pointer as u8).wrapping_add(self.x) as u16;
+        self.addr_of_data = self.pointer;
+      }
+      MicroOp::IndexZeroPageY => {
+        self.read(self.pointer);
+        self.pointer = (self.pointer as u8).wrapping_add(self.y) as u16;
+        self.addr_of_data = self.pointer;
+      }
+      MicroOp::IndexPointerX => {
+        self.read(self.pc.wrapping_sub(1));
+        self.pointer = self.pointer.wrapping_add(self.x as u16);
+      }
+      MicroOp::DummyReadOperand => {
+        self.read(self.pc.wrapping_sub(1));
+      }
+      MicroOp::ReadPointerLo => self.addr_of_data = self.read(self.pointer) as u16,
+      MicroOp::ReadPointerHi => {
+        let hi = self.read(self.pointer.wrapping_add(1)) as u16;
+        self.addr_of_data |= hi << 8;
+      }
+      MicroOp::ReadPointerHiZeroPage => {
+        let hi = self.read((self.pointer as u8).wrapping_add(1) as u16) as u16;
+        self.addr_of_data |= hi << 8;
+      }
+      MicroOp::ReadPointerHiPageWrap => {
+        let addr = (self.pointer & HI_BYTE_MASK) | (self.pointer.wrapping_add(1) & LO_BYTE_MASK);
+        let hi = self.read(addr) as u16;
+        self.addr_of_data |= hi << 8;
+      }
+      MicroOp::ReadPointerHiY => {
+        let hi = self.read((self.pointer as u8).wrapping_add(1) as u16) as u16;
+        self.pointer = (hi << 8) | self.addr_of_data;
+        self.index_pointer(self.y);
+      }
+      MicroOp::FixPage | MicroOp::FixPageIfCrossed => {
+        let addr = self.get_unfixed_addr();
+        self.read(addr);
+      }
+      MicroOp::Execute => {
+        let instruction = decode(self.opcode, self.variant);
+        self.perform_operation(
+          self.addr_of_data,
+          instruction.get_operation(),
+          instruction.get_address_mode(),
+        );
+      }
+      MicroOp::ReadData => self.data = self.read(self.addr_of_data),
+      // The 65C02 reads the operand again instead of writing it back
+      MicroOp::DummyWrite => match self.variant {
+        Variant::Cmos65C02 => {
+          self.read(self.addr_of_data);
         }
+        _ => self.write(self.addr_of_data, self.data),
+      },
+      MicroOp::Modify => {
+        let instruction = decode(self.opcode, self.variant);
+        self.operate(
+          self.addr_of_data,
+          self.data,
+          instruction.get_operation(),
+          instruction.get_address_mode(),
+        );
+      }
+      MicroOp::DecimalCycle => {
+        self.read(self.addr_of_data);
+      }
+      MicroOp::Implied => {
+        self.read(self.pc);
+        let instruction = decode(self.opcode, self.variant);
+        self.operate(
+          0x0000,
+          0x00,
+          instruction.get_operation(),
+          instruction.get_address_mode(),
+        );
+      }
+      MicroOp::Operate => {
+        let instruction = decode(self.opcode, self.variant);
+        self.operate(
+          0x0000,
+          0x00,
+          instruction.get_operation(),
+          instruction.get_address_mode(),
+        );
+      }
+      MicroOp::FetchOffset => {
+        // Sign extend the offset so that adding it wraps backwards
+        self.addr_of_data = self.read_pc_addr() as i8 as u16;
+      }
+      MicroOp::BranchTaken => {
+        self.read(self.pc);
+        self.pointer = self.pc.wrapping_add(self.addr_of_data);
+        self.page_crossed = (self.pointer & HI_BYTE_MASK) != (self.pc & HI_BYTE_MASK);
+        self.pc = (self.pc & HI_BYTE_MASK) | (self.pointer & LO_BYTE_MASK);
+      }
+      MicroOp::BranchFix => {
+        self.read(self.pc);
+        self.pc = self.pointer;
+      }
+      MicroOp::DummyReadPc => {
+        self.read(self.pc);
       }
-      AddressMode::ZeroPage => self.read_pc_addr() as u16,
-      AddressMode::ZeroPageX => self.x.wrapping_add(self.read_pc_addr()) as u16,
-      AddressMode::ZeroPageY => self.y.wrapping_add(self.read_pc_addr()) as u16,
-      AddressMode::XXX => 0x0000,
+      MicroOp::DummyFetch => {
+        self.read_pc_addr();
+      }
+      MicroOp::DummyReadStack => {
+        self.read(STACK_BASE_ADDR + (self.stkp as u16));
+      }
+      MicroOp::PushPch => self.stack_push((self.pc >> 8) as u8),
+      MicroOp::PushPcl => self.stack_push(self.pc as u8),
+      MicroOp::PushStatus => {
+        self.hijack_vector();
+        self.stack_push((self.status & !STATUS_BREAK) | STATUS_UNUSED);
+        self.set_flag('I', 1);
+        self.clear_decimal_on_interrupt();
+      }
+      MicroOp::PushStatusBreak => {
+        self.hijack_vector();
+        self.stack_push(self.status | STATUS_BREAK | STATUS_UNUSED);
+        self.set_flag('I', 1);
+        self.clear_decimal_on_interrupt();
+      }
+      MicroOp::PullStatus => {
+        self.status = (self.stack_pop() & !STATUS_BREAK) | STATUS_UNUSED;
+      }
+      MicroOp::PullPcl => {
+        self.pc = (self.pc & HI_BYTE_MASK) | self.stack_pop() as u16;
+      }
+      MicroOp::PullPch => {
+        self.pc = (self.pc & LO_BYTE_MASK) | (self.stack_pop() as u16) << 8;
+      }
+      MicroOp::JumpToSubroutine => {
+        let hi = self.read(self.pc) as u16;
+        self.pc = (hi << 8) | (self.pointer & LO_BYTE_MASK);
+      }
+      MicroOp::ResetStack => {
+        self.read(STACK_BASE_ADDR + (self.stkp as u16));
+        self.stkp = self.stkp.wrapping_sub(1);
+      }
+      MicroOp::FetchVectorLo => {
+        self.addr_of_data = self.read(self.pointer) as u16;
+        self.nmi_polled = false;
+        self.irq_polled = false;
+      }
+      MicroOp::FetchVectorHi => {
+        let hi = self.read(self.pointer.wrapping_add(1)) as u16;
+        self.addr_of_data |= hi << 8;
+      }
+      MicroOp::Internal => (),
+      MicroOp::Jump => self.pc = self.addr_of_data,
+    }
+  }
+
+  // The NMOS chips read the address before the carry reached its high byte, the 65C02 reads the
+  // last operand byte again
+  fn get_unfixed_addr(&self) -> u16 {
+    match self.variant {
+      Variant::Cmos65C02 => self.pc.wrapping_sub(1),
+      _ => (self.pointer & HI_BYTE_MASK) | (self.addr_of_data & LO_BYTE_MASK),
     }
   }
+
+  // The address the next cycle reads, or None when it writes or makes no access
+  fn get_next_read_addr(&self) -> Option<u16> {
+    let micro_op = match self.micro_ops.get(self.micro_op_index) {
+      Some(&micro_op) if !self.is_cycle_complete() => micro_op,
+      // An opcode fetch or the first cycle of an interrupt
+      _ => return Some(self.pc),
+    };
+    let stack_addr = STACK_BASE_ADDR + self.stkp as u16;
+    let pull_addr = STACK_BASE_ADDR + self.stkp.wrapping_add(1) as u16;
+    let instruction = decode(self.opcode, self.variant);
+    let operation = instruction.get_operation();
+    match micro_op {
+      MicroOp::FetchAddrLo
+      | MicroOp::FetchAddrHi
+      | MicroOp::FetchAddrHiX
+      | MicroOp::FetchAddrHiY
+      | MicroOp::Implied
+      | MicroOp::FetchOffset
+      | MicroOp::BranchTaken
+      | MicroOp::BranchFix
+      | MicroOp::DummyReadPc
+      | MicroOp::DummyFetch
+      | MicroOp::JumpToSubroutine => Some(self.pc),
+      MicroOp::IndexZeroPageX
+      | MicroOp::IndexZeroPageY
+      | MicroOp::ReadPointerLo
+      | MicroOp::FetchVectorLo => Some(self.pointer),
+      MicroOp::IndexPointerX | MicroOp::DummyReadOperand => Some(self.pc.wrapping_sub(1)),
+      MicroOp::ReadPointerHi | MicroOp::FetchVectorHi => Some(self.pointer.wrapping_add(1)),
+      MicroOp::ReadPointerHiZeroPage | MicroOp::ReadPointerHiY => {
+        Some((self.pointer as u8).wrapping_add(1) as u16)
+      }
+      MicroOp::ReadPointerHiPageWrap => {
+        Some((self.pointer & HI_BYTE_MASK) | (self.pointer.wrapping_add(1) & LO_BYTE_MASK))
+      }
+      MicroOp::FixPage | MicroOp::FixPageIfCrossed => Some(self.get_unfixed_addr()),
+      MicroOp::Execute if is_store(operation) => None,
+      MicroOp::Execute | MicroOp::ReadData | MicroOp::DecimalCycle => Some(self.addr_of_data),
+      MicroOp::DummyWrite => match self.variant {
+        Variant::Cmos65C02 => Some(self.addr_of_data),
+        _ => None,
+      },
+      MicroOp::Operate => match operation {
+        Operation::PHA | Operation::PHP | Operation::PHX | Operation::PHY => None,
+        _ => Some(pull_addr),
+      },
+      MicroOp::DummyReadStack | MicroOp::ResetStack => Some(stack_addr),
+      MicroOp::PullStatus | MicroOp::PullPcl | MicroOp::PullPch => Some(pull_addr),
+      MicroOp::Modify
+      | MicroOp::PushPch
+      | MicroOp::PushPcl
+      | MicroOp::PushStatus
+      | MicroOp::PushStatusBreak
+      | MicroOp::Internal
+      | MicroOp::Jump => None,
+    }
+  }
+
+  // Add an index to the base address in pointer, noting when it crosses a page
+  fn index_pointer(&mut self, index: u8) {
+    self.addr_of_data = self.pointer.wrapping_add(index as u16);
+    self.page_crossed = (self.addr_of_data & HI_BYTE_MASK) != (self.pointer & HI_BYTE_MASK);
+  }
+
+  // Runs the addressing cycles back to back and returns the address they work out
+  #[cfg(test)]
+  fn get_data_ptr(&mut self, address_mode: &AddressMode) -> u16 {
+    for micro_op in self.addressing_micro_ops(address_mode) {
+      self.run_micro_op(micro_op);
+    }
+    self.addr_of_data
+  }
+  // The address of the JAM opcode is left in ppc
+  pub fn is_jammed(&self) -> bool {
+    self.jammed
+  }
+
   // Returns if current cycle is complete
   pub fn is_cycle_complete(&self) -> bool {
     self.cycles == 0
@@ -638,6 +1333,159 @@ impl Cpu {
     }
   }
 
+  fn is_decimal_mode(&self) -> bool {
+    self.variant != Variant::Ricoh2A03 && self.get_flag('D')
+  }
+
+  fn clear_decimal_on_interrupt(&mut self) {
+    if self.variant == Variant::Cmos65C02 {
+      self.set_flag('D', 0);
+    }
+  }
+
+  fn add_with_carry(&mut self, data: u8) {
+    if self.is_decimal_mode() {
+      return match self.variant {
+        Variant::Cmos65C02 => self.add_decimal_cmos(data),
+        _ => self.add_decimal(data),
+      };
+    }
+    self.add_binary(data);
+  }
+
+  fn subtract_with_borrow(&mut self, data: u8) {
+    if self.is_decimal_mode() {
+      return match self.variant {
+        Variant::Cmos65C02 => self.subtract_decimal_cmos(data),
+        _ => self.subtract_decimal(data),
+      };
+    }
+    // Subtraction adds the ones' complement, carry is the inverted borrow
+    self.add_binary(!data);
+  }
+
+  fn add_binary(&mut self, data: u8) {
+    let result_16 = (self.acc as u16) + (data as u16) + (self.get_flag('C') as u16);
+    self.set_flag_with_bool('C', result_16 > 0xFF);
+
+    let result = result_16 as u8;
+    self.set_flag_with_bool('V', ((self.acc ^ result) & !(self.acc ^ data)) & 0x80 > 0);
+    self.set_zero_and_negative(result);
+    self.acc = result;
+  }
+
+  // Source: http://www.6502.org/tutorials/decimal_mode.html, appendix A
+  // The NMOS 6502 sets Z from the binary sum, and N and V from the sum before the high digit
+  // is adjusted
+  fn add_decimal(&mut self, data: u8) {
+    let carry = self.get_flag('C') as u16;
+    let binary = (self.acc as u16 + data as u16 + carry) as u8;
+
+    let mut lo = (self.acc & 0x0F) as u16 + (data & 0x0F) as u16 + carry;
+    if lo >= 0x0A {
+      lo = ((lo + 0x06) & 0x0F) + 0x10;
+    }
+    let mut result = (self.acc & 0xF0) as u16 + (data & 0xF0) as u16 + lo;
+    let signed = (self.acc & 0xF0) as i8 as i16 + (data & 0xF0) as i8 as i16 + lo as i16;
+    self.set_flag_with_bool('N', result & 0x80 > 0);
+    self.set_flag_with_bool('V', !(-128..=127).contains(&signed));
+    if result >= 0xA0 {
+      result += 0x60;
+    }
+
+    self.set_flag_with_bool('C', result > 0xFF);
+    self.set_flag_with_bool('Z', binary == 0);
+    self.acc = result as u8;
+  }
+
+  // Every flag comes from the binary subtraction, only the accumulator is adjusted
+  fn subtract_decimal(&mut self, data: u8) {
+    let borrow = 1 - self.get_flag('C') as i16;
+
+    let mut lo = (self.acc & 0x0F) as i16 - (data & 0x0F) as i16 - borrow;
+    if lo < 0 {
+      lo = ((lo - 0x06) & 0x0F) - 0x10;
+    }
+    let mut result = (self.acc & 0xF0) as i16 - (data & 0xF0) as i16 + lo;
+    if result < 0 {
+      result -= 0x60;
+    }
+
+    self.add_binary(!data);
+    self.acc = result as u8;
+  }
+
+  // The 65C02 spends an extra cycle to set N and Z from the decimal result
+  fn add_decimal_cmos(&mut self, data: u8) {
+    self.add_decimal(data);
+    self.set_zero_and_negative(self.acc);
+  }
+
+  fn subtract_decimal_cmos(&mut self, data: u8) {
+    let borrow = 1 - self.get_flag('C') as i16;
+
+    let lo = (self.acc & 0x0F) as i16 - (data & 0x0F) as i16 - borrow;
+    let mut result = self.acc as i16 - data as i16 - borrow;
+    if result < 0 {
+      result -= 0x60;
+    }
+    if lo < 0 {
+      result -= 0x06;
+    }
+
+    self.add_binary(!data);
+    self.acc = result as u8;
+    self.set_zero_and_negative(self.acc);
+  }
+
+  fn compare(&mut self, register: u8, data: u8) {
+    self.set_flag_with_bool('C', register >= data);
+    self.set_zero_and_negative(register.wrapping_sub(data));
+  }
+
+  fn shift_left(&mut self, value: u8) -> u8 {
+    let result = value << 1;
+    self.set_flag_with_bool('C', value & 0x80 > 0);
+    self.set_zero_and_negative(result);
+    result
+  }
+
+  fn shift_right(&mut self, value: u8) -> u8 {
+    let result = value >> 1;
+    self.set_flag_with_bool('C', value & 0x01 > 0);
+    self.set_zero_and_negative(result);
+    result
+  }
+
+  fn rotate_left(&mut self, value: u8) -> u8 {
+    let result = (value << 1) | self.get_flag('C') as u8;
+    self.set_flag_with_bool('C', value & 0x80 > 0);
+    self.set_zero_and_negative(result);
+    result
+  }
+
+  fn rotate_right(&mut self, value: u8) -> u8 {
+    let result = ((self.get_flag('C') as u8) << 7) | (value >> 1);
+    self.set_flag_with_bool('C', value & 0x01 > 0);
+    self.set_zero_and_negative(result);
+    result
+  }
+
+  fn store_high_byte_and(&mut self, addr: u16, value: u8, index: u8) {
+    let base = addr.wrapping_sub(index as u16);
+    let result = value & ((base >> 8) as u8).wrapping_add(1);
+    let addr = match self.page_crossed {
+      true => ((result as u16) << 8) | (addr & LO_BYTE_MASK),
+      false => addr,
+    };
+    self.write(addr, result);
+  }
+
+  fn set_zero_and_negative(&mut self, value: u8) {
+    self.set_flag_with_bool('Z', value == 0);
+    self.set_flag_with_bool('N', value & 0x80 > 0);
+  }
+
   fn set_flag_true(&mut self, flag: u8) {
     self.status |= 0b1 << (flag - 1)
   }
@@ -650,21 +1498,19 @@ impl Cpu {
   }
 
   fn read_pc_addr(&mut self) -> u8 {
-    let result = self.read_addr(self.pc);
+    let result = self.read(self.pc);
     self.pc = self.pc.wrapping_add(1);
     result
   }
 
-  fn get_byte_at_pc(&mut self) -> u16 {
-    let lo = self.read_pc_addr() as u16;
-    let hi = self.read_pc_addr() as u16;
-    (hi << 8) | lo
+  // A read the bus and its devices see, as opposed to inspecting memory with read_addr
+  fn read(&mut self, addr: u16) -> u8 {
+    self.bus.read(addr)
   }
 
-  fn read_addr_from(&self, addr: u16) -> u16 {
-    let lo = self.read_addr(addr) as u16;
-    let hi = self.read_addr(addr.wrapping_add(1)) as u16;
-    (hi << 8) | lo
+  // A write the access handler sees, as opposed to poking memory with write_addr
+  fn write(&mut self, addr: u16, data: u8) {
+    self.bus.write(addr, data)
   }
 }
 
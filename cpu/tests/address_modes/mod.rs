@@ -1,3 +1,6 @@
+#[cfg(test)]
+use bus::Bus;
+
 #[cfg(test)]
 use cpu::instruction::AddressMode;
 
@@ -16,7 +19,7 @@ mod absolute {
   const ADDR_MODE: &AddressMode = &AddressMode::Absolute;
   #[test]
   fn ptr_retrieved_from_memory_at_pc_addr() {
-    let mut cpu = Cpu::new();
+    let mut cpu = Cpu::with_bus(Bus::flat());
     cpu.pc = ROOT;
     cpu.write_addr(ROOT + 0, 0xED);
     cpu.write_addr(ROOT + 1, 0xCD);
@@ -31,7 +34,7 @@ mod accumulator {
   const ADDR_MODE: &AddressMode = &AddressMode::Accumulator;
   #[test]
   fn ptr_should_be_zero_since_unnecessary() {
-    let mut cpu = Cpu::new();
+    let mut cpu = Cpu::with_bus(Bus::flat());
     cpu.acc = 0xDE;
     cpu.pc = ROOT + 0x3000;
     let ptr = cpu.get_data_ptr(ADDR_MODE);
@@ -45,7 +48,7 @@ mod implied {
   const ADDR_MODE: &AddressMode = &AddressMode::Implied;
   #[test]
   fn ptr_should_be_zero_since_unnecessary() {
-    let mut cpu = Cpu::new();
+    let mut cpu = Cpu::with_bus(Bus::flat());
     cpu.acc = 0xDE;
     cpu.pc = 0xFBCD;
     let ptr = cpu.get_data_ptr(ADDR_MODE);
@@ -59,7 +62,7 @@ mod immediate {
   const ADDR_MODE: &AddressMode = &AddressMode::Immediate;
   #[test]
   fn ptr_should_be_equal_to_pc() {
-    let mut cpu = Cpu::new();
+    let mut cpu = Cpu::with_bus(Bus::flat());
     cpu.pc = ROOT;
     cpu.write_addr(ROOT, 0xFC);
     let ptr = cpu.get_data_ptr(ADDR_MODE);
@@ -73,7 +76,7 @@ mod zero_page {
   const ADDR_MODE: &AddressMode = &AddressMode::ZeroPage;
   #[test]
   fn ptr_should_be_zero_page() {
-    let mut cpu = Cpu::new();
+    let mut cpu = Cpu::with_bus(Bus::flat());
     cpu.pc = ROOT;
     cpu.write_addr(ROOT, 0xFC);
     let ptr = cpu.get_data_ptr(ADDR_MODE);
@@ -81,7 +84,7 @@ mod zero_page {
   }
   #[test]
   fn ptr_lo_bit_should_be_data_from_pc_addr() {
-    let mut cpu = Cpu::new();
+    let mut cpu = Cpu::with_bus(Bus::flat());
     cpu.pc = ROOT;
     cpu.write_addr(ROOT, 0xFC);
     let ptr = cpu.get_data_ptr(ADDR_MODE);
@@ -95,7 +98,7 @@ mod zero_page_x {
   const ADDR_MODE: &AddressMode = &AddressMode::ZeroPageX;
   #[test]
   fn ptr_should_be_zero_page() {
-    let mut cpu = Cpu::new();
+    let mut cpu = Cpu::with_bus(Bus::flat());
     cpu.pc = ROOT;
     cpu.write_addr(ROOT, 0xFC);
     let ptr = cpu.get_data_ptr(ADDR_MODE);
@@ -103,7 +106,7 @@ mod zero_page_x {
   }
   #[test]
   fn ptr_should_be_sum_of_x_register_and_data_at_pc_addr() {
-    let mut cpu = Cpu::new();
+    let mut cpu = Cpu::with_bus(Bus::flat());
     cpu.pc = ROOT;
     cpu.x = 0x01;
     cpu.write_addr(ROOT, 0xFC);
@@ -112,7 +115,7 @@ mod zero_page_x {
   }
   #[test]
   fn ptr_should_not_cross_pages_on_overflow() {
-    let mut cpu = Cpu::new();
+    let mut cpu = Cpu::with_bus(Bus::flat());
     cpu.pc = ROOT;
     cpu.x = 0x04;
     cpu.write_addr(ROOT, 0xFC);
@@ -121,7 +124,7 @@ mod zero_page_x {
   }
   #[test]
   fn ptr_should_wrap_on_overflow() {
-    let mut cpu = Cpu::new();
+    let mut cpu = Cpu::with_bus(Bus::flat());
     cpu.pc = ROOT;
     cpu.x = 0x05;
     cpu.write_addr(ROOT, 0xFC);
@@ -136,7 +139,7 @@ mod zero_page_y {
   const ADDR_MODE: &AddressMode = &AddressMode::ZeroPageY;
   #[test]
   fn ptr_should_be_zero_page() {
-    let mut cpu = Cpu::new();
+    let mut cpu = Cpu::with_bus(Bus::flat());
     cpu.pc = ROOT;
     cpu.write_addr(ROOT, 0xFC);
     let ptr = cpu.get_data_ptr(ADDR_MODE);
@@ -144,7 +147,7 @@ mod zero_page_y {
   }
   #[test]
   fn ptr_should_be_sum_of_y_register_and_data_at_pc_addr() {
-    let mut cpu = Cpu::new();
+    let mut cpu = Cpu::with_bus(Bus::flat());
     cpu.pc = ROOT;
     cpu.y = 0x01;
     cpu.write_addr(ROOT, 0xFC);
@@ -153,7 +156,7 @@ mod zero_page_y {
   }
   #[test]
   fn ptr_should_not_cross_pages_on_overflow() {
-    let mut cpu = Cpu::new();
+    let mut cpu = Cpu::with_bus(Bus::flat());
     cpu.pc = ROOT;
     cpu.y = 0x04;
     cpu.write_addr(ROOT, 0xFC);
@@ -162,7 +165,7 @@ mod zero_page_y {
   }
   #[test]
   fn ptr_should_wrap_on_overflow() {
-    let mut cpu = Cpu::new();
+    let mut cpu = Cpu::with_bus(Bus::flat());
     cpu.pc = ROOT;
     cpu.y = 0x05;
     cpu.write_addr(ROOT, 0xFC);
@@ -177,7 +180,7 @@ mod absolute_x {
   const ADDR_MODE: &AddressMode = &AddressMode::AbsoluteX;
   #[test]
   fn ptr_should_be_addr_at_pc_address() {
-    let mut cpu = Cpu::new();
+    let mut cpu = Cpu::with_bus(Bus::flat());
     cpu.pc = ROOT;
     cpu.write_addr(ROOT + 0, 0xFC);
     cpu.write_addr(ROOT + 1, 0xFC);
@@ -186,7 +189,7 @@ mod absolute_x {
   }
   #[test]
   fn ptr_should_be_sum_of_x_register_and_addr_at_pc_address() {
-    let mut cpu = Cpu::new();
+    let mut cpu = Cpu::with_bus(Bus::flat());
     cpu.pc = ROOT;
     cpu.write_addr(ROOT + 0, 0xFC);
     cpu.write_addr(ROOT + 1, 0xFC);
@@ -196,7 +199,7 @@ mod absolute_x {
   }
   #[test]
   fn ptr_should_wrap_on_overflow() {
-    let mut cpu = Cpu::new();
+    let mut cpu = Cpu::with_bus(Bus::flat());
     cpu.pc = ROOT;
     cpu.write_addr(ROOT + 0, 0xFF);
     cpu.write_addr(ROOT + 1, 0xFF);
@@ -212,7 +215,7 @@ mod absolute_y {
   const ADDR_MODE: &AddressMode = &AddressMode::AbsoluteY;
   #[test]
   fn ptr_should_be_addr_at_pc_address() {
-    let mut cpu = Cpu::new();
+    let mut cpu = Cpu::with_bus(Bus::flat());
     cpu.pc = ROOT;
     cpu.write_addr(ROOT + 0, 0xFC);
     cpu.write_addr(ROOT + 1, 0xFC);
@@ -221,7 +224,7 @@ mod absolute_y {
   }
   #[test]
   fn ptr_should_be_sum_of_x_register_and_addr_at_pc_address() {
-    let mut cpu = Cpu::new();
+    let mut cpu = Cpu::with_bus(Bus::flat());
     cpu.pc = ROOT;
     cpu.write_addr(ROOT + 0, 0xFC);
     cpu.write_addr(ROOT + 1, 0xFC);
@@ -231,7 +234,7 @@ mod absolute_y {
   }
   #[test]
   fn ptr_should_wrap_on_overflow() {
-    let mut cpu = Cpu::new();
+    let mut cpu = Cpu::with_bus(Bus::flat());
     cpu.pc = ROOT;
     cpu.write_addr(ROOT + 0, 0xFF);
     cpu.write_addr(ROOT + 1, 0xFF);
@@ -247,7 +250,7 @@ mod relative {
   const ADDR_MODE: &AddressMode = &AddressMode::Relative;
   #[test]
   fn pc_should_increment() {
-    let mut cpu = Cpu::new();
+    let mut cpu = Cpu::with_bus(Bus::flat());
     cpu.pc = ROOT;
     cpu.get_data_ptr(ADDR_MODE);
     assert_eq!(cpu.pc, ROOT + 1);
@@ -255,7 +258,7 @@ mod relative {
   #[test]
   fn ptr_should_be_offset() {
     const OFFSET: u8 = 0x0C;
-    let mut cpu = Cpu::new();
+    let mut cpu = Cpu::with_bus(Bus::flat());
     cpu.pc = ROOT;
     cpu.write_addr(ROOT, OFFSET);
     let ptr = cpu.get_data_ptr(ADDR_MODE);
@@ -269,7 +272,7 @@ mod indirect_x {
   const ADDR_MODE: &AddressMode = &AddressMode::IndirectX;
   #[test]
   fn pc_should_increment() {
-    let mut cpu = Cpu::new();
+    let mut cpu = Cpu::with_bus(Bus::flat());
     cpu.pc = ROOT;
     cpu.get_data_ptr(ADDR_MODE);
     assert_eq!(cpu.pc, ROOT + 1);
@@ -277,7 +280,7 @@ mod indirect_x {
   #[test]
   fn ptr_should_be_address_from_zero_page_addr_specified_by_byte_at_pc() {
     const ZERO_PAGE_ADDR: u16 = 0x0020;
-    let mut cpu = Cpu::new();
+    let mut cpu = Cpu::with_bus(Bus::flat());
     cpu.pc = ROOT;
     cpu.write_addr(ZERO_PAGE_ADDR + 0, 0xAD); // lo
     cpu.write_addr(ZERO_PAGE_ADDR + 1, 0xFA); // hi
@@ -288,7 +291,7 @@ mod indirect_x {
   #[test]
   fn should_wrap_zero_page_on_overflow() {
     const ZERO_PAGE_ADDR: u8 = 0xFF;
-    let mut cpu = Cpu::new();
+    let mut cpu = Cpu::with_bus(Bus::flat());
     cpu.pc = ROOT;
     cpu.write_addr(ZERO_PAGE_ADDR as u16, 0xAD); // lo
     cpu.write_addr(ZERO_PAGE_ADDR.wrapping_add(1) as u16, 0xFA); // hi
@@ -304,7 +307,7 @@ mod indirect_y {
   const ADDR_MODE: &AddressMode = &AddressMode::IndirectY;
   #[test]
   fn pc_should_increment() {
-    let mut cpu = Cpu::new();
+    let mut cpu = Cpu::with_bus(Bus::flat());
     cpu.pc = ROOT;
     cpu.get_data_ptr(ADDR_MODE);
     assert_eq!(cpu.pc, ROOT + 1);
@@ -312,7 +315,7 @@ mod indirect_y {
   #[test]
   fn ptr_should_be_address_from_zero_page_addr_specified_by_byte_at_pc() {
     const ZERO_PAGE_ADDR: u16 = 0x0020;
-    let mut cpu = Cpu::new();
+    let mut cpu = Cpu::with_bus(Bus::flat());
     cpu.pc = ROOT;
     cpu.y = 0x02;
     cpu.write_addr(ZERO_PAGE_ADDR + 0, 0xAD); // lo
@@ -324,7 +327,7 @@ mod indirect_y {
   #[test]
   fn should_wrap_zero_page_on_overflow() {
     const ZERO_PAGE_ADDR: u8 = 0xFF;
-    let mut cpu = Cpu::new();
+    let mut cpu = Cpu::with_bus(Bus::flat());
     cpu.pc = ROOT;
     cpu.y = 0x02;
     cpu.write_addr(ZERO_PAGE_ADDR as u16, 0xAD); // lo
@@ -336,7 +339,7 @@ mod indirect_y {
   #[test]
   fn should_wrap_address_on_overflow_with_y_register() {
     const ZERO_PAGE_ADDR: u8 = 0xFF;
-    let mut cpu = Cpu::new();
+    let mut cpu = Cpu::with_bus(Bus::flat());
     cpu.pc = ROOT;
     cpu.y = 0x02;
     cpu.write_addr(ZERO_PAGE_ADDR as u16, 0xFF); // lo
@@ -353,14 +356,14 @@ mod absolute_indirect {
   const ADDR_MODE: &AddressMode = &AddressMode::AbsoluteIndirect;
   #[test]
   fn pc_should_increment_twice() {
-    let mut cpu = Cpu::new();
+    let mut cpu = Cpu::with_bus(Bus::flat());
     cpu.pc = ROOT;
     cpu.get_data_ptr(ADDR_MODE);
     assert_eq!(cpu.pc, ROOT + 2);
   }
   #[test]
   fn ptr_should_be_addr_in_memory_at_addr_at_pc() {
-    let mut cpu = Cpu::new();
+    let mut cpu = Cpu::with_bus(Bus::flat());
     cpu.pc = ROOT;
     cpu.write_addr(ROOT + 0, 0xAB);
     cpu.write_addr(ROOT + 1, 0xCD);
@@ -371,7 +374,7 @@ mod absolute_indirect {
   }
   #[test]
   fn should_respect_page_boundary_hardware_bug() {
-    let mut cpu = Cpu::new();
+    let mut cpu = Cpu::with_bus(Bus::flat());
     cpu.pc = ROOT;
     cpu.write_addr(ROOT + 0, 0xFF);
     cpu.write_addr(ROOT + 1, 0xCD);
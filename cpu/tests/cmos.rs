@@ -0,0 +1,220 @@
+#[cfg(test)]
+use bus::Bus;
+
+#[cfg(test)]
+use cpu::instruction::{decode, Operation};
+
+#[cfg(test)]
+use cpu::{Cpu, Variant};
+
+#[cfg(test)]
+use data_flow::ReadWrite;
+
+#[cfg(test)]
+const PROGRAM_ADDR: u16 = 0x0200;
+
+#[cfg(test)]
+fn setup(variant: Variant, program: &[u8]) -> Cpu {
+  let mut cpu = Cpu::with_variant(Bus::flat(), variant);
+  for (offset, byte) in program.iter().enumerate() {
+    cpu.write_addr(PROGRAM_ADDR + offset as u16, *byte);
+  }
+  cpu.pc = PROGRAM_ADDR;
+  cpu
+}
+
+// Executes the next instruction and returns the cycles it takes
+#[cfg(test)]
+fn run(cpu: &mut Cpu) -> u8 {
+  let tick = cpu.current_tick;
+  cpu.step();
+  (cpu.current_tick - tick) as u8
+}
+
+#[cfg(test)]
+mod instructions {
+  use super::*;
+  #[test]
+  fn bra_always_branches() {
+    let mut cpu = setup(Variant::Cmos65C02, &[0x80, 0x10]);
+    cpu.status = 0b1111_1111;
+    assert_eq!(run(&mut cpu), 3);
+    assert_eq!(cpu.pc, PROGRAM_ADDR + 0x12);
+  }
+  #[test]
+  fn pushes_and_pulls_x_and_y() {
+    // PHX, PHY, PLX, PLY swaps X and Y
+    let mut cpu = setup(Variant::Cmos65C02, &[0xDA, 0x5A, 0xFA, 0x7A]);
+    cpu.x = 0x12;
+    cpu.y = 0x84;
+    for _ in 0..4 {
+      cpu.step();
+    }
+    assert_eq!(cpu.x, 0x84);
+    assert_eq!(cpu.y, 0x12);
+    assert!(!cpu.get_flag('N'));
+  }
+  #[test]
+  fn stz_stores_zero() {
+    let mut cpu = setup(Variant::Cmos65C02, &[0x64, 0x10]);
+    cpu.write_addr(0x0010, 0xFF);
+    assert_eq!(run(&mut cpu), 3);
+    assert_eq!(cpu.read_addr(0x0010), 0x00);
+  }
+  #[test]
+  fn tsb_sets_bits() {
+    let mut cpu = setup(Variant::Cmos65C02, &[0x04, 0x10]);
+    cpu.acc = 0x0F;
+    cpu.write_addr(0x0010, 0x3C);
+    assert_eq!(run(&mut cpu), 5);
+    assert_eq!(cpu.read_addr(0x0010), 0x3F);
+    assert!(!cpu.get_flag('Z'));
+  }
+  #[test]
+  fn trb_resets_bits() {
+    let mut cpu = setup(Variant::Cmos65C02, &[0x1C, 0x00, 0x30]);
+    cpu.acc = 0x0F;
+    cpu.write_addr(0x3000, 0xF0);
+    assert_eq!(run(&mut cpu), 6);
+    assert_eq!(cpu.read_addr(0x3000), 0xF0);
+    assert!(cpu.get_flag('Z'));
+  }
+  #[test]
+  fn bit_immediate_only_sets_z() {
+    let mut cpu = setup(Variant::Cmos65C02, &[0x89, 0x80]);
+    cpu.acc = 0x01;
+    cpu.status = 0b0100_0000;
+    cpu.step();
+    assert_eq!(cpu.status, 0b0110_0010);
+  }
+  #[test]
+  fn increments_and_decrements_accumulator() {
+    let mut cpu = setup(Variant::Cmos65C02, &[0x1A, 0x3A, 0x3A]);
+    cpu.acc = 0x7F;
+    cpu.step();
+    assert_eq!(cpu.acc, 0x80);
+    assert!(cpu.get_flag('N'));
+    cpu.step();
+    cpu.step();
+    assert_eq!(cpu.acc, 0x7E);
+  }
+  #[test]
+  fn unused_opcodes_are_nops() {
+    for opcode in 0..=0xFF {
+      let instruction = decode(opcode, Variant::Cmos65C02);
+      if let Operation::JAM = instruction.get_operation() {
+        panic!("{:02X} jams", opcode);
+      }
+    }
+    // 0x03 takes one cycle, 0x02 skips an operand
+    let mut cpu = setup(Variant::Cmos65C02, &[0x03, 0x02, 0xFF]);
+    assert_eq!(run(&mut cpu), 1);
+    cpu.step();
+    assert_eq!(cpu.pc, PROGRAM_ADDR + 3);
+  }
+}
+
+#[cfg(test)]
+mod address_modes {
+  use super::*;
+  #[test]
+  fn zero_page_indirect() {
+    let mut cpu = setup(Variant::Cmos65C02, &[0xB2, 0x10]);
+    cpu.write_addr(0x0010, 0x34);
+    cpu.write_addr(0x0011, 0x12);
+    cpu.write_addr(0x1234, 0x42);
+    assert_eq!(run(&mut cpu), 5);
+    assert_eq!(cpu.acc, 0x42);
+  }
+  #[test]
+  fn jmp_absolute_indexed_indirect() {
+    let mut cpu = setup(Variant::Cmos65C02, &[0x7C, 0x00, 0x30]);
+    cpu.x = 0x04;
+    cpu.write_addr(0x3004, 0x78);
+    cpu.write_addr(0x3005, 0x56);
+    assert_eq!(run(&mut cpu), 6);
+    assert_eq!(cpu.pc, 0x5678);
+  }
+  #[test]
+  fn jmp_indirect_reads_pointer_across_page() {
+    let mut cpu = setup(Variant::Cmos65C02, &[0x6C, 0xFF, 0x30]);
+    cpu.write_addr(0x30FF, 0x78);
+    cpu.write_addr(0x3100, 0x56);
+    cpu.write_addr(0x3000, 0x12);
+    assert_eq!(run(&mut cpu), 6);
+    assert_eq!(cpu.pc, 0x5678);
+  }
+  #[test]
+  fn nmos_jmp_indirect_wraps_within_page() {
+    let mut cpu = setup(Variant::Nmos6502, &[0x6C, 0xFF, 0x30]);
+    cpu.write_addr(0x30FF, 0x78);
+    cpu.write_addr(0x3100, 0x56);
+    cpu.write_addr(0x3000, 0x12);
+    assert_eq!(run(&mut cpu), 5);
+    assert_eq!(cpu.pc, 0x1278);
+  }
+}
+
+#[cfg(test)]
+mod page_crossing {
+  use super::*;
+  #[test]
+  fn shifts_and_rotates_on_abs_x_fix_page_if_crossed() {
+    // ASL, ROL, LSR, ROR $30F0,X
+    for opcode in &[0x1E, 0x3E, 0x5E, 0x7E] {
+      let mut cpu = setup(Variant::Cmos65C02, &[*opcode, 0xF0, 0x30]);
+      cpu.x = 0x0F;
+      cpu.write_addr(0x30FF, 0x40);
+      assert_eq!(run(&mut cpu), 6, "{:02X}", opcode);
+      assert_ne!(cpu.read_addr(0x30FF), 0x40, "{:02X}", opcode);
+
+      let mut cpu = setup(Variant::Cmos65C02, &[*opcode, 0xF0, 0x30]);
+      cpu.x = 0x10;
+      cpu.write_addr(0x3100, 0x40);
+      assert_eq!(run(&mut cpu), 7, "{:02X}", opcode);
+      assert_ne!(cpu.read_addr(0x3100), 0x40, "{:02X}", opcode);
+    }
+  }
+  #[test]
+  fn inc_and_dec_on_abs_x_always_fix_page() {
+    // INC, DEC $30F0,X
+    for opcode in &[0xFE, 0xDE] {
+      let mut cpu = setup(Variant::Cmos65C02, &[*opcode, 0xF0, 0x30]);
+      cpu.x = 0x0F;
+      assert_eq!(run(&mut cpu), 7, "{:02X}", opcode);
+    }
+  }
+  #[test]
+  fn nmos_shifts_on_abs_x_always_fix_page() {
+    // ASL $30F0,X
+    let mut cpu = setup(Variant::Nmos6502, &[0x1E, 0xF0, 0x30]);
+    cpu.x = 0x0F;
+    assert_eq!(run(&mut cpu), 7);
+  }
+}
+
+#[cfg(test)]
+mod decimal_mode {
+  use super::*;
+  #[test]
+  fn takes_extra_cycle() {
+    // ADC #$01, then in binary mode
+    let mut cpu = setup(Variant::Cmos65C02, &[0x69, 0x01, 0x69, 0x01]);
+    cpu.status = 0b0000_1000;
+    assert_eq!(run(&mut cpu), 3);
+    cpu.status = 0b0000_0000;
+    assert_eq!(run(&mut cpu), 2);
+  }
+  #[test]
+  fn interrupts_clear_decimal_flag() {
+    let mut cpu = setup(Variant::Cmos65C02, &[0x00]);
+    cpu.status = 0b0000_1000;
+    cpu.step();
+    assert!(!cpu.get_flag('D'));
+
+    let mut cpu = setup(Variant::Nmos6502, &[0x00]);
+    cpu.status = 0b0000_1000;
+    cpu.step();
+    assert!(cpu.get_flag('D'));
+  }
+}
@@ -0,0 +1,66 @@
+#[cfg(test)]
+use bus::Bus;
+
+#[cfg(test)]
+use cpu::Cpu;
+
+#[cfg(test)]
+use data_flow::ReadWrite;
+
+#[cfg(test)]
+const PROGRAM_ADDR: u16 = 0x0200;
+
+// Executes one instruction and returns the cycles it takes
+#[cfg(test)]
+fn run(program: &[u8], x: u8, y: u8) -> u8 {
+  let mut cpu = Cpu::with_bus(Bus::flat());
+  for (offset, byte) in program.iter().enumerate() {
+    cpu.write_addr(PROGRAM_ADDR + offset as u16, *byte);
+  }
+  // Pointer for indirect modes at 0x0010
+  cpu.write_addr(0x0010, 0xF0);
+  cpu.write_addr(0x0011, 0x30);
+  cpu.pc = PROGRAM_ADDR;
+  cpu.x = x;
+  cpu.y = y;
+  cpu.step();
+  cpu.current_tick as u8
+}
+
+#[cfg(test)]
+mod page_crossing {
+  use super::*;
+  #[test]
+  fn reads_take_extra_cycle() {
+    // LDA $30F0,X
+    assert_eq!(run(&[0xBD, 0xF0, 0x30], 0x0F, 0), 4);
+    assert_eq!(run(&[0xBD, 0xF0, 0x30], 0x10, 0), 5);
+    // LDA $30F0,Y
+    assert_eq!(run(&[0xB9, 0xF0, 0x30], 0, 0x10), 5);
+    // LDA ($10),Y
+    assert_eq!(run(&[0xB1, 0x10], 0, 0x0F), 5);
+    assert_eq!(run(&[0xB1, 0x10], 0, 0x10), 6);
+    // NOP $30F0,X
+    assert_eq!(run(&[0xFC, 0xF0, 0x30], 0x0F, 0), 4);
+    assert_eq!(run(&[0xFC, 0xF0, 0x30], 0x10, 0), 5);
+    // LAX ($10),Y
+    assert_eq!(run(&[0xB3, 0x10], 0, 0x10), 6);
+  }
+  #[test]
+  fn stores_always_take_extra_cycle() {
+    // STA $30F0,X
+    assert_eq!(run(&[0x9D, 0xF0, 0x30], 0x0F, 0), 5);
+    assert_eq!(run(&[0x9D, 0xF0, 0x30], 0x10, 0), 5);
+    // STA ($10),Y
+    assert_eq!(run(&[0x91, 0x10], 0, 0x10), 6);
+  }
+  #[test]
+  fn read_modify_write_is_fixed() {
+    // INC $30F0,X
+    assert_eq!(run(&[0xFE, 0xF0, 0x30], 0x10, 0), 7);
+    // DCP $30F0,Y
+    assert_eq!(run(&[0xDB, 0xF0, 0x30], 0, 0x10), 7);
+    // SLO ($10),Y
+    assert_eq!(run(&[0x13, 0x10], 0, 0x10), 8);
+  }
+}
@@ -0,0 +1,317 @@
+// Source: http://www.6502.org/tutorials/decimal_mode.html
+// Bruce Clark's test runs every accumulator, operand and carry combination, here valid BCD
+// results are checked against plain decimal arithmetic and the NMOS flags against binary mode
+// and a model of the adder that works one digit at a time
+
+#[cfg(test)]
+use bus::Bus;
+
+#[cfg(test)]
+use cpu::instruction::{AddressMode, Operation};
+
+#[cfg(test)]
+use cpu::{Cpu, Variant};
+
+#[cfg(test)]
+use data_flow::ReadWrite;
+
+#[cfg(test)]
+const ROOT: u16 = 0x0000;
+
+#[cfg(test)]
+const DEF_ADDR_MODE: &AddressMode = &AddressMode::Implied;
+
+#[cfg(test)]
+const STATUS_DECIMAL: u8 = 0b0000_1000;
+
+#[cfg(test)]
+fn to_bcd(value: u8) -> u8 {
+  ((value / 10) << 4) | (value % 10)
+}
+
+// Runs the operation on acc and operand, returning the accumulator and status
+#[cfg(test)]
+fn run(cpu: &mut Cpu, operation: &Operation, acc: u8, operand: u8, status: u8) -> (u8, u8) {
+  cpu.acc = acc;
+  cpu.status = status;
+  cpu.write_addr(ROOT, operand);
+  cpu.perform_operation(ROOT, operation, DEF_ADDR_MODE);
+  (cpu.acc, cpu.status)
+}
+
+#[cfg(test)]
+fn assert_adds_every_bcd_pair(variant: Variant) {
+  let mut cpu = Cpu::with_variant(Bus::flat(), variant);
+  for a in 0..100 {
+    for b in 0..100 {
+      for carry in 0..2 {
+        let sum = a + b + carry;
+        let (acc, status) = run(
+          &mut cpu,
+          &Operation::ADC,
+          to_bcd(a),
+          to_bcd(b),
+          STATUS_DECIMAL | carry,
+        );
+        assert_eq!(acc, to_bcd(sum % 100), "{} + {} + {}", a, b, carry);
+        assert_eq!(
+          status & 0x01,
+          (sum >= 100) as u8,
+          "{} + {} + {}",
+          a,
+          b,
+          carry
+        );
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+fn assert_subtracts_every_bcd_pair(variant: Variant) {
+  let mut cpu = Cpu::with_variant(Bus::flat(), variant);
+  for a in 0..100 {
+    for b in 0..100 {
+      for carry in 0..2 {
+        let difference = a as i16 - b as i16 - 1 + carry as i16;
+        let (acc, status) = run(
+          &mut cpu,
+          &Operation::SBC,
+          to_bcd(a),
+          to_bcd(b),
+          STATUS_DECIMAL | carry,
+        );
+        let expected = to_bcd(((difference + 100) % 100) as u8);
+        assert_eq!(acc, expected, "{} - {} - {}", a, b, 1 - carry);
+        assert_eq!(
+          status & 0x01,
+          (difference >= 0) as u8,
+          "{} - {} - {}",
+          a,
+          b,
+          1 - carry
+        );
+      }
+    }
+  }
+}
+
+// The NMOS adder one digit at a time, Z comes from the binary sum and N and V from the high
+// digit before it is adjusted. Returns the accumulator and status without D
+#[cfg(test)]
+fn add_digits(acc: u8, operand: u8, carry: u8) -> (u8, u8) {
+  let mut lo = (acc & 0x0F) + (operand & 0x0F) + carry;
+  let half_carry = lo > 9;
+  if half_carry {
+    lo += 6;
+  }
+  let mut hi = (acc >> 4) + (operand >> 4) + half_carry as u8;
+  let sign = (hi << 4) & 0x80;
+  // Both inputs have the same sign and the sum does not
+  let overflow = (acc ^ operand) & 0x80 == 0 && (acc & 0x80) != sign;
+  if hi > 9 {
+    hi += 6;
+  }
+  let binary = acc.wrapping_add(operand).wrapping_add(carry);
+  let status = sign | (overflow as u8) << 6 | ((binary == 0) as u8) << 1 | (hi > 0x0F) as u8;
+  ((hi << 4) | (lo & 0x0F), status)
+}
+
+#[cfg(test)]
+mod nmos {
+  use super::*;
+  #[test]
+  fn adds_every_bcd_pair() {
+    assert_adds_every_bcd_pair(Variant::Nmos6502);
+  }
+  #[test]
+  fn subtracts_every_bcd_pair() {
+    assert_subtracts_every_bcd_pair(Variant::Nmos6502);
+  }
+  #[test]
+  fn subtract_flags_match_binary_mode() {
+    let mut decimal = Cpu::with_variant(Bus::flat(), Variant::Nmos6502);
+    let mut binary = Cpu::with_variant(Bus::flat(), Variant::Nmos6502);
+    for acc in 0..=0xFF {
+      for operand in 0..=0xFF {
+        for carry in 0..2 {
+          let (_, expected) = run(&mut binary, &Operation::SBC, acc, operand, carry);
+          let (_, status) = run(
+            &mut decimal,
+            &Operation::SBC,
+            acc,
+            operand,
+            STATUS_DECIMAL | carry,
+          );
+          assert_eq!(
+            status & !STATUS_DECIMAL,
+            expected,
+            "{:02X} - {:02X}",
+            acc,
+            operand
+          );
+        }
+      }
+    }
+  }
+  #[test]
+  fn add_zero_flag_matches_binary_mode() {
+    let mut decimal = Cpu::with_variant(Bus::flat(), Variant::Nmos6502);
+    let mut binary = Cpu::with_variant(Bus::flat(), Variant::Nmos6502);
+    for acc in 0..=0xFF {
+      for operand in 0..=0xFF {
+        for carry in 0..2 {
+          let (_, expected) = run(&mut binary, &Operation::ADC, acc, operand, carry);
+          let (_, status) = run(
+            &mut decimal,
+            &Operation::ADC,
+            acc,
+            operand,
+            STATUS_DECIMAL | carry,
+          );
+          assert_eq!(
+            status & 0x02,
+            expected & 0x02,
+            "{:02X} + {:02X}",
+            acc,
+            operand
+          );
+        }
+      }
+    }
+  }
+  #[test]
+  fn add_matches_digit_model_for_every_input() {
+    let mut cpu = Cpu::with_variant(Bus::flat(), Variant::Nmos6502);
+    for acc in 0..=0xFF {
+      for operand in 0..=0xFF {
+        for carry in 0..2 {
+          let (result, status) = run(
+            &mut cpu,
+            &Operation::ADC,
+            acc,
+            operand,
+            STATUS_DECIMAL | carry,
+          );
+          assert_eq!(
+            (result, status & !STATUS_DECIMAL),
+            add_digits(acc, operand, carry),
+            "{:02X} + {:02X} + {}",
+            acc,
+            operand,
+            carry
+          );
+        }
+      }
+    }
+  }
+  #[test]
+  fn add_sets_n_and_v_before_adjusting_high_digit() {
+    let mut cpu = Cpu::with_variant(Bus::flat(), Variant::Nmos6502);
+    // 99 + 01 = 00 with carry, N comes from 0xA0 and Z from 0x9A
+    assert_eq!(
+      run(&mut cpu, &Operation::ADC, 0x99, 0x01, STATUS_DECIMAL),
+      (0x00, 0b1000_1001)
+    );
+    // 79 + 00 + 1 = 80, overflowing into the sign bit
+    assert_eq!(
+      run(&mut cpu, &Operation::ADC, 0x79, 0x00, STATUS_DECIMAL | 0x01),
+      (0x80, 0b1100_1000)
+    );
+  }
+  #[test]
+  fn adjusts_invalid_bcd_digits() {
+    let mut cpu = Cpu::with_variant(Bus::flat(), Variant::Nmos6502);
+    assert_eq!(
+      run(&mut cpu, &Operation::ADC, 0x0F, 0x00, STATUS_DECIMAL).0,
+      0x15
+    );
+    assert_eq!(
+      run(&mut cpu, &Operation::SBC, 0x00, 0x01, STATUS_DECIMAL | 0x01),
+      (0x99, 0b1000_1000)
+    );
+  }
+  #[test]
+  fn unofficial_operations_use_decimal_mode() {
+    let mut cpu = Cpu::with_variant(Bus::flat(), Variant::Nmos6502);
+    // ISC increments 0x08 to 0x09, then 10 - 09 = 01
+    assert_eq!(
+      run(&mut cpu, &Operation::ISC, 0x10, 0x08, STATUS_DECIMAL | 0x01).0,
+      0x01
+    );
+  }
+}
+
+#[cfg(test)]
+mod cmos {
+  use super::*;
+  #[test]
+  fn adds_every_bcd_pair() {
+    assert_adds_every_bcd_pair(Variant::Cmos65C02);
+  }
+  #[test]
+  fn subtracts_every_bcd_pair() {
+    assert_subtracts_every_bcd_pair(Variant::Cmos65C02);
+  }
+  #[test]
+  fn n_and_z_follow_decimal_result() {
+    let mut cpu = Cpu::with_variant(Bus::flat(), Variant::Cmos65C02);
+    for operation in &[Operation::ADC, Operation::SBC] {
+      for acc in 0..=0xFF {
+        for operand in 0..=0xFF {
+          let (result, status) = run(&mut cpu, operation, acc, operand, STATUS_DECIMAL);
+          assert_eq!(
+            status & 0x80,
+            result & 0x80,
+            "{} {:02X} {:02X}",
+            operation,
+            acc,
+            operand
+          );
+          assert_eq!(
+            status & 0x02 > 0,
+            result == 0,
+            "{} {:02X} {:02X}",
+            operation,
+            acc,
+            operand
+          );
+        }
+      }
+    }
+  }
+  #[test]
+  fn adds_with_valid_zero_flag() {
+    let mut cpu = Cpu::with_variant(Bus::flat(), Variant::Cmos65C02);
+    assert_eq!(
+      run(&mut cpu, &Operation::ADC, 0x99, 0x01, STATUS_DECIMAL),
+      (0x00, 0b0000_1011)
+    );
+  }
+  #[test]
+  fn subtracts_invalid_bcd_digits() {
+    let mut cpu = Cpu::with_variant(Bus::flat(), Variant::Cmos65C02);
+    assert_eq!(
+      run(&mut cpu, &Operation::SBC, 0x00, 0x01, STATUS_DECIMAL | 0x01),
+      (0x99, 0b1000_1000)
+    );
+  }
+}
+
+#[cfg(test)]
+mod ricoh {
+  use super::*;
+  #[test]
+  fn ignores_decimal_flag() {
+    let mut cpu = Cpu::with_bus(Bus::flat());
+    assert_eq!(cpu.get_variant(), Variant::Ricoh2A03);
+    assert_eq!(
+      run(&mut cpu, &Operation::ADC, 0x09, 0x01, STATUS_DECIMAL).0,
+      0x0A
+    );
+    assert_eq!(
+      run(&mut cpu, &Operation::SBC, 0x10, 0x01, STATUS_DECIMAL | 0x01).0,
+      0x0F
+    );
+  }
+}
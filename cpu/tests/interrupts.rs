@@ -0,0 +1,298 @@
+#[cfg(test)]
+use cartridge::Cartridge;
+
+#[cfg(test)]
+use cpu::Cpu;
+
+#[cfg(test)]
+use data_flow::ReadWrite;
+
+#[cfg(test)]
+use mapper::from_cartridge;
+
+#[cfg(test)]
+const IRQ_HANDLER: u16 = 0x8123;
+
+#[cfg(test)]
+const NMI_HANDLER: u16 = 0x8456;
+
+// MMC3 cartridge with the program at 0x8000 followed by NOPs, and its IRQ enabled
+#[cfg(test)]
+fn setup(program: &[u8]) -> Cpu {
+  let mut rom = vec![
+    0x4E, 0x45, 0x53, 0x1A, 2, 1, 0x40, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+  ];
+  let mut prg_rom = vec![0xEA; 32 * 1024];
+  prg_rom[..program.len()].copy_from_slice(program);
+  prg_rom[0x7FFA] = NMI_HANDLER as u8;
+  prg_rom[0x7FFB] = (NMI_HANDLER >> 8) as u8;
+  prg_rom[0x7FFC] = 0x00;
+  prg_rom[0x7FFD] = 0x80;
+  prg_rom[0x7FFE] = IRQ_HANDLER as u8;
+  prg_rom[0x7FFF] = (IRQ_HANDLER >> 8) as u8;
+  rom.extend(prg_rom);
+  rom.extend(vec![0; 8 * 1024]);
+
+  let mut cpu = Cpu::new();
+  let bus = cpu.get_mut_bus_ref();
+  bus.insert_cartridge(from_cartridge(Cartridge::from_bytes(&rom).unwrap()).unwrap());
+  bus.write_addr(0xC000, 0);
+  bus.write_addr(0xE001, 0);
+  cpu.pc = 0x8000;
+  cpu.status = 0b0010_0000;
+  cpu
+}
+
+// A rise of PPU A12 clocks the MMC3 counter to zero, which asserts its IRQ until acknowledged
+#[cfg(test)]
+fn raise_irq(cpu: &mut Cpu) {
+  let mapper = cpu.get_mut_bus_ref().get_mut_mapper().unwrap();
+  mapper.ppu_read(0x0000);
+  for _ in 0..3 {
+    mapper.cpu_clock();
+  }
+  mapper.ppu_read(0x1000);
+}
+
+#[cfg(test)]
+fn clock(cpu: &mut Cpu, cycles: usize) {
+  for _ in 0..cycles {
+    cpu.clock();
+  }
+}
+
+#[cfg(test)]
+mod irq {
+  use super::*;
+  #[test]
+  fn services_mapper_irq_at_instruction_boundary() {
+    let mut cpu = setup(&[]);
+    raise_irq(&mut cpu);
+    cpu.step();
+    assert_eq!(cpu.pc, 0x8001);
+    cpu.step();
+    assert_eq!(cpu.pc, IRQ_HANDLER);
+    assert_eq!(cpu.current_tick, 2 + 7);
+    assert!(cpu.get_flag('I'));
+    // Status is pushed with I still clear so that RTI re-enables interrupts
+    assert_eq!(cpu.stack_pop(), 0b0010_0000);
+    assert_eq!(cpu.pop_address_from_stack(), 0x8001);
+  }
+  #[test]
+  fn waits_for_current_instruction_to_finish() {
+    // LDA $00
+    let mut cpu = setup(&[0xA5, 0x00]);
+    cpu.clock();
+    raise_irq(&mut cpu);
+    cpu.clock();
+    assert_eq!(cpu.pc, 0x8002);
+    assert!(!cpu.is_cycle_complete());
+    cpu.step();
+    assert_eq!(cpu.pc, 0x8002);
+    cpu.step();
+    assert_eq!(cpu.pc, IRQ_HANDLER);
+  }
+  #[test]
+  fn ignores_irq_while_interrupts_disabled() {
+    let mut cpu = setup(&[]);
+    cpu.status = 0b0010_0100;
+    raise_irq(&mut cpu);
+    for _ in 0..4 {
+      cpu.step();
+    }
+    assert_eq!(cpu.pc, 0x8004);
+  }
+  #[test]
+  fn line_is_polled_before_the_last_cycle() {
+    // LDA $00, the line goes low during the last cycle
+    let mut cpu = setup(&[0xA5, 0x00]);
+    clock(&mut cpu, 3);
+    raise_irq(&mut cpu);
+    assert_eq!(cpu.pc, 0x8002);
+    cpu.step();
+    assert_eq!(cpu.pc, 0x8003);
+    cpu.step();
+    assert_eq!(cpu.pc, IRQ_HANDLER);
+  }
+  #[test]
+  fn line_is_level_triggered() {
+    // The handler returns with the IRQ still unacknowledged, so it is taken again
+    let mut cpu = setup(&[]);
+    raise_irq(&mut cpu);
+    cpu.step();
+    cpu.step();
+    assert_eq!(cpu.pc, IRQ_HANDLER);
+    let stkp = cpu.stkp;
+    cpu.set_flag('I', 0);
+    cpu.step();
+    cpu.step();
+    assert_eq!(cpu.pc, IRQ_HANDLER);
+    assert_eq!(cpu.stkp, stkp.wrapping_sub(3));
+  }
+}
+
+#[cfg(test)]
+mod delayed_flag {
+  use super::*;
+  #[test]
+  fn cli_lets_one_more_instruction_run() {
+    let mut cpu = setup(&[0x58]);
+    cpu.status = 0b0010_0100;
+    raise_irq(&mut cpu);
+    cpu.step();
+    assert_eq!(cpu.pc, 0x8001);
+    cpu.step();
+    assert_eq!(cpu.pc, 0x8002);
+    cpu.step();
+    assert_eq!(cpu.pc, IRQ_HANDLER);
+    cpu.stack_pop();
+    assert_eq!(cpu.pop_address_from_stack(), 0x8002);
+  }
+  #[test]
+  fn sei_is_interrupted_with_i_already_set() {
+    let mut cpu = setup(&[0x78]);
+    raise_irq(&mut cpu);
+    cpu.step();
+    assert_eq!(cpu.pc, 0x8001);
+    cpu.step();
+    assert_eq!(cpu.pc, IRQ_HANDLER);
+    assert_eq!(cpu.stack_pop(), 0b0010_0100);
+  }
+  #[test]
+  fn plp_lets_one_more_instruction_run() {
+    let mut cpu = setup(&[0x28]);
+    cpu.status = 0b0010_0100;
+    cpu.stack_push(0b0010_0000);
+    raise_irq(&mut cpu);
+    cpu.step();
+    assert!(!cpu.get_flag('I'));
+    cpu.step();
+    assert_eq!(cpu.pc, 0x8002);
+    cpu.step();
+    assert_eq!(cpu.pc, IRQ_HANDLER);
+  }
+  #[test]
+  fn rti_takes_effect_immediately() {
+    let mut cpu = setup(&[0x40]);
+    cpu.status = 0b0010_0100;
+    cpu.stack_push(0x80);
+    cpu.stack_push(0x10);
+    cpu.stack_push(0b0010_0000);
+    raise_irq(&mut cpu);
+    cpu.step();
+    assert_eq!(cpu.pc, 0x8010);
+    cpu.step();
+    assert_eq!(cpu.pc, IRQ_HANDLER);
+    cpu.stack_pop();
+    assert_eq!(cpu.pop_address_from_stack(), 0x8010);
+  }
+}
+
+#[cfg(test)]
+mod branch {
+  use super::*;
+  #[test]
+  fn taken_branch_skips_its_last_poll() {
+    // BNE +0, the line goes low during the operand fetch
+    let mut cpu = setup(&[0xD0, 0x00]);
+    clock(&mut cpu, 2);
+    raise_irq(&mut cpu);
+    cpu.step();
+    assert_eq!(cpu.pc, 0x8002);
+    cpu.step();
+    assert_eq!(cpu.pc, 0x8003);
+    cpu.step();
+    assert_eq!(cpu.pc, IRQ_HANDLER);
+  }
+  #[test]
+  fn taken_branch_polls_before_its_first_cycle() {
+    // BNE +0, the line goes low during the opcode fetch
+    let mut cpu = setup(&[0xD0, 0x00]);
+    cpu.clock();
+    raise_irq(&mut cpu);
+    cpu.step();
+    assert_eq!(cpu.pc, 0x8002);
+    cpu.step();
+    assert_eq!(cpu.pc, IRQ_HANDLER);
+  }
+  #[test]
+  fn page_crossing_branch_polls_again() {
+    // BNE +0x7F from 0x8080 lands on 0x8101
+    let mut program = vec![0xEA; 0x82];
+    program[0x80] = 0xD0;
+    program[0x81] = 0x7F;
+    let mut cpu = setup(&program);
+    cpu.pc = 0x8080;
+    clock(&mut cpu, 2);
+    raise_irq(&mut cpu);
+    cpu.step();
+    assert_eq!(cpu.pc, 0x8101);
+    cpu.step();
+    assert_eq!(cpu.pc, IRQ_HANDLER);
+  }
+}
+
+#[cfg(test)]
+mod nmi {
+  use super::*;
+  #[test]
+  fn takes_priority_over_irq() {
+    let mut cpu = setup(&[]);
+    raise_irq(&mut cpu);
+    cpu.nmi_pending = true;
+    cpu.step();
+    cpu.step();
+    assert_eq!(cpu.pc, NMI_HANDLER);
+    // The IRQ is still asserted once the handler's first instruction has run
+    cpu.set_flag('I', 0);
+    cpu.step();
+    assert_eq!(cpu.pc, NMI_HANDLER + 1);
+    cpu.step();
+    assert_eq!(cpu.pc, IRQ_HANDLER);
+  }
+  #[test]
+  fn hijacks_brk() {
+    let mut cpu = setup(&[0x00]);
+    clock(&mut cpu, 3);
+    cpu.nmi_pending = true;
+    cpu.step();
+    assert_eq!(cpu.pc, NMI_HANDLER);
+    // The B flag still tells the handler a BRK was interrupted
+    assert_eq!(cpu.stack_pop(), 0b0011_0000);
+    assert_eq!(cpu.pop_address_from_stack(), 0x8002);
+    cpu.step();
+    assert_eq!(cpu.pc, NMI_HANDLER + 1);
+  }
+  #[test]
+  fn hijacks_irq() {
+    let mut cpu = setup(&[]);
+    raise_irq(&mut cpu);
+    cpu.step();
+    clock(&mut cpu, 3);
+    cpu.nmi_pending = true;
+    cpu.step();
+    assert_eq!(cpu.pc, NMI_HANDLER);
+    assert_eq!(cpu.stack_pop(), 0b0010_0000);
+  }
+  #[test]
+  fn waits_for_handler_after_status_push() {
+    let mut cpu = setup(&[0x00]);
+    clock(&mut cpu, 5);
+    cpu.nmi_pending = true;
+    cpu.step();
+    assert_eq!(cpu.pc, IRQ_HANDLER);
+    cpu.step();
+    assert_eq!(cpu.pc, IRQ_HANDLER + 1);
+    cpu.step();
+    assert_eq!(cpu.pc, NMI_HANDLER);
+  }
+  #[test]
+  fn is_cleared_by_reset() {
+    let mut cpu = setup(&[]);
+    cpu.nmi_pending = true;
+    cpu.reset();
+    cpu.step();
+    cpu.step();
+    assert_eq!(cpu.pc, 0x8001);
+  }
+}
@@ -0,0 +1,60 @@
+#[cfg(test)]
+use bus::Bus;
+
+#[cfg(test)]
+use cpu::Cpu;
+
+#[cfg(test)]
+use data_flow::ReadWrite;
+
+#[cfg(test)]
+const PROGRAM_ADDR: u16 = 0x0200;
+
+// Flat memory with a JAM opcode at 0x0200 and NOPs after it
+#[cfg(test)]
+fn setup() -> Cpu {
+  let mut cpu = Cpu::with_bus(Bus::flat());
+  cpu.write_addr(PROGRAM_ADDR, 0x02);
+  cpu.write_addr(PROGRAM_ADDR + 1, 0xEA);
+  cpu.write_addr(0xFFFC, PROGRAM_ADDR as u8);
+  cpu.write_addr(0xFFFD, (PROGRAM_ADDR >> 8) as u8);
+  cpu.pc = PROGRAM_ADDR;
+  cpu
+}
+
+#[test]
+fn halts_on_jam_opcode() {
+  let mut cpu = setup();
+  cpu.step();
+  assert!(cpu.is_jammed());
+  assert_eq!(*cpu.get_ppc(), PROGRAM_ADDR);
+
+  let pc = cpu.pc;
+  let tick = cpu.current_tick;
+  for _ in 0..10 {
+    cpu.step();
+  }
+  assert_eq!(cpu.pc, pc);
+  assert_eq!(*cpu.get_ppc(), PROGRAM_ADDR);
+  assert_eq!(cpu.current_tick, tick + 10);
+}
+
+#[test]
+fn every_kil_opcode_jams() {
+  for opcode in &[
+    0x02, 0x12, 0x22, 0x32, 0x42, 0x52, 0x62, 0x72, 0x92, 0xB2, 0xD2, 0xF2,
+  ] {
+    let mut cpu = setup();
+    cpu.write_addr(PROGRAM_ADDR, *opcode);
+    cpu.step();
+    assert!(cpu.is_jammed(), "{:02X} did not jam", opcode);
+  }
+}
+
+#[test]
+fn reset_recovers() {
+  let mut cpu = setup();
+  cpu.step();
+  cpu.reset();
+  assert!(!cpu.is_jammed());
+}
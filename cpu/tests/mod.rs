@@ -1,2 +1,9 @@
 mod address_modes;
+mod cmos;
+mod cycles;
+mod decimal;
+mod interrupts;
+mod jam;
 mod operations;
+mod processor_tests;
+mod sequencing;
@@ -9,7 +9,7 @@ fn bcc_no_branch() {
   let mut cpu = Cpu::new();
   cpu.pc = 0x0000;
   cpu.status = 0b0000_0001;
-  cpu.perform_operation(0b0000_0001, &Operation::BCC, &AddressMode::XXX);
+  cpu.perform_operation(0b0000_0001, &Operation::BCC, &AddressMode::Implied);
   assert_eq!(cpu.pc, 0);
 }
 
@@ -18,7 +18,7 @@ fn bcc_branch() {
   let mut cpu = Cpu::new();
   cpu.pc = 0x0000;
   cpu.status = 0b0000_0000;
-  cpu.perform_operation(0b0000_0001, &Operation::BCC, &AddressMode::XXX);
+  cpu.perform_operation(0b0000_0001, &Operation::BCC, &AddressMode::Implied);
   assert_eq!(cpu.pc, 1);
 }
 
@@ -27,7 +27,7 @@ fn bcs_no_branch() {
   let mut cpu = Cpu::new();
   cpu.pc = 0x0000;
   cpu.status = 0b0000_0000;
-  cpu.perform_operation(0b0000_0001, &Operation::BCS, &AddressMode::XXX);
+  cpu.perform_operation(0b0000_0001, &Operation::BCS, &AddressMode::Implied);
   assert_eq!(cpu.pc, 0);
 }
 
@@ -36,7 +36,7 @@ fn bcs_branch() {
   let mut cpu = Cpu::new();
   cpu.pc = 0x0000;
   cpu.status = 0b0000_0001;
-  cpu.perform_operation(0b0000_0001, &Operation::BCS, &AddressMode::XXX);
+  cpu.perform_operation(0b0000_0001, &Operation::BCS, &AddressMode::Implied);
   assert_eq!(cpu.pc, 1);
 }
 
@@ -45,7 +45,7 @@ fn beq_no_branch() {
   let mut cpu = Cpu::new();
   cpu.pc = 0x0000;
   cpu.status = 0b0000_0000;
-  cpu.perform_operation(0b0000_0001, &Operation::BEQ, &AddressMode::XXX);
+  cpu.perform_operation(0b0000_0001, &Operation::BEQ, &AddressMode::Implied);
   assert_eq!(cpu.pc, 0);
 }
 
@@ -54,7 +54,7 @@ fn beq_branch() {
   let mut cpu = Cpu::new();
   cpu.pc = 0x0000;
   cpu.status = 0b0000_0010;
-  cpu.perform_operation(0b0000_0001, &Operation::BEQ, &AddressMode::XXX);
+  cpu.perform_operation(0b0000_0001, &Operation::BEQ, &AddressMode::Implied);
   assert_eq!(cpu.pc, 1);
 }
 
@@ -63,7 +63,7 @@ fn bmi_no_branch() {
   let mut cpu = Cpu::new();
   cpu.pc = 0x0000;
   cpu.status = 0b0000_0000;
-  cpu.perform_operation(0b0000_0001, &Operation::BMI, &AddressMode::XXX);
+  cpu.perform_operation(0b0000_0001, &Operation::BMI, &AddressMode::Implied);
   assert_eq!(cpu.pc, 0);
 }
 
@@ -72,7 +72,7 @@ fn bmi_branch() {
   let mut cpu = Cpu::new();
   cpu.pc = 0x0000;
   cpu.status = 0b1000_0000;
-  cpu.perform_operation(0b0000_0001, &Operation::BMI, &AddressMode::XXX);
+  cpu.perform_operation(0b0000_0001, &Operation::BMI, &AddressMode::Implied);
   assert_eq!(cpu.pc, 1);
 }
 
@@ -81,7 +81,7 @@ fn bne_no_branch() {
   let mut cpu = Cpu::new();
   cpu.pc = 0x0000;
   cpu.status = 0b0000_0010;
-  cpu.perform_operation(0b0000_0001, &Operation::BNE, &AddressMode::XXX);
+  cpu.perform_operation(0b0000_0001, &Operation::BNE, &AddressMode::Implied);
   assert_eq!(cpu.pc, 0);
 }
 
@@ -90,7 +90,7 @@ fn bne_branch() {
   let mut cpu = Cpu::new();
   cpu.pc = 0x0000;
   cpu.status = 0b0000_0000;
-  cpu.perform_operation(0b0000_0001, &Operation::BNE, &AddressMode::XXX);
+  cpu.perform_operation(0b0000_0001, &Operation::BNE, &AddressMode::Implied);
   assert_eq!(cpu.pc, 1);
 }
 
@@ -99,7 +99,7 @@ fn bpl_no_branch() {
   let mut cpu = Cpu::new();
   cpu.pc = 0x0000;
   cpu.status = 0b1000_0000;
-  cpu.perform_operation(0b0000_0001, &Operation::BPL, &AddressMode::XXX);
+  cpu.perform_operation(0b0000_0001, &Operation::BPL, &AddressMode::Implied);
   assert_eq!(cpu.pc, 0);
 }
 
@@ -108,7 +108,7 @@ fn bpl_branch() {
   let mut cpu = Cpu::new();
   cpu.pc = 0x0000;
   cpu.status = 0b0000_0000;
-  cpu.perform_operation(0b0000_0001, &Operation::BPL, &AddressMode::XXX);
+  cpu.perform_operation(0b0000_0001, &Operation::BPL, &AddressMode::Implied);
   assert_eq!(cpu.pc, 1);
 }
 
@@ -117,7 +117,7 @@ fn bvc_no_branch() {
   let mut cpu = Cpu::new();
   cpu.pc = 0x0000;
   cpu.status = 0b0100_0000;
-  cpu.perform_operation(0b0000_0001, &Operation::BVC, &AddressMode::XXX);
+  cpu.perform_operation(0b0000_0001, &Operation::BVC, &AddressMode::Implied);
   assert_eq!(cpu.pc, 0);
 }
 
@@ -126,7 +126,7 @@ fn bvc_branch() {
   let mut cpu = Cpu::new();
   cpu.pc = 0x0000;
   cpu.status = 0b0000_0000;
-  cpu.perform_operation(0b0000_0001, &Operation::BVC, &AddressMode::XXX);
+  cpu.perform_operation(0b0000_0001, &Operation::BVC, &AddressMode::Implied);
   assert_eq!(cpu.pc, 1);
 }
 
@@ -135,7 +135,7 @@ fn bvs_no_branch() {
   let mut cpu = Cpu::new();
   cpu.pc = 0x0000;
   cpu.status = 0b0000_0000;
-  cpu.perform_operation(0b0000_0001, &Operation::BVS, &AddressMode::XXX);
+  cpu.perform_operation(0b0000_0001, &Operation::BVS, &AddressMode::Implied);
   assert_eq!(cpu.pc, 0);
 }
 
@@ -144,6 +144,6 @@ fn bvs_branch() {
   let mut cpu = Cpu::new();
   cpu.pc = 0x0000;
   cpu.status = 0b0100_0000;
-  cpu.perform_operation(0b0000_0001, &Operation::BVS, &AddressMode::XXX);
+  cpu.perform_operation(0b0000_0001, &Operation::BVS, &AddressMode::Implied);
   assert_eq!(cpu.pc, 1);
 }
@@ -5,7 +5,7 @@ use cpu::instruction::{AddressMode, Operation};
 use cpu::Cpu;
 
 #[cfg(test)]
-const DEF_ADDR_MODE: &AddressMode = &AddressMode::XXX;
+const DEF_ADDR_MODE: &AddressMode = &AddressMode::Implied;
 
 #[cfg(test)]
 mod tax {
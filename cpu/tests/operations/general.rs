@@ -1,3 +1,6 @@
+#[cfg(test)]
+use bus::Bus;
+
 #[cfg(test)]
 use cpu::instruction::{AddressMode, Operation};
 
@@ -11,7 +14,7 @@ use data_flow::ReadWrite;
 const ROOT: u16 = 0x0000;
 
 #[cfg(test)]
-const DEF_ADDR_MODE: &AddressMode = &AddressMode::XXX;
+const DEF_ADDR_MODE: &AddressMode = &AddressMode::Implied;
 
 #[cfg(test)]
 mod adc {
@@ -220,40 +223,46 @@ mod bit {
 #[cfg(test)]
 mod brk {
   use super::*;
-  const OP: &Operation = &Operation::BRK;
-  #[test]
-  fn set_pc_to_brk_vector_address() {
-    let mut cpu = Cpu::new();
+  // BRK only runs as micro ops, so it is stepped from 0x10F0 with its vector at 0x4004
+  fn run_brk() -> Cpu {
+    let mut cpu = Cpu::with_bus(Bus::flat());
+    cpu.write_addr(0x10F0, 0x00);
     cpu.write_addr(0xFFFE, 0x04);
     cpu.write_addr(0xFFFF, 0x40);
-    cpu.perform_operation(ROOT, OP, DEF_ADDR_MODE);
+    cpu.pc = 0x10F0;
+    cpu.status = 0;
+    cpu.step();
+    cpu
+  }
+  #[test]
+  fn set_pc_to_brk_vector_address() {
+    let cpu = run_brk();
     assert_eq!(cpu.pc, 0x4004);
   }
   #[test]
   fn pushes_status_register_to_stack() {
-    let mut cpu = Cpu::new();
-    cpu.perform_operation(ROOT, OP, DEF_ADDR_MODE);
+    let mut cpu = run_brk();
+    // B and U are set in the pushed copy, I only once it is on the stack
     let status = cpu.stack_pop();
-    assert_eq!(status, 0b0001_0100);
+    assert_eq!(status, 0b0011_0000);
+    assert!(cpu.get_flag('I'));
   }
   #[test]
   fn pushes_pc_to_stack_before_status_register() {
-    let mut cpu = Cpu::new();
-    cpu.pc = 0x10F0;
-    cpu.perform_operation(ROOT, OP, DEF_ADDR_MODE);
+    let mut cpu = run_brk();
     // Remove status register
     let _ = cpu.stack_pop();
-    // Get pc
+    // The return address skips the byte after BRK
     let pc_lo = cpu.stack_pop();
     let pc_hi = cpu.stack_pop();
-    assert_eq!(pc_lo, 0xF1);
+    assert_eq!(pc_lo, 0xF2);
     assert_eq!(pc_hi, 0x10);
   }
   #[test]
   fn flag_i_but_not_b() {
-    let mut cpu = Cpu::new();
-    cpu.perform_operation(ROOT, OP, DEF_ADDR_MODE);
-    assert_eq!(cpu.status, 0b0000_0100);
+    let cpu = run_brk();
+    // U always reads as set
+    assert_eq!(cpu.status, 0b0010_0100);
   }
 }
 
@@ -373,19 +382,6 @@ mod lsr {
   }
 }
 
-#[cfg(test)]
-mod nop {
-  use super::*;
-  const OP: &Operation = &Operation::NOP;
-  #[test]
-  fn increments_cycle_on_0xfc_opcode() {
-    let mut cpu = Cpu::new();
-    cpu.opcode = 0xFC;
-    cpu.perform_operation(ROOT, OP, DEF_ADDR_MODE);
-    assert_eq!(cpu.cycles, 1);
-  }
-}
-
 #[cfg(test)]
 mod ora {
   use super::*;
@@ -434,12 +430,13 @@ mod php {
   use super::*;
   const OP: &Operation = &Operation::PHP;
   #[test]
-  // TODO: Do we need to worry about this: https://stackoverflow.com/questions/52017657/6502-emulator-testing-nestest
-  fn pushes_status_register_onto_stack() {
+  // Source: https://stackoverflow.com/questions/52017657/6502-emulator-testing-nestest
+  fn pushes_status_register_onto_stack_with_b_set() {
     let mut cpu = Cpu::new();
     cpu.status = 0xAD;
     cpu.perform_operation(ROOT, OP, DEF_ADDR_MODE);
-    assert_eq!(cpu.stack_pop(), 0xAD);
+    assert_eq!(cpu.stack_pop(), 0xBD);
+    assert_eq!(cpu.status, 0xAD);
   }
 }
 
@@ -496,12 +493,12 @@ mod rti {
   use super::*;
   const OP: &Operation = &Operation::RTI;
   #[test]
-  fn pops_stack_into_status_register_with_bui_flags_cleared() {
+  fn pops_stack_into_status_register_without_b() {
     let mut cpu = Cpu::new();
     cpu.status = 0x00;
-    cpu.stack_push(0b1111_1111);
+    cpu.stack_push(0b1101_1111);
     cpu.perform_operation(ROOT, OP, DEF_ADDR_MODE);
-    assert_eq!(cpu.status, 0b1100_1011);
+    assert_eq!(cpu.status, 0b1110_1111);
   }
   #[test]
   fn pops_pc_after_status_register() {
@@ -529,21 +526,10 @@ mod rts {
   }
 }
 
-#[cfg(test)]
-mod xxx {
-  use super::*;
-  const OP: &Operation = &Operation::XXX;
-  #[test]
-  fn does_nothing() {
-    let mut cpu = Cpu::new();
-    assert_eq!((), cpu.perform_operation(ROOT, OP, DEF_ADDR_MODE));
-  }
-}
-
 #[test]
 fn immediate_addressing_increments_pc() {
   let mut cpu = Cpu::new();
   cpu.pc = 0x0000;
-  cpu.perform_operation(ROOT, &Operation::XXX, &AddressMode::Immediate);
+  cpu.perform_operation(ROOT, &Operation::NOP, &AddressMode::Immediate);
   assert_eq!(cpu.pc, 1);
 }
@@ -8,7 +8,7 @@ use data_flow::ReadWrite;
 use cpu::Cpu;
 
 #[cfg(test)]
-const DEF_ADDR_MODE: &AddressMode = &AddressMode::XXX;
+const DEF_ADDR_MODE: &AddressMode = &AddressMode::Implied;
 
 #[cfg(test)]
 const ROOT: u16 = 0x0000;
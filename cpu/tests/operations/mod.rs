@@ -7,3 +7,4 @@ mod general;
 mod increments;
 mod loads;
 mod rotations;
+mod unofficial;
@@ -0,0 +1,242 @@
+#[cfg(test)]
+use cpu::instruction::{AddressMode, Operation};
+
+#[cfg(test)]
+use data_flow::ReadWrite;
+
+#[cfg(test)]
+use cpu::Cpu;
+
+#[cfg(test)]
+const DEF_ADDR_MODE: &AddressMode = &AddressMode::Implied;
+
+#[cfg(test)]
+const ROOT: u16 = 0x0000;
+
+#[cfg(test)]
+mod lax {
+  use super::*;
+  const OP: &Operation = &Operation::LAX;
+  #[test]
+  fn loads_accumulator_and_x() {
+    let mut cpu = Cpu::new();
+    cpu.write_addr(ROOT, 0b1000_0001);
+    cpu.perform_operation(ROOT, OP, DEF_ADDR_MODE);
+    assert_eq!(cpu.acc, 0b1000_0001);
+    assert_eq!(cpu.x, 0b1000_0001);
+    assert_eq!(cpu.status, 0b1000_0000);
+  }
+}
+
+#[cfg(test)]
+mod sax {
+  use super::*;
+  const OP: &Operation = &Operation::SAX;
+  #[test]
+  fn stores_accumulator_and_x() {
+    let mut cpu = Cpu::new();
+    cpu.acc = 0b1100_1100;
+    cpu.x = 0b1010_1010;
+    cpu.perform_operation(ROOT, OP, DEF_ADDR_MODE);
+    assert_eq!(cpu.read_addr(ROOT), 0b1000_1000);
+    assert_eq!(cpu.status, 0b0000_0000);
+  }
+}
+
+#[cfg(test)]
+mod dcp {
+  use super::*;
+  const OP: &Operation = &Operation::DCP;
+  #[test]
+  fn decrements_then_compares() {
+    let mut cpu = Cpu::new();
+    cpu.acc = 0x10;
+    cpu.write_addr(ROOT, 0x11);
+    cpu.perform_operation(ROOT, OP, DEF_ADDR_MODE);
+    assert_eq!(cpu.read_addr(ROOT), 0x10);
+    assert_eq!(cpu.status, 0b0000_0011);
+  }
+}
+
+#[cfg(test)]
+mod isc {
+  use super::*;
+  const OP: &Operation = &Operation::ISC;
+  #[test]
+  fn increments_then_subtracts() {
+    let mut cpu = Cpu::new();
+    cpu.status = 0b0000_0001;
+    cpu.acc = 0x10;
+    cpu.write_addr(ROOT, 0x0F);
+    cpu.perform_operation(ROOT, OP, DEF_ADDR_MODE);
+    assert_eq!(cpu.read_addr(ROOT), 0x10);
+    assert_eq!(cpu.acc, 0x00);
+    assert_eq!(cpu.status, 0b0000_0011);
+  }
+}
+
+#[cfg(test)]
+mod slo {
+  use super::*;
+  const OP: &Operation = &Operation::SLO;
+  #[test]
+  fn shifts_left_then_ors() {
+    let mut cpu = Cpu::new();
+    cpu.acc = 0b0000_0001;
+    cpu.write_addr(ROOT, 0b1100_0000);
+    cpu.perform_operation(ROOT, OP, DEF_ADDR_MODE);
+    assert_eq!(cpu.read_addr(ROOT), 0b1000_0000);
+    assert_eq!(cpu.acc, 0b1000_0001);
+    assert_eq!(cpu.status, 0b1000_0001);
+  }
+}
+
+#[cfg(test)]
+mod rla {
+  use super::*;
+  const OP: &Operation = &Operation::RLA;
+  #[test]
+  fn rotates_left_then_ands() {
+    let mut cpu = Cpu::new();
+    cpu.status = 0b0000_0001;
+    cpu.acc = 0b0000_0011;
+    cpu.write_addr(ROOT, 0b1000_0001);
+    cpu.perform_operation(ROOT, OP, DEF_ADDR_MODE);
+    assert_eq!(cpu.read_addr(ROOT), 0b0000_0011);
+    assert_eq!(cpu.acc, 0b0000_0011);
+    assert_eq!(cpu.status, 0b0000_0001);
+  }
+}
+
+#[cfg(test)]
+mod sre {
+  use super::*;
+  const OP: &Operation = &Operation::SRE;
+  #[test]
+  fn shifts_right_then_eors() {
+    let mut cpu = Cpu::new();
+    cpu.acc = 0b0000_0001;
+    cpu.write_addr(ROOT, 0b0000_0011);
+    cpu.perform_operation(ROOT, OP, DEF_ADDR_MODE);
+    assert_eq!(cpu.read_addr(ROOT), 0b0000_0001);
+    assert_eq!(cpu.acc, 0b0000_0000);
+    assert_eq!(cpu.status, 0b0000_0011);
+  }
+}
+
+#[cfg(test)]
+mod rra {
+  use super::*;
+  const OP: &Operation = &Operation::RRA;
+  #[test]
+  fn rotates_right_then_adds_with_carry_out() {
+    let mut cpu = Cpu::new();
+    cpu.acc = 0x01;
+    cpu.write_addr(ROOT, 0b0000_0101);
+    cpu.perform_operation(ROOT, OP, DEF_ADDR_MODE);
+    assert_eq!(cpu.read_addr(ROOT), 0b0000_0010);
+    assert_eq!(cpu.acc, 0x04);
+    assert_eq!(cpu.status, 0b0000_0000);
+  }
+}
+
+#[cfg(test)]
+mod anc {
+  use super::*;
+  const OP: &Operation = &Operation::ANC;
+  #[test]
+  fn copies_negative_into_carry() {
+    let mut cpu = Cpu::new();
+    cpu.acc = 0b1111_0000;
+    cpu.write_addr(ROOT, 0b1000_1000);
+    cpu.perform_operation(ROOT, OP, DEF_ADDR_MODE);
+    assert_eq!(cpu.acc, 0b1000_0000);
+    assert_eq!(cpu.status, 0b1000_0001);
+  }
+}
+
+#[cfg(test)]
+mod alr {
+  use super::*;
+  const OP: &Operation = &Operation::ALR;
+  #[test]
+  fn ands_then_shifts_right() {
+    let mut cpu = Cpu::new();
+    cpu.acc = 0b0000_0111;
+    cpu.write_addr(ROOT, 0b0000_0101);
+    cpu.perform_operation(ROOT, OP, DEF_ADDR_MODE);
+    assert_eq!(cpu.acc, 0b0000_0010);
+    assert_eq!(cpu.status, 0b0000_0001);
+  }
+}
+
+#[cfg(test)]
+mod arr {
+  use super::*;
+  const OP: &Operation = &Operation::ARR;
+  #[test]
+  fn takes_carry_and_overflow_from_bits_6_and_5() {
+    let mut cpu = Cpu::new();
+    cpu.status = 0b0000_0001;
+    cpu.acc = 0b1000_0000;
+    cpu.write_addr(ROOT, 0b1111_1111);
+    cpu.perform_operation(ROOT, OP, DEF_ADDR_MODE);
+    assert_eq!(cpu.acc, 0b1100_0000);
+    assert_eq!(cpu.status, 0b1100_0001);
+  }
+}
+
+#[cfg(test)]
+mod axs {
+  use super::*;
+  const OP: &Operation = &Operation::AXS;
+  #[test]
+  fn subtracts_from_accumulator_and_x_without_borrow() {
+    let mut cpu = Cpu::new();
+    cpu.acc = 0b0000_1111;
+    cpu.x = 0b0011_1100;
+    cpu.write_addr(ROOT, 0x0D);
+    cpu.perform_operation(ROOT, OP, DEF_ADDR_MODE);
+    assert_eq!(cpu.x, 0xFF);
+    assert_eq!(cpu.status, 0b1000_0000);
+  }
+}
+
+#[cfg(test)]
+mod las {
+  use super::*;
+  const OP: &Operation = &Operation::LAS;
+  #[test]
+  fn ands_with_stack_pointer_into_a_x_and_sp() {
+    let mut cpu = Cpu::new();
+    cpu.stkp = 0b1111_0000;
+    cpu.write_addr(ROOT, 0b1010_1010);
+    cpu.perform_operation(ROOT, OP, DEF_ADDR_MODE);
+    assert_eq!(cpu.acc, 0b1010_0000);
+    assert_eq!(cpu.x, 0b1010_0000);
+    assert_eq!(cpu.stkp, 0b1010_0000);
+  }
+}
+
+#[cfg(test)]
+mod shx {
+  use super::*;
+  const OP: &Operation = &Operation::SHX;
+  #[test]
+  fn ands_with_high_byte_plus_one() {
+    let mut cpu = Cpu::new();
+    cpu.x = 0xFF;
+    cpu.y = 0x01;
+    cpu.perform_operation(0x0201, OP, DEF_ADDR_MODE);
+    assert_eq!(cpu.read_addr(0x0201), 0x03);
+  }
+  #[test]
+  fn replaces_high_byte_of_address_when_page_crossed() {
+    let mut cpu = Cpu::new();
+    cpu.x = 0x05;
+    cpu.y = 0x10;
+    cpu.page_crossed = true;
+    cpu.perform_operation(0x0308, OP, DEF_ADDR_MODE);
+    assert_eq!(cpu.read_addr(0x0108), 0x01);
+  }
+}
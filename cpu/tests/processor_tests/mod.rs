@@ -0,0 +1,317 @@
+// Source: https://github.com/TomHarte/ProcessorTests
+// Every opcode has a JSON file of single instruction tests, each with the registers and RAM before
+// and after, and the address, data and direction of every cycle's bus access. The suite is too big
+// to check in, point PROCESSOR_TESTS at a checkout or copy it to roms/ProcessorTests
+
+#[cfg(test)]
+use bus::{Access, Bus};
+
+#[cfg(test)]
+use cpu::instruction::{decode, Operation};
+
+#[cfg(test)]
+use cpu::{Cpu, Variant};
+
+#[cfg(test)]
+use data_flow::ReadWrite;
+
+#[cfg(test)]
+use serde_json::{self, Value};
+
+#[cfg(test)]
+use std::cell::RefCell;
+
+#[cfg(test)]
+use std::env;
+
+#[cfg(test)]
+use std::fs;
+
+#[cfg(test)]
+use std::path::{Path, PathBuf};
+
+#[cfg(test)]
+use std::rc::Rc;
+
+#[cfg(test)]
+const TESTS_DIR_VAR: &str = "PROCESSOR_TESTS";
+
+#[cfg(test)]
+fn as_u16(value: &Value) -> Option<u16> {
+  value
+    .as_u64()
+    .filter(|&number| number <= 0xFFFF)
+    .map(|number| number as u16)
+}
+
+#[cfg(test)]
+struct State {
+  pc: u16,
+  s: u8,
+  a: u8,
+  x: u8,
+  y: u8,
+  p: u8,
+  ram: Vec<(u16, u8)>,
+}
+
+#[cfg(test)]
+impl State {
+  fn from_json(value: &Value) -> Option<State> {
+    let register = |name| value.get(name).and_then(as_u16);
+    let ram = value
+      .get("ram")?
+      .as_array()?
+      .iter()
+      .map(|entry| {
+        let entry = entry.as_array()?;
+        Some((as_u16(entry.first()?)?, as_u16(entry.get(1)?)? as u8))
+      })
+      .collect::<Option<Vec<_>>>()?;
+    Some(State {
+      pc: register("pc")?,
+      s: register("s")? as u8,
+      a: register("a")? as u8,
+      x: register("x")? as u8,
+      y: register("y")? as u8,
+      p: register("p")? as u8,
+      ram,
+    })
+  }
+}
+
+#[cfg(test)]
+fn parse_cycles(value: &Value) -> Option<Vec<(Access, u16, u8)>> {
+  value
+    .as_array()?
+    .iter()
+    .map(|cycle| {
+      let cycle = cycle.as_array()?;
+      let access = match cycle.get(2)?.as_str()? {
+        "read" => Access::Read,
+        "write" => Access::Write,
+        _ => return None,
+      };
+      Some((
+        access,
+        as_u16(cycle.first()?)?,
+        as_u16(cycle.get(1)?)? as u8,
+      ))
+    })
+    .collect()
+}
+
+// JAM never finishes and the unstable opcodes depend on the chip they were measured on
+#[cfg(test)]
+fn is_skipped(opcode: u8, variant: Variant) -> bool {
+  matches!(
+    decode(opcode, variant).get_operation(),
+    Operation::JAM
+      | Operation::ANE
+      | Operation::LXA
+      | Operation::SHA
+      | Operation::SHX
+      | Operation::SHY
+      | Operation::TAS
+  )
+}
+
+// Runs one test case, describing the first difference from the expected result
+#[cfg(test)]
+fn run_case(case: &Value, variant: Variant) -> Result<(), String> {
+  let name = case.get("name").and_then(Value::as_str).unwrap_or("?");
+  let malformed = || format!("{}: malformed test", name);
+  let initial = case
+    .get("initial")
+    .and_then(State::from_json)
+    .ok_or_else(malformed)?;
+  let expected = case
+    .get("final")
+    .and_then(State::from_json)
+    .ok_or_else(malformed)?;
+  let cycles = case
+    .get("cycles")
+    .and_then(parse_cycles)
+    .ok_or_else(malformed)?;
+
+  let mut cpu = Cpu::with_variant(Bus::flat(), variant);
+  for &(addr, data) in &initial.ram {
+    cpu.write_addr(addr, data);
+  }
+  cpu.pc = initial.pc;
+  cpu.stkp = initial.s;
+  cpu.acc = initial.a;
+  cpu.x = initial.x;
+  cpu.y = initial.y;
+  cpu.status = initial.p;
+
+  let accesses = Rc::new(RefCell::new(Vec::new()));
+  let log = accesses.clone();
+  cpu
+    .get_mut_bus_ref()
+    .set_access_handler(Box::new(move |access, addr, data| {
+      log.borrow_mut().push((access, addr, data))
+    }));
+  cpu.step();
+
+  let registers = [
+    ("pc", cpu.pc, expected.pc),
+    ("s", cpu.stkp as u16, expected.s as u16),
+    ("a", cpu.acc as u16, expected.a as u16),
+    ("x", cpu.x as u16, expected.x as u16),
+    ("y", cpu.y as u16, expected.y as u16),
+    ("p", cpu.status as u16, expected.p as u16),
+  ];
+  for &(register, actual, expected) in &registers {
+    if actual != expected {
+      return Err(format!(
+        "{}: {} is {:02X}, expected {:02X}",
+        name, register, actual, expected
+      ));
+    }
+  }
+  for &(addr, data) in &expected.ram {
+    let actual = cpu.read_addr(addr);
+    if actual != data {
+      return Err(format!(
+        "{}: ${:04X} is {:02X}, expected {:02X}",
+        name, addr, actual, data
+      ));
+    }
+  }
+  let accesses = accesses.borrow();
+  let cycle_count = accesses.len().max(cycles.len());
+  for cycle in 0..cycle_count {
+    if accesses.get(cycle) != cycles.get(cycle) {
+      return Err(format!(
+        "{}: cycle {} was {:?}, expected {:?}",
+        name,
+        cycle + 1,
+        accesses.get(cycle),
+        cycles.get(cycle)
+      ));
+    }
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+fn get_tests_dir() -> PathBuf {
+  match env::var(TESTS_DIR_VAR) {
+    Ok(dir) => PathBuf::from(dir),
+    Err(_) => Path::new(env!("CARGO_MANIFEST_DIR")).join("roms/ProcessorTests"),
+  }
+}
+
+// Runs every opcode file in the suite's directory, skips the suite when it is not there
+#[cfg(test)]
+fn run_suite(dir: &str, variant: Variant) {
+  let path = get_tests_dir().join(dir);
+  if !path.is_dir() {
+    println!("Skipping {} tests, {} not found", variant, path.display());
+    return;
+  }
+
+  let mut failures = Vec::new();
+  let mut files = 0;
+  for opcode in 0..=0xFF {
+    let file = path.join(format!("{:02x}.json", opcode));
+    if is_skipped(opcode, variant) || !file.is_file() {
+      continue;
+    }
+    files += 1;
+    let text = fs::read_to_string(&file).unwrap();
+    let cases: Value =
+      serde_json::from_str(&text).unwrap_or_else(|error| panic!("{}: {}", file.display(), error));
+    let cases = cases
+      .as_array()
+      .unwrap_or_else(|| panic!("{}: expected an array of tests", file.display()));
+    let errors: Vec<String> = cases
+      .iter()
+      .filter_map(|case| run_case(case, variant).err())
+      .collect();
+    if let Some(error) = errors.first() {
+      failures.push(format!(
+        "{:02X}: {} of {} failed, first {}",
+        opcode,
+        errors.len(),
+        cases.len(),
+        error
+      ));
+    }
+  }
+  assert!(files > 0, "No opcode files in {}", path.display());
+  assert!(
+    failures.is_empty(),
+    "{} opcodes failed:\n{}",
+    failures.len(),
+    failures.join("\n")
+  );
+}
+
+#[test]
+fn nes6502() {
+  run_suite("nes6502/v1", Variant::Ricoh2A03);
+}
+
+#[test]
+fn nmos6502() {
+  run_suite("6502/v1", Variant::Nmos6502);
+}
+
+#[test]
+fn cmos65c02() {
+  run_suite("synertek65c02/v1", Variant::Cmos65C02);
+}
+
+// The runner itself, on cases written in the suite's format
+#[cfg(test)]
+mod runner {
+  use super::*;
+  const STA_ABSOLUTE: &str = r#"{
+    "name": "8d 00 03",
+    "initial": {"pc": 512, "s": 253, "a": 66, "x": 0, "y": 0, "p": 36,
+      "ram": [[512, 141], [513, 0], [514, 3], [768, 0]]},
+    "final": {"pc": 515, "s": 253, "a": 66, "x": 0, "y": 0, "p": 36,
+      "ram": [[512, 141], [513, 0], [514, 3], [768, 66]]},
+    "cycles": [[512, 141, "read"], [513, 0, "read"], [514, 3, "read"], [768, 66, "write"]]
+  }"#;
+  fn run(text: &str) -> Result<(), String> {
+    run_case(&serde_json::from_str(text).unwrap(), Variant::Ricoh2A03)
+  }
+  #[test]
+  fn passes_matching_case() {
+    assert_eq!(run(STA_ABSOLUTE), Ok(()));
+  }
+  #[test]
+  fn reports_register_difference() {
+    let case = STA_ABSOLUTE.replace(r#""pc": 515"#, r#""pc": 516"#);
+    assert_eq!(
+      run(&case),
+      Err("8d 00 03: pc is 203, expected 204".to_string())
+    );
+  }
+  #[test]
+  fn reports_ram_difference() {
+    let case = STA_ABSOLUTE.replace("[768, 66]]}", "[768, 67]]}");
+    assert_eq!(
+      run(&case),
+      Err("8d 00 03: $0300 is 42, expected 43".to_string())
+    );
+  }
+  #[test]
+  fn reports_bus_cycle_difference() {
+    let case = STA_ABSOLUTE.replace(r#"[768, 66, "write"]"#, r#"[768, 0, "read"]"#);
+    assert_eq!(
+      run(&case),
+      Err(
+        "8d 00 03: cycle 4 was Some((Write, 768, 66)), expected Some((Read, 768, 0))".to_string()
+      )
+    );
+  }
+  #[test]
+  fn reports_missing_bus_cycle() {
+    let case = STA_ABSOLUTE.replace(r#"[514, 3, "read"], "#, "");
+    assert!(run(&case).unwrap_err().contains("cycle 3"));
+  }
+}
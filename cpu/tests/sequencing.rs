@@ -0,0 +1,464 @@
+#[cfg(test)]
+use bus::{Access, Bus};
+
+#[cfg(test)]
+use cpu::instruction::{decode, AddressMode};
+
+#[cfg(test)]
+use cpu::{Cpu, Variant};
+
+#[cfg(test)]
+use data_flow::ReadWrite;
+
+#[cfg(test)]
+use std::cell::RefCell;
+
+#[cfg(test)]
+use std::rc::Rc;
+
+#[cfg(test)]
+const PROGRAM_ADDR: u16 = 0x0200;
+
+#[cfg(test)]
+type AccessLog = Rc<RefCell<Vec<(Access, u16, u8)>>>;
+
+#[cfg(test)]
+fn setup(variant: Variant, program: &[u8]) -> (Cpu, AccessLog) {
+  setup_with_bus(Bus::flat(), variant, program)
+}
+
+#[cfg(test)]
+fn setup_with_bus(bus: Bus, variant: Variant, program: &[u8]) -> (Cpu, AccessLog) {
+  let mut cpu = Cpu::with_variant(bus, variant);
+  for (offset, byte) in program.iter().enumerate() {
+    cpu.write_addr(PROGRAM_ADDR + offset as u16, *byte);
+  }
+  cpu.pc = PROGRAM_ADDR;
+
+  let accesses = Rc::new(RefCell::new(Vec::new()));
+  let log = accesses.clone();
+  cpu
+    .get_mut_bus_ref()
+    .set_access_handler(Box::new(move |access, addr, data| {
+      log.borrow_mut().push((access, addr, data))
+    }));
+  (cpu, accesses)
+}
+
+// Executes one instruction and returns the bus accesses it made, one per cycle
+#[cfg(test)]
+fn run(cpu: &mut Cpu, accesses: &AccessLog) -> Vec<(Access, u16, u8)> {
+  let tick = cpu.current_tick;
+  cpu.step();
+  let accesses = accesses.replace(Vec::new());
+  assert_eq!(accesses.len(), cpu.current_tick - tick);
+  accesses
+}
+
+#[cfg(test)]
+const R: Access = Access::Read;
+
+#[cfg(test)]
+const W: Access = Access::Write;
+
+#[cfg(test)]
+fn assert_every_opcode_takes_its_cycles(variant: Variant) {
+  for opcode in 0..=0xFF {
+    // Zero operands keep indexing on its page and branches on theirs
+    let (mut cpu, accesses) = setup(variant, &[opcode, 0x00, 0x00]);
+    let instruction = decode(opcode, variant);
+    let mut expected = instruction.get_cycles() as usize;
+    if let AddressMode::Relative = instruction.get_address_mode() {
+      if cpu.is_branch_taken(instruction.get_operation()) {
+        expected += 1;
+      }
+    }
+
+    cpu.step();
+    assert_eq!(cpu.current_tick, expected, "{:02X} cycles", opcode);
+    // Only the 65C02's eight cycle NOP has cycles without an access
+    if opcode != 0x5C || variant != Variant::Cmos65C02 {
+      assert_eq!(accesses.borrow().len(), expected, "{:02X} accesses", opcode);
+    }
+  }
+}
+
+#[test]
+fn every_opcode_takes_its_cycles() {
+  assert_every_opcode_takes_its_cycles(Variant::Ricoh2A03);
+  assert_every_opcode_takes_its_cycles(Variant::Cmos65C02);
+}
+
+#[cfg(test)]
+fn assert_next_read_addr_is_predicted(variant: Variant) {
+  for opcode in 0..=0xFF {
+    // Operands and indexes that cross pages, branches that are taken across one
+    for &(operand, status) in [(0xF0, 0x00), (0xF0, 0xFF)].iter() {
+      let (mut cpu, accesses) = setup(variant, &[opcode, operand, 0x30]);
+      cpu.x = 0x20;
+      cpu.y = 0x20;
+      cpu.status = status;
+      cpu.write_addr(0x00F0, 0xF0);
+      cpu.write_addr(0x00F1, 0x30);
+      loop {
+        let predicted = cpu.get_next_read_addr();
+        let count = accesses.borrow().len();
+        cpu.clock();
+        let accesses = accesses.borrow();
+        let access = accesses.get(count).map(|access| (access.0, access.1));
+        match predicted {
+          Some(addr) => assert_eq!(access, Some((R, addr)), "{:02X}", opcode),
+          None => assert_ne!(access.map(|access| access.0), Some(R), "{:02X}", opcode),
+        }
+        if cpu.is_cycle_complete() {
+          break;
+        }
+      }
+    }
+  }
+}
+
+// DMA halts the CPU on reads and repeats them, so it has to know them ahead
+#[test]
+fn next_read_addr_is_predicted() {
+  assert_next_read_addr_is_predicted(Variant::Ricoh2A03);
+  assert_next_read_addr_is_predicted(Variant::Cmos65C02);
+}
+
+#[cfg(test)]
+mod page_crossing {
+  use super::*;
+  #[test]
+  fn read_without_crossing_reads_once() {
+    // LDA $30F0,X
+    let (mut cpu, accesses) = setup(Variant::Ricoh2A03, &[0xBD, 0xF0, 0x30]);
+    cpu.x = 0x0F;
+    assert_eq!(
+      run(&mut cpu, &accesses),
+      vec![
+        (R, 0x0200, 0xBD),
+        (R, 0x0201, 0xF0),
+        (R, 0x0202, 0x30),
+        (R, 0x30FF, 0x00),
+      ]
+    );
+  }
+  #[test]
+  fn read_reads_unfixed_address_first() {
+    let (mut cpu, accesses) = setup(Variant::Ricoh2A03, &[0xBD, 0xF0, 0x30]);
+    cpu.x = 0x10;
+    cpu.write_addr(0x3100, 0x42);
+    assert_eq!(
+      run(&mut cpu, &accesses),
+      vec![
+        (R, 0x0200, 0xBD),
+        (R, 0x0201, 0xF0),
+        (R, 0x0202, 0x30),
+        (R, 0x3000, 0x00),
+        (R, 0x3100, 0x42),
+      ]
+    );
+  }
+  #[test]
+  fn store_always_reads_before_writing() {
+    // STA $30F0,X
+    let (mut cpu, accesses) = setup(Variant::Ricoh2A03, &[0x9D, 0xF0, 0x30]);
+    cpu.acc = 0x42;
+    cpu.x = 0x0F;
+    assert_eq!(
+      run(&mut cpu, &accesses)[3..],
+      [(R, 0x30FF, 0x00), (W, 0x30FF, 0x42)]
+    );
+  }
+  #[test]
+  fn indirect_y_reads_unfixed_address_first() {
+    // LDA ($10),Y
+    let (mut cpu, accesses) = setup(Variant::Ricoh2A03, &[0xB1, 0x10]);
+    cpu.write_addr(0x0010, 0xF0);
+    cpu.write_addr(0x0011, 0x30);
+    cpu.y = 0x10;
+    assert_eq!(
+      run(&mut cpu, &accesses)[2..],
+      [
+        (R, 0x0010, 0xF0),
+        (R, 0x0011, 0x30),
+        (R, 0x3000, 0x00),
+        (R, 0x3100, 0x00),
+      ]
+    );
+  }
+  #[test]
+  fn cmos_reads_last_operand_byte_instead() {
+    let (mut cpu, accesses) = setup(Variant::Cmos65C02, &[0xBD, 0xF0, 0x30]);
+    cpu.x = 0x10;
+    assert_eq!(
+      run(&mut cpu, &accesses)[3..],
+      [(R, 0x0202, 0x30), (R, 0x3100, 0x00)]
+    );
+  }
+}
+
+#[cfg(test)]
+mod read_modify_write {
+  use super::*;
+  #[test]
+  fn writes_unmodified_value_then_result() {
+    // INC $10
+    let (mut cpu, accesses) = setup(Variant::Ricoh2A03, &[0xE6, 0x10]);
+    cpu.write_addr(0x0010, 0x41);
+    assert_eq!(
+      run(&mut cpu, &accesses),
+      vec![
+        (R, 0x0200, 0xE6),
+        (R, 0x0201, 0x10),
+        (R, 0x0010, 0x41),
+        (W, 0x0010, 0x41),
+        (W, 0x0010, 0x42),
+      ]
+    );
+  }
+  #[test]
+  fn indexed_always_reads_unfixed_address() {
+    // ASL $30F0,X
+    let (mut cpu, accesses) = setup(Variant::Ricoh2A03, &[0x1E, 0xF0, 0x30]);
+    cpu.x = 0x01;
+    cpu.write_addr(0x30F1, 0x01);
+    assert_eq!(
+      run(&mut cpu, &accesses)[3..],
+      [
+        (R, 0x30F1, 0x01),
+        (R, 0x30F1, 0x01),
+        (W, 0x30F1, 0x01),
+        (W, 0x30F1, 0x02),
+      ]
+    );
+  }
+  #[test]
+  fn cmos_reads_twice_instead_of_writing_back() {
+    let (mut cpu, accesses) = setup(Variant::Cmos65C02, &[0xE6, 0x10]);
+    cpu.write_addr(0x0010, 0x41);
+    assert_eq!(
+      run(&mut cpu, &accesses)[2..],
+      [(R, 0x0010, 0x41), (R, 0x0010, 0x41), (W, 0x0010, 0x42)]
+    );
+  }
+}
+
+#[cfg(test)]
+mod branches {
+  use super::*;
+  #[test]
+  fn not_taken_reads_offset_only() {
+    // BCS +$10
+    let (mut cpu, accesses) = setup(Variant::Ricoh2A03, &[0xB0, 0x10]);
+    assert_eq!(
+      run(&mut cpu, &accesses),
+      vec![(R, 0x0200, 0xB0), (R, 0x0201, 0x10)]
+    );
+  }
+  #[test]
+  fn across_page_reads_unfixed_pc() {
+    // BCC -$10 from 0x0202 lands on 0x01F2
+    let (mut cpu, accesses) = setup(Variant::Ricoh2A03, &[0x90, 0xF0]);
+    assert_eq!(
+      run(&mut cpu, &accesses)[2..],
+      [(R, 0x0202, 0x00), (R, 0x02F2, 0x00)]
+    );
+    assert_eq!(cpu.pc, 0x01F2);
+  }
+}
+
+#[cfg(test)]
+mod stack {
+  use super::*;
+  #[test]
+  fn jsr_pushes_before_reading_high_byte() {
+    // JSR $3000
+    let (mut cpu, accesses) = setup(Variant::Ricoh2A03, &[0x20, 0x00, 0x30]);
+    assert_eq!(
+      run(&mut cpu, &accesses),
+      vec![
+        (R, 0x0200, 0x20),
+        (R, 0x0201, 0x00),
+        (R, 0x01FD, 0x00),
+        (W, 0x01FD, 0x02),
+        (W, 0x01FC, 0x02),
+        (R, 0x0202, 0x30),
+      ]
+    );
+    assert_eq!(cpu.pc, 0x3000);
+  }
+  #[test]
+  fn rts_reads_return_address_then_skips_it() {
+    let (mut cpu, accesses) = setup(Variant::Ricoh2A03, &[0x60]);
+    cpu.stkp = 0xFB;
+    cpu.write_addr(0x01FC, 0x02);
+    cpu.write_addr(0x01FD, 0x30);
+    assert_eq!(
+      run(&mut cpu, &accesses),
+      vec![
+        (R, 0x0200, 0x60),
+        (R, 0x0201, 0x00),
+        (R, 0x01FB, 0x00),
+        (R, 0x01FC, 0x02),
+        (R, 0x01FD, 0x30),
+        (R, 0x3002, 0x00),
+      ]
+    );
+    assert_eq!(cpu.pc, 0x3003);
+  }
+  #[test]
+  fn reset_reads_stack_without_writing() {
+    let (mut cpu, accesses) = setup(Variant::Ricoh2A03, &[]);
+    cpu.write_addr(0xFFFC, 0x00);
+    cpu.write_addr(0xFFFD, 0x80);
+    cpu.reset();
+    let accesses = run(&mut cpu, &accesses);
+    assert_eq!(
+      accesses[2..],
+      [
+        (R, 0x0100, 0x00),
+        (R, 0x01FF, 0x00),
+        (R, 0x01FE, 0x00),
+        (R, 0xFFFC, 0x00),
+        (R, 0xFFFD, 0x80),
+      ]
+    );
+    assert_eq!(cpu.pc, 0x8000);
+    assert_eq!(cpu.stkp, 0xFD);
+  }
+}
+
+#[cfg(test)]
+mod dma {
+  use super::*;
+  // NES memory map, so the APU's DMC is there to ask for samples
+  fn setup_nes(program: &[u8]) -> (Cpu, AccessLog) {
+    setup_with_bus(Bus::new(), Variant::Ricoh2A03, program)
+  }
+  fn clock(cpu: &mut Cpu, cycles: usize) {
+    for _ in 0..cycles {
+      cpu.clock();
+      cpu.get_mut_bus_ref().clock();
+    }
+  }
+  // A one byte sample from 0xC000, fetched as soon as it is enabled
+  fn start_sample(cpu: &mut Cpu) {
+    let bus = cpu.get_mut_bus_ref();
+    bus.write_addr(0x4012, 0x00);
+    bus.write_addr(0x4013, 0x00);
+    bus.write_addr(0x4015, 0x10);
+  }
+  #[test]
+  fn halts_on_read_and_repeats_it() {
+    // NOP
+    let (mut cpu, accesses) = setup_nes(&[0xEA]);
+    start_sample(&mut cpu);
+    clock(&mut cpu, 5);
+    assert_eq!(
+      *accesses.borrow(),
+      vec![
+        (R, 0x0200, 0xEA),
+        (R, 0x0200, 0xEA),
+        (R, 0x0200, 0xEA),
+        (R, 0xC000, 0x00),
+        (R, 0x0200, 0xEA),
+      ]
+    );
+    assert_eq!(cpu.current_tick, 5);
+    assert_eq!(cpu.get_bus_ref().get_dmc_dma_addr(), None);
+  }
+  #[test]
+  fn waits_for_writes() {
+    // PHA
+    let (mut cpu, accesses) = setup_nes(&[0x48, 0xEA]);
+    cpu.acc = 0x42;
+    clock(&mut cpu, 2);
+    start_sample(&mut cpu);
+    clock(&mut cpu, 5);
+    assert_eq!(
+      accesses.borrow()[2..],
+      [
+        (W, 0x01FD, 0x42),
+        (R, 0x0201, 0xEA),
+        (R, 0x0201, 0xEA),
+        (R, 0xC000, 0x00),
+        (R, 0x0201, 0xEA),
+      ]
+    );
+  }
+  // STA $4014 copying page 0x0300, filled with its offsets
+  fn setup_oam_dma(apu_cycles: usize) -> (Cpu, AccessLog) {
+    let (mut cpu, accesses) = setup_nes(&[0x8D, 0x14, 0x40, 0xEA]);
+    cpu.acc = 0x03;
+    for offset in 0..=0xFF {
+      cpu.write_addr(0x0300 + offset, offset as u8);
+    }
+    for _ in 0..apu_cycles {
+      cpu.get_mut_bus_ref().clock();
+    }
+    clock(&mut cpu, 4);
+    accesses.borrow_mut().clear();
+    (cpu, accesses)
+  }
+  // Cycles until the CPU runs again
+  fn get_stall(cpu: &mut Cpu) -> usize {
+    let mut cycles = 0;
+    clock(cpu, 1);
+    while cpu.is_halted() {
+      cycles += 1;
+      clock(cpu, 1);
+    }
+    cycles
+  }
+  #[test]
+  fn oam_dma_copies_page_to_ppu() {
+    let (mut cpu, accesses) = setup_oam_dma(0);
+    assert_eq!(get_stall(&mut cpu), 513);
+    let oam: Vec<u8> = (0..=0xFF).collect();
+    assert_eq!(cpu.get_bus_ref().get_ppu().get_oam(), &oam[..]);
+    let accesses = accesses.borrow();
+    // Halted on the next opcode fetch, then a get and a put for each byte
+    assert_eq!(accesses[0], (R, 0x0203, 0xEA));
+    assert_eq!(
+      accesses[1..5],
+      [
+        (R, 0x0300, 0x00),
+        (W, 0x2004, 0x00),
+        (R, 0x0301, 0x01),
+        (W, 0x2004, 0x01),
+      ]
+    );
+    assert_eq!(accesses[512], (W, 0x2004, 0xFF));
+    assert_eq!(accesses[513], (R, 0x0203, 0xEA));
+  }
+  #[test]
+  fn oam_dma_aligns_to_get_cycle() {
+    let (mut cpu, accesses) = setup_oam_dma(1);
+    assert_eq!(get_stall(&mut cpu), 514);
+    assert_eq!(
+      accesses.borrow()[..3],
+      [(R, 0x0203, 0xEA), (R, 0x0203, 0xEA), (R, 0x0300, 0x00),]
+    );
+  }
+  #[test]
+  fn oam_dma_waits_for_dmc_fetch() {
+    let (mut cpu, accesses) = setup_oam_dma(0);
+    clock(&mut cpu, 100);
+    start_sample(&mut cpu);
+    assert_eq!(100 + get_stall(&mut cpu), 513 + 2);
+    assert_eq!(cpu.get_bus_ref().get_dmc_dma_addr(), None);
+    let oam: Vec<u8> = (0..=0xFF).collect();
+    assert_eq!(cpu.get_bus_ref().get_ppu().get_oam(), &oam[..]);
+    // The sample is fetched in place of a get, the put after it realigns
+    let accesses = accesses.borrow();
+    let fetch = accesses
+      .iter()
+      .position(|access| access.1 == 0xC000)
+      .unwrap();
+    assert_eq!(accesses[fetch - 1].0, W);
+    assert_eq!(accesses[fetch + 1], (R, 0x0203, 0xEA));
+    assert_eq!(accesses[fetch + 2].0, R);
+    assert_eq!(accesses[fetch + 3].1, 0x2004);
+  }
+}
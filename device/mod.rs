@@ -1,30 +1,79 @@
+use std::error;
+use std::fmt;
+
 const MAX_MEMORY: usize = 65_536;
 
+#[derive(Clone, Debug, PartialEq)]
+pub enum DeviceError {
+  OutOfRange { addr: usize, size: usize }, // Address past the end of the device
+  ReadOnly(usize),                         // Write to memory that cannot be written
+  Unmapped(u16),                           // Nothing answers this bus address
+  Fault(String),                           // Device could not complete the access
+}
+
+impl fmt::Display for DeviceError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      DeviceError::OutOfRange { addr, size } => {
+        write!(
+          f,
+          "Address {:#X} is outside a device of {:#X} bytes",
+          addr, size
+        )
+      }
+      DeviceError::ReadOnly(addr) => write!(f, "Address {:#X} is read-only", addr),
+      DeviceError::Unmapped(addr) => write!(f, "Nothing is mapped at {:#06X}", addr),
+      DeviceError::Fault(ref reason) => write!(f, "Device fault: {}", reason),
+    }
+  }
+}
+
+impl error::Error for DeviceError {}
+
 pub trait ExactSize {
   fn mem_size(&self) -> usize;
 }
 
 pub trait Readable {
-  fn read_addr(&self, addr: usize) -> Result<u8, u16>;
+  fn read_addr(&self, addr: usize) -> Result<u8, DeviceError>;
 }
 
 pub trait Writable {
-  fn write_addr(&mut self, addr: usize, data: u8) -> Result<u8, u16>;
+  fn write_addr(&mut self, addr: usize, data: u8) -> Result<(), DeviceError>;
 }
 
 pub struct Device {
   memory: Box<[u8]>,
+  writable: bool,
 }
 
 impl Device {
   pub fn new(memory: Box<[u8]>) -> Device {
-    println!("Memory size: {:#x?}", memory.len());
-    
     if memory.len() > MAX_MEMORY {
       panic!("Maximum memory size exceeded. Must not exceed {}", MAX_MEMORY)
     }
 
-    Device { memory }
+    Device {
+      memory,
+      writable: true,
+    }
+  }
+
+  pub fn read_only(memory: Box<[u8]>) -> Device {
+    Device {
+      writable: false,
+      ..Device::new(memory)
+    }
+  }
+
+  fn check_range(&self, addr: usize) -> Result<(), DeviceError> {
+    match self.memory.len() > addr {
+      true => Ok(()),
+      false => Err(DeviceError::OutOfRange {
+        addr,
+        size: self.mem_size(),
+      }),
+    }
   }
 }
 
@@ -35,22 +84,42 @@ impl ExactSize for Device {
 }
 
 impl Writable for Device {
-  fn write_addr(&mut self, addr: usize, data: u8) -> Result<u8, u16> {
-    if self.memory.len() > addr {
-      self.memory[addr] = data;
-      Ok(data)
-    } else {
-      Err(self.mem_size() as u16)
+  fn write_addr(&mut self, addr: usize, data: u8) -> Result<(), DeviceError> {
+    self.check_range(addr)?;
+    if !self.writable {
+      return Err(DeviceError::ReadOnly(addr));
     }
+    self.memory[addr] = data;
+    Ok(())
   }
 }
 
 impl Readable for Device {
-  fn read_addr(&self, addr: usize) -> Result<u8, u16> {
-    if self.memory.len() > addr {
-      Ok(self.memory[addr])
-    } else {
-      Err(self.mem_size() as u16)
-    }
+  fn read_addr(&self, addr: usize) -> Result<u8, DeviceError> {
+    self.check_range(addr)?;
+    Ok(self.memory[addr])
+  }
+}
+
+// Anything that can be attached to the bus as a memory-mapped device
+pub trait Peripheral: ExactSize + Readable + Writable {}
+
+impl<T: ExactSize + Readable + Writable> Peripheral for T {}
+
+impl ExactSize for Box<dyn Peripheral> {
+  fn mem_size(&self) -> usize {
+    (**self).mem_size()
+  }
+}
+
+impl Readable for Box<dyn Peripheral> {
+  fn read_addr(&self, addr: usize) -> Result<u8, DeviceError> {
+    (**self).read_addr(addr)
+  }
+}
+
+impl Writable for Box<dyn Peripheral> {
+  fn write_addr(&mut self, addr: usize, data: u8) -> Result<(), DeviceError> {
+    (**self).write_addr(addr, data)
   }
 }
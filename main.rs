@@ -1,22 +1,36 @@
+#[cfg(test)]
+extern crate serde_json;
 extern crate termion;
 extern crate tui;
 
+mod apu;
+mod audio;
 mod bus;
+mod cartridge;
+mod connection;
 mod cpu;
 mod data_flow;
+mod device;
 mod event;
-
-use bus::Bus;
-use cpu::instruction::{get_instruction, Instruction};
-use cpu::Cpu;
+mod mapper;
+mod nes;
+mod ppu;
+mod program;
+
+use audio::get_channel_name;
+use cartridge::Cartridge;
+use cpu::instruction::{decode, Instruction};
+use cpu::{Cpu, Variant};
 use data_flow::{HexByte, HexSlice, MemoryAddress, ReadRange, ReadWrite};
 use event::{Config, Event, Events};
+use nes::trace::trace;
+use nes::Nes;
+use program::Program;
 
-use std::convert::TryInto;
 use std::env;
-use std::fs::File;
+use std::fs;
 use std::io;
-use std::io::prelude::*;
+use std::path::Path;
 use std::time::Duration;
 
 use termion::event::Key;
@@ -28,15 +42,21 @@ use tui::style::{Color, Modifier, Style};
 use tui::widgets::{Block, Borders, Paragraph, Text, Widget};
 use tui::{Frame, Terminal};
 
-const CARTRIDGE_ADDR: u16 = 0x4020;
 const ZERO_PAGE_START: u16 = 0x0000;
 const STACK_BASE_ADDR: u16 = 0x0100;
 const DEFAULT_TICK_RATE: u64 = 200;
 const BYTES_PER_ROW: u16 = 16;
+const FLAT_INSTRUCTION_LIMIT: usize = 100_000_000;
 // const MEMORY_WINDOW_START_ADDRESS: u16 = 0xC000;
 
 fn main() -> Result<(), failure::Error> {
   let args: Vec<String> = env::args().collect();
+  if args[1] == "--flat" {
+    return run_flat_binary(&args[2..]);
+  }
+  if args[1] == "--wav" {
+    return export_wav(&args[2..]);
+  }
   let filename = &args[1];
   let events = Events::with_config(Config {
     tick_rate: Duration::from_millis(DEFAULT_TICK_RATE),
@@ -49,18 +69,16 @@ fn main() -> Result<(), failure::Error> {
   let mut current_tick = 4;
   let mut new_tick: bool;
 
-  let mut cpu = Cpu::new();
+  let mut nes = load_program(filename)?;
 
-  load_program_memory(cpu.get_mut_bus_ref(), filename)?;
-
-  cpu.reset();
-  // This section sets the clock to 4 after the reset
-  cpu.step();
+  nes.reset();
+  // This section sets the clock to 7 after the reset
+  nes.step_instruction();
   println!("{}", termion::clear::All);
 
   loop {
-    if current_tick != *cpu.get_current_tick() {
-      current_tick = *cpu.get_current_tick();
+    if current_tick != *nes.get_cpu().get_current_tick() {
+      current_tick = *nes.get_cpu().get_current_tick();
       new_tick = true;
     } else {
       new_tick = false;
@@ -70,17 +88,27 @@ fn main() -> Result<(), failure::Error> {
     //   cpu.clock();
     //   continue;
     // }
-    draw_ui(&mut terminal, &mut cpu, &mut history, new_tick)?;
+    // Populate the log, in the same format as nestest.log
+    if new_tick {
+      history.push(Text::raw(format!("{}\n", trace(&nes))));
+    }
+    draw_ui(&mut terminal, nes.get_mut_cpu(), &mut history)?;
     match events.next()? {
       Event::Input(key) => match key {
         Key::Char(' ') => {
-          cpu.step();
+          nes.step_instruction();
         }
         Key::Right => {
-          cpu.clock();
+          nes.step_cycle();
+        }
+        Key::Down => {
+          nes.step_scanline();
+        }
+        Key::Char('f') => {
+          nes.step_frame();
         }
         Key::Char('r') => {
-          cpu.reset();
+          nes.reset();
         }
         Key::Char('q') => {
           break;
@@ -95,7 +123,7 @@ fn main() -> Result<(), failure::Error> {
   Ok(())
 }
 
-fn draw_ui<B>(terminal: &mut Terminal<B>, cpu: &mut Cpu, history: &mut Vec<Text>, new_tick: bool) -> Result<(), io::Error>
+fn draw_ui<B>(terminal: &mut Terminal<B>, cpu: &mut Cpu, history: &mut Vec<Text>) -> Result<(), io::Error>
 where
   B: Backend,
 {
@@ -106,101 +134,74 @@ where
       .constraints([Constraint::Percentage(60), Constraint::Percentage(40)].as_ref())
       .split(f.size());
     draw_memory_panel(&mut f, chunks[0], cpu);
-    draw_instructions_panel(&mut f, chunks[1], cpu, history, new_tick);
+    draw_instructions_panel(&mut f, chunks[1], cpu, history);
   })
 }
 
-fn load_program_memory(bus: &mut Bus, filename: &String) -> std::io::Result<()> {
-  let mut cartridge = File::open(filename)?;
-  let metadata = cartridge.metadata()?;
-  let mut cartridge_memory = Vec::with_capacity(metadata.len().try_into().unwrap());
-  cartridge.read_to_end(&mut cartridge_memory)?;
+fn load_program(filename: &str) -> Result<Nes, failure::Error> {
+  let cartridge = Cartridge::from_file(filename)?;
 
-  if filename.contains("/nestest.nes") {
-    load_nestest(bus, &cartridge_memory);
-  } else {
-    let mut current_address = CARTRIDGE_ADDR;
-    for byte in cartridge_memory {
-      bus.write_addr(current_address, byte);
-      if current_address == 0xFFFF {
-        break;
-      }
-      current_address += 1;
-    }
-  }
-
-  Ok(())
+  Ok(Nes::from_cartridge(cartridge)?)
 }
 
-fn load_nestest(bus: &mut Bus, memory: &Vec<u8>) {
-  let mirrored_memory = &memory[0x0010..0x4010];
-  let mut current_address = 0x8000 - 1;
-
-  for byte in mirrored_memory {
-    bus.write_addr(current_address, *byte);
-    current_address += 1;
+// Headless: --wav <rom> <frames> <sample rate> <output> [--channels]. With --channels each
+// channel is also written on its own next to the output, e.g. out-pulse1.wav
+fn export_wav(args: &[String]) -> Result<(), failure::Error> {
+  let per_channel = args.len() == 5 && args[4] == "--channels";
+  if args.len() != 4 && !per_channel {
+    return Err(failure::err_msg(
+      "Usage: --wav <rom> <frames> <sample rate> <output> [--channels]",
+    ));
   }
-  current_address = 0xC000 - 1;
-  for byte in mirrored_memory {
-    current_address += 1;
-    bus.write_addr(current_address, *byte);
+  let mut nes = load_program(&args[0])?;
+  nes.reset();
+  let recording = audio::record(&mut nes, args[1].parse()?, args[2].parse()?, per_channel)?;
+  let output = Path::new(&args[3]);
+  fs::write(output, recording.to_wav())?;
+  let stem = output.with_extension("");
+  for (channel, samples) in recording.channels.iter() {
+    let path = format!("{}-{}.wav", stem.display(), get_channel_name(*channel));
+    fs::write(path, audio::wav::encode(samples, recording.sample_rate))?;
   }
+  println!(
+    "Wrote {} samples at {} Hz",
+    recording.mix.len(),
+    recording.sample_rate
+  );
+  Ok(())
 }
 
-fn log(cpu: &Cpu, history: &mut Vec<Text>) {
-  let current_clock_cycle = cpu.get_current_tick();
-  let ppc = MemoryAddress::new(*cpu.get_ppc());
-
-  let opcode = cpu.get_opcode_at(*cpu.get_ppc());
-  let hex_opcode = HexByte::new(opcode);
-  let instruction = get_instruction(opcode);
-
-  // Format data addr
-  let data_addr = *cpu.get_addr_of_data();
-  let data_addr_struct = MemoryAddress::new(data_addr);
-  let data_hi = (data_addr & 0xFF00) >> 8;
-  let data_lo = data_addr & 0x00FF;
-  let data_lo_hex = HexByte::new(data_lo as u8);
-  let data_hi_hex = HexByte::new(data_hi as u8);
-
-  // Registers
-  let status_hex = HexByte::new(*cpu.get_status());
-  let acc_hex = HexByte::new(*cpu.get_acc());
-  let x_hex = HexByte::new(*cpu.get_x());
-  let y_hex = HexByte::new(*cpu.get_y());
-  let stack_ptr_hex = HexByte::new(*cpu.get_stkp());
-  
-  history.push(
-    Text::raw(
-      format!("{}  {} {} {}  {} ${}                       A:{} X:{} Y:{} P:{} SP:{} PPU:{},{} CYC:{}\n",
-        ppc,
-        hex_opcode,
-        data_lo_hex,
-        data_hi_hex,
-        instruction.get_operation(),
-        data_addr_struct,
-        acc_hex,
-        x_hex,
-        y_hex,
-        status_hex,
-        stack_ptr_hex,
-        "000",
-        "000",
-        current_clock_cycle
-      )
-    )
+// Headless: --flat <binary> <load addr> <start addr> <success addr>, addresses in hex
+fn run_flat_binary(args: &[String]) -> Result<(), failure::Error> {
+  if args.len() != 4 {
+    return Err(failure::err_msg(
+      "Usage: --flat <binary> <load addr> <start addr> <success addr>",
+    ));
+  }
+  let bytes = fs::read(&args[0])?;
+  let addrs = args[1..]
+    .iter()
+    .map(|arg| u16::from_str_radix(arg.trim_start_matches("0x").trim_start_matches('$'), 16))
+    .collect::<Result<Vec<u16>, _>>()?;
+  let mut program = Program::load(&bytes, addrs[0], addrs[1], Variant::Nmos6502)?;
+  let trap = program.run_until_trap(FLAT_INSTRUCTION_LIMIT)?;
+  println!(
+    "Trapped at {:04X} after {} instructions and {} cycles",
+    trap.addr, trap.instructions, trap.cycles
   );
+  match trap.addr == addrs[2] {
+    true => Ok(()),
+    false => Err(failure::err_msg(format!(
+      "Failed, expected a trap at {:04X}",
+      addrs[2]
+    ))),
+  }
 }
 
-fn draw_instructions_panel<B>(f: &mut Frame<B>, area: Rect, cpu: &mut Cpu, history: &mut Vec<Text>, new_tick: bool)
+fn draw_instructions_panel<B>(f: &mut Frame<B>, area: Rect, cpu: &mut Cpu, history: &mut Vec<Text>)
 where
   B: Backend,
 {
-  // Populate the log
-  if new_tick {
-    log(cpu, history);
-  }
-
   // Write to the screen
   // let addr_hex = MemoryAddress::new(MEMORY_WINDOW_START_ADDRESS);
   // let memory_view_title = format!(" Program memory starting at 0x{} ", addr_hex);
@@ -306,7 +307,7 @@ where
   text.push(Text::raw("\nExecuted opcode: "));
   let opcode = cpu.get_opcode_at(*cpu.get_ppc());
   let hex_opcode = HexByte::new(opcode);
-  let instruction = get_instruction(opcode);
+  let instruction = decode(opcode, cpu.get_variant());
   let data_addr = *cpu.get_addr_of_data();
   let data_addr_struct = MemoryAddress::new(data_addr);
   let data = HexByte::new(cpu.read_addr(data_addr));
@@ -324,6 +325,14 @@ where
   let tick_counter = cpu.get_current_tick();
   text.push(Text::raw(format!("{}", tick_counter)));
 
+  if cpu.is_jammed() {
+    let ppc = MemoryAddress::new(*cpu.get_ppc());
+    text.push(Text::styled(
+      format!("\nCPU jammed at ${}, press r to reset", ppc),
+      Style::default().modifier(Modifier::BOLD).fg(Color::Red),
+    ));
+  }
+
   Paragraph::new(text.iter())
     .block(
       Block::default()
@@ -491,7 +500,7 @@ fn draw_memory_page<B>(
         _ => (),
       };
     } else {
-      text.push(Text::raw(format!("{}", HexSlice::new(range))));
+      text.push(Text::raw(format!("{}", HexSlice::new(&range))));
     }
     text.push(Text::raw("\n"));
   }
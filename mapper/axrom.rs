@@ -0,0 +1,82 @@
+// Source: https://wiki.nesdev.com/w/index.php/AxROM
+
+use cartridge::{Cartridge, Mirroring};
+use mapper::{Mapper, MapperError};
+
+const PRG_BANK_SIZE: usize = 32 * 1024; // 32 KB
+const CHR_SIZE: usize = 8 * 1024; // 8 KB
+
+pub struct Axrom {
+  prg_rom: Vec<u8>,
+  chr: Vec<u8>,
+  chr_is_ram: bool,
+  bus_conflicts: bool,
+  register: u8, // ---M-PPP: nametable page and 32 KB PRG bank
+}
+
+impl Axrom {
+  pub fn new(cartridge: Cartridge) -> Result<Axrom, MapperError> {
+    let prg_rom = cartridge.get_prg_rom().to_vec();
+    if !prg_rom.len().is_multiple_of(PRG_BANK_SIZE) {
+      return Err(MapperError::InvalidPrgRomSize(prg_rom.len()));
+    }
+
+    let chr_is_ram = cartridge.get_chr_rom().is_empty();
+    let chr = match chr_is_ram {
+      true => vec![0; CHR_SIZE],
+      false => cartridge.get_chr_rom().to_vec(),
+    };
+    if chr.len() != CHR_SIZE {
+      return Err(MapperError::InvalidChrSize(chr.len()));
+    }
+
+    Ok(Axrom {
+      prg_rom,
+      chr,
+      chr_is_ram,
+      // Only AMROM (submapper 2) has bus conflicts. ANROM and AOROM games rely on their absence,
+      // so an unspecified submapper is treated as conflict free
+      bus_conflicts: cartridge.get_submapper() == 2,
+      register: 0,
+    })
+  }
+}
+
+impl Mapper for Axrom {
+  fn cpu_read(&self, addr: u16) -> Option<u8> {
+    match addr {
+      0x8000..=0xFFFF => {
+        let bank = (self.register & 0x07) as usize % (self.prg_rom.len() / PRG_BANK_SIZE);
+        Some(self.prg_rom[bank * PRG_BANK_SIZE + (addr as usize & (PRG_BANK_SIZE - 1))])
+      }
+      _ => None,
+    }
+  }
+
+  fn cpu_write(&mut self, addr: u16, data: u8) {
+    if addr >= 0x8000 {
+      // The ROM drives the bus at the same time as the CPU, the result is their AND
+      self.register = match self.bus_conflicts {
+        true => data & self.cpu_read(addr).unwrap_or(0xFF),
+        false => data,
+      };
+    }
+  }
+
+  fn ppu_read(&mut self, addr: u16) -> u8 {
+    self.chr[(addr & 0x1FFF) as usize]
+  }
+
+  fn ppu_write(&mut self, addr: u16, data: u8) {
+    if self.chr_is_ram {
+      self.chr[(addr & 0x1FFF) as usize] = data;
+    }
+  }
+
+  fn get_mirroring(&self) -> Mirroring {
+    match self.register & 0x10 {
+      0 => Mirroring::SingleScreenLower,
+      _ => Mirroring::SingleScreenUpper,
+    }
+  }
+}
@@ -0,0 +1,72 @@
+// Source: https://wiki.nesdev.com/w/index.php/CNROM
+
+use cartridge::{Cartridge, Mirroring};
+use mapper::{Mapper, MapperError};
+
+const PRG_ROM_ADDR: u16 = 0x8000;
+const NROM_128_SIZE: usize = 16 * 1024; // 16 KB
+const NROM_256_SIZE: usize = 32 * 1024; // 32 KB
+const CHR_BANK_SIZE: usize = 8 * 1024; // 8 KB
+
+pub struct Cnrom {
+  prg_rom: Vec<u8>,
+  chr_rom: Vec<u8>,
+  mirroring: Mirroring,
+  bus_conflicts: bool,
+  chr_bank: u8, // 8 KB bank at PPU 0x0000-0x1FFF
+}
+
+impl Cnrom {
+  pub fn new(cartridge: Cartridge) -> Result<Cnrom, MapperError> {
+    let prg_rom = cartridge.get_prg_rom().to_vec();
+    match prg_rom.len() {
+      NROM_128_SIZE | NROM_256_SIZE => (),
+      size => return Err(MapperError::InvalidPrgRomSize(size)),
+    };
+
+    let chr_rom = cartridge.get_chr_rom().to_vec();
+    if chr_rom.is_empty() || !chr_rom.len().is_multiple_of(CHR_BANK_SIZE) {
+      return Err(MapperError::InvalidChrSize(chr_rom.len()));
+    }
+
+    Ok(Cnrom {
+      prg_rom,
+      chr_rom,
+      mirroring: cartridge.get_mirroring(),
+      // Submapper 1 marks boards without bus conflicts, 0 and 2 are treated as CNROM
+      bus_conflicts: cartridge.get_submapper() != 1,
+      chr_bank: 0,
+    })
+  }
+}
+
+impl Mapper for Cnrom {
+  fn cpu_read(&self, addr: u16) -> Option<u8> {
+    match addr {
+      // A 16 KB PRG ROM is mirrored into 0xC000-0xFFFF
+      0x8000..=0xFFFF => Some(self.prg_rom[(addr - PRG_ROM_ADDR) as usize % self.prg_rom.len()]),
+      _ => None,
+    }
+  }
+
+  fn cpu_write(&mut self, addr: u16, data: u8) {
+    if addr >= 0x8000 {
+      // The ROM drives the bus at the same time as the CPU, the result is their AND
+      self.chr_bank = match self.bus_conflicts {
+        true => data & self.cpu_read(addr).unwrap_or(0xFF),
+        false => data,
+      };
+    }
+  }
+
+  fn ppu_read(&mut self, addr: u16) -> u8 {
+    let bank = self.chr_bank as usize % (self.chr_rom.len() / CHR_BANK_SIZE);
+    self.chr_rom[bank * CHR_BANK_SIZE + (addr & 0x1FFF) as usize]
+  }
+
+  fn ppu_write(&mut self, _addr: u16, _data: u8) {}
+
+  fn get_mirroring(&self) -> Mirroring {
+    self.mirroring
+  }
+}
@@ -0,0 +1,65 @@
+// Source: https://wiki.nesdev.com/w/index.php/GxROM
+
+use cartridge::{Cartridge, Mirroring};
+use mapper::{Mapper, MapperError};
+
+const PRG_BANK_SIZE: usize = 32 * 1024; // 32 KB
+const CHR_BANK_SIZE: usize = 8 * 1024; // 8 KB
+
+pub struct Gxrom {
+  prg_rom: Vec<u8>,
+  chr_rom: Vec<u8>,
+  mirroring: Mirroring,
+  register: u8, // --PP--CC: 32 KB PRG bank and 8 KB CHR bank
+}
+
+impl Gxrom {
+  pub fn new(cartridge: Cartridge) -> Result<Gxrom, MapperError> {
+    let prg_rom = cartridge.get_prg_rom().to_vec();
+    if !prg_rom.len().is_multiple_of(PRG_BANK_SIZE) {
+      return Err(MapperError::InvalidPrgRomSize(prg_rom.len()));
+    }
+
+    let chr_rom = cartridge.get_chr_rom().to_vec();
+    if chr_rom.is_empty() || !chr_rom.len().is_multiple_of(CHR_BANK_SIZE) {
+      return Err(MapperError::InvalidChrSize(chr_rom.len()));
+    }
+
+    Ok(Gxrom {
+      prg_rom,
+      chr_rom,
+      mirroring: cartridge.get_mirroring(),
+      register: 0,
+    })
+  }
+}
+
+impl Mapper for Gxrom {
+  fn cpu_read(&self, addr: u16) -> Option<u8> {
+    match addr {
+      0x8000..=0xFFFF => {
+        let bank = ((self.register >> 4) & 0x03) as usize % (self.prg_rom.len() / PRG_BANK_SIZE);
+        Some(self.prg_rom[bank * PRG_BANK_SIZE + (addr as usize & (PRG_BANK_SIZE - 1))])
+      }
+      _ => None,
+    }
+  }
+
+  fn cpu_write(&mut self, addr: u16, data: u8) {
+    if addr >= 0x8000 {
+      // All GxROM boards have bus conflicts, the result is the AND of CPU and ROM
+      self.register = data & self.cpu_read(addr).unwrap_or(0xFF);
+    }
+  }
+
+  fn ppu_read(&mut self, addr: u16) -> u8 {
+    let bank = (self.register & 0x03) as usize % (self.chr_rom.len() / CHR_BANK_SIZE);
+    self.chr_rom[bank * CHR_BANK_SIZE + (addr & 0x1FFF) as usize]
+  }
+
+  fn ppu_write(&mut self, _addr: u16, _data: u8) {}
+
+  fn get_mirroring(&self) -> Mirroring {
+    self.mirroring
+  }
+}
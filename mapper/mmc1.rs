@@ -0,0 +1,185 @@
+// Source: https://wiki.nesdev.com/w/index.php/MMC1
+
+use cartridge::{Cartridge, Mirroring};
+use mapper::{Mapper, MapperError};
+
+const PRG_RAM_ADDR: u16 = 0x6000;
+const PRG_BANK_SIZE: usize = 16 * 1024; // 16 KB
+const PRG_OUTER_BANK_SIZE: usize = 256 * 1024; // 256 KB, selected by CHR bank bit 4 on SUROM
+const CHR_BANK_SIZE: usize = 4 * 1024; // 4 KB
+const CHR_RAM_SIZE: usize = 8 * 1024; // 8 KB
+const PRG_RAM_SIZE: usize = 8 * 1024; // 8 KB
+const SHIFT_RESET: u8 = 0b1_0000; // Marker bit, reaches bit 0 after the fifth write
+const CONTROL_RESET: u8 = 0x0C; // PRG mode 3: fix last bank at 0xC000
+
+pub struct Mmc1 {
+  prg_rom: Vec<u8>,
+  prg_ram: Vec<u8>,
+  chr: Vec<u8>,
+  chr_is_ram: bool,
+  shift: u8,      // Serial load register
+  control: u8,    // 0x8000-0x9FFF: CPPMM
+  chr_bank_0: u8, // 0xA000-0xBFFF
+  chr_bank_1: u8, // 0xC000-0xDFFF
+  prg_bank: u8,   // 0xE000-0xFFFF: RPPPP
+  cycle: u64,
+  last_write_cycle: Option<u64>,
+}
+
+impl Mmc1 {
+  pub fn new(cartridge: Cartridge) -> Result<Mmc1, MapperError> {
+    let prg_rom = cartridge.get_prg_rom().to_vec();
+    if !prg_rom.len().is_multiple_of(PRG_BANK_SIZE) || prg_rom.len() > 2 * PRG_OUTER_BANK_SIZE {
+      return Err(MapperError::InvalidPrgRomSize(prg_rom.len()));
+    }
+
+    let chr_is_ram = cartridge.get_chr_rom().is_empty();
+    let chr = match chr_is_ram {
+      true => vec![0; CHR_RAM_SIZE],
+      false => cartridge.get_chr_rom().to_vec(),
+    };
+    if !chr.len().is_multiple_of(CHR_BANK_SIZE) {
+      return Err(MapperError::InvalidChrSize(chr.len()));
+    }
+
+    let prg_ram_size = match cartridge.get_prg_ram_size() + cartridge.get_prg_nvram_size() {
+      0 => PRG_RAM_SIZE,
+      size => size,
+    };
+
+    Ok(Mmc1 {
+      prg_rom,
+      prg_ram: vec![0; prg_ram_size],
+      chr,
+      chr_is_ram,
+      shift: SHIFT_RESET,
+      control: CONTROL_RESET,
+      chr_bank_0: 0,
+      chr_bank_1: 0,
+      prg_bank: 0,
+      cycle: 0,
+      last_write_cycle: None,
+    })
+  }
+
+  fn write_register(&mut self, addr: u16, data: u8) {
+    // Of writes on consecutive cycles, like the two of a read-modify-write, only the first counts
+    let consecutive = self
+      .last_write_cycle
+      .is_some_and(|cycle| cycle + 1 == self.cycle);
+    self.last_write_cycle = Some(self.cycle);
+    if consecutive {
+      return;
+    }
+
+    // Writing a value with bit 7 set resets the shift register and locks PRG mode 3
+    if data & 0x80 > 0 {
+      self.shift = SHIFT_RESET;
+      self.control |= CONTROL_RESET;
+      return;
+    }
+
+    let complete = self.shift & 0x01 > 0;
+    self.shift = (self.shift >> 1) | ((data & 0x01) << 4);
+
+    // On the fifth write, bits 13 and 14 of the address select the target register
+    if complete {
+      let value = self.shift;
+      match addr & 0xE000 {
+        0x8000 => self.control = value,
+        0xA000 => self.chr_bank_0 = value,
+        0xC000 => self.chr_bank_1 = value,
+        _ => self.prg_bank = value,
+      }
+      self.shift = SHIFT_RESET;
+    }
+  }
+
+  fn is_prg_ram_enabled(&self) -> bool {
+    self.prg_bank & 0x10 == 0
+  }
+
+  fn get_prg_offset(&self, addr: u16) -> usize {
+    let bank_count = self.prg_rom.len() / PRG_BANK_SIZE;
+    // SUROM/SXROM use CHR bank bit 4 to select the 256 KB half of the PRG ROM
+    let outer = match self.prg_rom.len() > PRG_OUTER_BANK_SIZE {
+      true => (self.chr_bank_0 & 0x10) as usize,
+      false => 0,
+    };
+    let last_bank = (outer | 0x0F).min(bank_count - 1);
+    let selected = outer | (self.prg_bank & 0x0F) as usize;
+    let bank = match ((self.control >> 2) & 0x03, addr) {
+      // 32 KB mode, the low bit of the bank number is ignored
+      (0, 0x8000..=0xBFFF) | (1, 0x8000..=0xBFFF) => selected & !0x01,
+      (0, _) | (1, _) => selected | 0x01,
+      // Fix first bank at 0x8000, switch 16 KB bank at 0xC000
+      (2, 0x8000..=0xBFFF) => outer,
+      (2, _) => selected,
+      // Switch 16 KB bank at 0x8000, fix last bank at 0xC000
+      (_, 0x8000..=0xBFFF) => selected,
+      (_, _) => last_bank,
+    };
+    (bank % bank_count) * PRG_BANK_SIZE + (addr as usize & (PRG_BANK_SIZE - 1))
+  }
+
+  fn get_chr_offset(&self, addr: u16) -> usize {
+    let bank_count = self.chr.len() / CHR_BANK_SIZE;
+    let bank = match (self.control & 0x10 > 0, addr) {
+      // 8 KB mode, the low bit of the bank number is ignored
+      (false, 0x0000..=0x0FFF) => self.chr_bank_0 & !0x01,
+      (false, _) => self.chr_bank_0 | 0x01,
+      // Two independent 4 KB banks
+      (true, 0x0000..=0x0FFF) => self.chr_bank_0,
+      (true, _) => self.chr_bank_1,
+    };
+    (bank as usize % bank_count) * CHR_BANK_SIZE + (addr as usize & (CHR_BANK_SIZE - 1))
+  }
+}
+
+impl Mapper for Mmc1 {
+  fn cpu_read(&self, addr: u16) -> Option<u8> {
+    match addr {
+      0x6000..=0x7FFF if self.is_prg_ram_enabled() => {
+        let offset = (addr - PRG_RAM_ADDR) as usize % self.prg_ram.len();
+        Some(self.prg_ram[offset])
+      }
+      0x8000..=0xFFFF => Some(self.prg_rom[self.get_prg_offset(addr)]),
+      _ => None,
+    }
+  }
+
+  fn cpu_write(&mut self, addr: u16, data: u8) {
+    match addr {
+      0x6000..=0x7FFF if self.is_prg_ram_enabled() => {
+        let offset = (addr - PRG_RAM_ADDR) as usize % self.prg_ram.len();
+        self.prg_ram[offset] = data;
+      }
+      0x8000..=0xFFFF => self.write_register(addr, data),
+      _ => (),
+    }
+  }
+
+  fn ppu_read(&mut self, addr: u16) -> u8 {
+    self.chr[self.get_chr_offset(addr & 0x1FFF)]
+  }
+
+  fn ppu_write(&mut self, addr: u16, data: u8) {
+    if self.chr_is_ram {
+      let offset = self.get_chr_offset(addr & 0x1FFF);
+      self.chr[offset] = data;
+    }
+  }
+
+  fn get_mirroring(&self) -> Mirroring {
+    match self.control & 0x03 {
+      0 => Mirroring::SingleScreenLower,
+      1 => Mirroring::SingleScreenUpper,
+      2 => Mirroring::Vertical,
+      _ => Mirroring::Horizontal,
+    }
+  }
+
+  fn cpu_clock(&mut self) {
+    self.cycle += 1;
+  }
+}
@@ -0,0 +1,202 @@
+// Source: https://wiki.nesdev.com/w/index.php/MMC3
+
+use cartridge::{Cartridge, Mirroring};
+use mapper::{Mapper, MapperError};
+
+const PRG_RAM_ADDR: u16 = 0x6000;
+const PRG_BANK_SIZE: usize = 8 * 1024; // 8 KB
+const CHR_BANK_SIZE: usize = 1024; // 1 KB
+const CHR_RAM_SIZE: usize = 8 * 1024; // 8 KB
+const PRG_RAM_SIZE: usize = 8 * 1024; // 8 KB
+const A12_MASK: u16 = 0x1000;
+// A12 must stay low for this many M2 cycles before a rising edge clocks the counter,
+// which filters out the short toggles between sprite pattern and garbage nametable fetches
+const A12_FILTER_CYCLES: u8 = 3;
+
+pub struct Mmc3 {
+  prg_rom: Vec<u8>,
+  prg_ram: Vec<u8>,
+  chr: Vec<u8>,
+  chr_is_ram: bool,
+  four_screen: bool,
+  bank_select: u8,     // 0x8000 even: CP---RRR
+  registers: [u8; 8],  // 0x8001 odd: R0-R7 bank numbers
+  mirroring: u8,       // 0xA000 even
+  prg_ram_protect: u8, // 0xA001 odd: RW------
+  irq_latch: u8,       // 0xC000 even
+  irq_counter: u8,
+  irq_reload: bool,  // 0xC001 odd
+  irq_enabled: bool, // 0xE000 even disables, 0xE001 odd enables
+  irq_asserted: bool,
+  a12: bool,          // Last level seen on PPU A12
+  a12_low_cycles: u8, // M2 cycles since A12 went low
+}
+
+impl Mmc3 {
+  pub fn new(cartridge: Cartridge) -> Result<Mmc3, MapperError> {
+    let prg_rom = cartridge.get_prg_rom().to_vec();
+    if !prg_rom.len().is_multiple_of(PRG_BANK_SIZE) || prg_rom.len() < 2 * PRG_BANK_SIZE {
+      return Err(MapperError::InvalidPrgRomSize(prg_rom.len()));
+    }
+
+    let chr_is_ram = cartridge.get_chr_rom().is_empty();
+    let chr = match chr_is_ram {
+      true => vec![0; CHR_RAM_SIZE],
+      false => cartridge.get_chr_rom().to_vec(),
+    };
+    if !chr.len().is_multiple_of(CHR_BANK_SIZE) {
+      return Err(MapperError::InvalidChrSize(chr.len()));
+    }
+
+    Ok(Mmc3 {
+      prg_rom,
+      prg_ram: vec![0; PRG_RAM_SIZE],
+      chr,
+      chr_is_ram,
+      four_screen: cartridge.get_mirroring() == Mirroring::FourScreen,
+      bank_select: 0,
+      registers: [0, 2, 4, 5, 6, 7, 0, 1],
+      mirroring: 0,
+      prg_ram_protect: 0x80,
+      irq_latch: 0,
+      irq_counter: 0,
+      irq_reload: false,
+      irq_enabled: false,
+      irq_asserted: false,
+      a12: false,
+      a12_low_cycles: 0,
+    })
+  }
+
+  fn write_register(&mut self, addr: u16, data: u8) {
+    match (addr & 0xE000, addr & 0x0001) {
+      (0x8000, 0) => self.bank_select = data,
+      (0x8000, _) => self.registers[(self.bank_select & 0x07) as usize] = data,
+      (0xA000, 0) => self.mirroring = data & 0x01,
+      (0xA000, _) => self.prg_ram_protect = data,
+      (0xC000, 0) => self.irq_latch = data,
+      (0xC000, _) => {
+        self.irq_counter = 0;
+        self.irq_reload = true;
+      }
+      // Disabling also acknowledges a pending interrupt
+      (0xE000, 0) => {
+        self.irq_enabled = false;
+        self.irq_asserted = false;
+      }
+      (_, _) => self.irq_enabled = true,
+    }
+  }
+
+  // Watches PPU A12 and clocks the scanline counter on filtered rising edges
+  fn observe_ppu_addr(&mut self, addr: u16) {
+    let a12 = addr & A12_MASK > 0;
+    if a12 && !self.a12 && self.a12_low_cycles >= A12_FILTER_CYCLES {
+      self.clock_irq_counter();
+    }
+    if !a12 && self.a12 {
+      self.a12_low_cycles = 0;
+    }
+    self.a12 = a12;
+  }
+
+  fn clock_irq_counter(&mut self) {
+    if self.irq_counter == 0 || self.irq_reload {
+      self.irq_counter = self.irq_latch;
+      self.irq_reload = false;
+    } else {
+      self.irq_counter -= 1;
+    }
+    if self.irq_counter == 0 && self.irq_enabled {
+      self.irq_asserted = true;
+    }
+  }
+
+  fn get_prg_offset(&self, addr: u16) -> usize {
+    let bank_count = self.prg_rom.len() / PRG_BANK_SIZE;
+    let swap_mode = self.bank_select & 0x40 > 0;
+    let bank = match (addr & 0xE000, swap_mode) {
+      (0x8000, false) | (0xC000, true) => self.registers[6] as usize & 0x3F,
+      (0xA000, _) => self.registers[7] as usize & 0x3F,
+      (0xC000, false) | (0x8000, true) => bank_count - 2,
+      (_, _) => bank_count - 1,
+    };
+    (bank % bank_count) * PRG_BANK_SIZE + (addr as usize & (PRG_BANK_SIZE - 1))
+  }
+
+  fn get_chr_offset(&self, addr: u16) -> usize {
+    let bank_count = self.chr.len() / CHR_BANK_SIZE;
+    // Inversion swaps the 2 KB banks at 0x0000 with the 1 KB banks at 0x1000
+    let slot = match self.bank_select & 0x80 > 0 {
+      true => (addr ^ 0x1000) / CHR_BANK_SIZE as u16,
+      false => addr / CHR_BANK_SIZE as u16,
+    };
+    let bank = match slot {
+      0 => self.registers[0] & 0xFE,
+      1 => self.registers[0] | 0x01,
+      2 => self.registers[1] & 0xFE,
+      3 => self.registers[1] | 0x01,
+      _ => self.registers[slot as usize - 2],
+    };
+    (bank as usize % bank_count) * CHR_BANK_SIZE + (addr as usize & (CHR_BANK_SIZE - 1))
+  }
+}
+
+impl Mapper for Mmc3 {
+  fn cpu_read(&self, addr: u16) -> Option<u8> {
+    match addr {
+      0x6000..=0x7FFF if self.prg_ram_protect & 0x80 > 0 => {
+        Some(self.prg_ram[(addr - PRG_RAM_ADDR) as usize])
+      }
+      0x8000..=0xFFFF => Some(self.prg_rom[self.get_prg_offset(addr)]),
+      _ => None,
+    }
+  }
+
+  fn cpu_write(&mut self, addr: u16, data: u8) {
+    match addr {
+      // Writes need the chip enabled and the write protection bit clear
+      0x6000..=0x7FFF if self.prg_ram_protect & 0xC0 == 0x80 => {
+        self.prg_ram[(addr - PRG_RAM_ADDR) as usize] = data;
+      }
+      0x8000..=0xFFFF => self.write_register(addr, data),
+      _ => (),
+    }
+  }
+
+  fn ppu_read(&mut self, addr: u16) -> u8 {
+    self.observe_ppu_addr(addr);
+    match addr {
+      0x0000..=0x1FFF => self.chr[self.get_chr_offset(addr)],
+      _ => 0,
+    }
+  }
+
+  fn ppu_write(&mut self, addr: u16, data: u8) {
+    self.observe_ppu_addr(addr);
+    if let 0x0000..=0x1FFF = addr {
+      if self.chr_is_ram {
+        let offset = self.get_chr_offset(addr);
+        self.chr[offset] = data;
+      }
+    }
+  }
+
+  fn get_mirroring(&self) -> Mirroring {
+    match (self.four_screen, self.mirroring) {
+      (true, _) => Mirroring::FourScreen,
+      (false, 0) => Mirroring::Vertical,
+      (false, _) => Mirroring::Horizontal,
+    }
+  }
+
+  fn is_irq_asserted(&self) -> bool {
+    self.irq_asserted
+  }
+
+  fn cpu_clock(&mut self) {
+    if !self.a12 {
+      self.a12_low_cycles = self.a12_low_cycles.saturating_add(1);
+    }
+  }
+}
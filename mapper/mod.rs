@@ -0,0 +1,102 @@
+// Source: https://wiki.nesdev.com/w/index.php/Mapper
+
+mod axrom;
+mod cnrom;
+mod gxrom;
+mod mmc1;
+mod mmc3;
+mod nrom;
+pub mod tests;
+mod uxrom;
+
+pub use self::axrom::Axrom;
+pub use self::cnrom::Cnrom;
+pub use self::gxrom::Gxrom;
+pub use self::mmc1::Mmc1;
+pub use self::mmc3::Mmc3;
+pub use self::nrom::Nrom;
+pub use self::uxrom::Uxrom;
+
+use cartridge::{Cartridge, Mirroring};
+use device::{DeviceError, ExactSize, Readable, Writable};
+
+use std::error;
+use std::fmt;
+
+pub const CARTRIDGE_ADDR: u16 = 0x4020;
+const CARTRIDGE_SPACE_SIZE: usize = 0x10000 - CARTRIDGE_ADDR as usize;
+
+pub trait Mapper {
+  // CPU side of the cartridge, 0x4020-0xFFFF. Returns None when the board does not drive the bus
+  fn cpu_read(&self, addr: u16) -> Option<u8>;
+  fn cpu_write(&mut self, addr: u16, data: u8);
+  // PPU side of the cartridge, 0x0000-0x1FFF pattern tables
+  fn ppu_read(&mut self, addr: u16) -> u8;
+  fn ppu_write(&mut self, addr: u16, data: u8);
+  // Nametable arrangement the board currently selects
+  fn get_mirroring(&self) -> Mirroring;
+  // State of the cartridge's IRQ output, active while true
+  fn is_irq_asserted(&self) -> bool {
+    false
+  }
+  // Called once per CPU cycle (M2) for boards that count time
+  fn cpu_clock(&mut self) {}
+}
+
+#[derive(Debug, PartialEq)]
+pub enum MapperError {
+  Unsupported(u16),         // No implementation for this mapper number
+  InvalidPrgRomSize(usize), // PRG ROM size the board cannot address
+  InvalidChrSize(usize),    // CHR ROM/RAM size the board cannot address
+}
+
+impl fmt::Display for MapperError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      MapperError::Unsupported(mapper) => write!(f, "Mapper {} is not supported", mapper),
+      MapperError::InvalidPrgRomSize(size) => {
+        write!(f, "Unsupported PRG ROM size: {:#X} bytes", size)
+      }
+      MapperError::InvalidChrSize(size) => write!(f, "Unsupported CHR size: {:#X} bytes", size),
+    }
+  }
+}
+
+impl error::Error for MapperError {}
+
+pub fn from_cartridge(cartridge: Cartridge) -> Result<Box<dyn Mapper>, MapperError> {
+  match cartridge.get_mapper() {
+    0 => Ok(Box::new(Nrom::new(cartridge)?)),
+    1 => Ok(Box::new(Mmc1::new(cartridge)?)),
+    2 => Ok(Box::new(Uxrom::new(cartridge)?)),
+    3 => Ok(Box::new(Cnrom::new(cartridge)?)),
+    4 => Ok(Box::new(Mmc3::new(cartridge)?)),
+    7 => Ok(Box::new(Axrom::new(cartridge)?)),
+    66 => Ok(Box::new(Gxrom::new(cartridge)?)),
+    mapper => Err(MapperError::Unsupported(mapper)),
+  }
+}
+
+// Lets a mapper sit on the bus as a device covering the whole cartridge space. Connections
+// address it relative to CARTRIDGE_ADDR, mappers decode the absolute CPU address
+impl ExactSize for Box<dyn Mapper> {
+  fn mem_size(&self) -> usize {
+    CARTRIDGE_SPACE_SIZE
+  }
+}
+
+impl Readable for Box<dyn Mapper> {
+  fn read_addr(&self, addr: usize) -> Result<u8, DeviceError> {
+    let cpu_addr = (addr + CARTRIDGE_ADDR as usize) as u16;
+    self
+      .cpu_read(cpu_addr)
+      .ok_or(DeviceError::Unmapped(cpu_addr))
+  }
+}
+
+impl Writable for Box<dyn Mapper> {
+  fn write_addr(&mut self, addr: usize, data: u8) -> Result<(), DeviceError> {
+    self.cpu_write((addr + CARTRIDGE_ADDR as usize) as u16, data);
+    Ok(())
+  }
+}
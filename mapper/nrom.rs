@@ -0,0 +1,77 @@
+// Source: https://wiki.nesdev.com/w/index.php/NROM
+
+use cartridge::{Cartridge, Mirroring};
+use device::{Device, ExactSize, Readable, Writable};
+use mapper::{Mapper, MapperError};
+
+const PRG_RAM_ADDR: u16 = 0x6000;
+const PRG_ROM_ADDR: u16 = 0x8000;
+const NROM_128_SIZE: usize = 16 * 1024; // 16 KB
+const NROM_256_SIZE: usize = 32 * 1024; // 32 KB
+const CHR_SIZE: usize = 8 * 1024; // 8 KB
+const PRG_RAM_SIZE: usize = 8 * 1024; // 8 KB, only fitted to Family Basic
+
+pub struct Nrom {
+  prg_rom: Device,
+  prg_ram: Device,
+  chr: Device,
+  mirroring: Mirroring,
+}
+
+impl Nrom {
+  pub fn new(cartridge: Cartridge) -> Result<Nrom, MapperError> {
+    let prg_rom = cartridge.get_prg_rom();
+    match prg_rom.len() {
+      NROM_128_SIZE | NROM_256_SIZE => (),
+      size => return Err(MapperError::InvalidPrgRomSize(size)),
+    };
+
+    let chr = match cartridge.get_chr_rom().len() {
+      0 => Device::new(vec![0; CHR_SIZE].into_boxed_slice()),
+      CHR_SIZE => Device::read_only(cartridge.get_chr_rom().to_vec().into_boxed_slice()),
+      size => return Err(MapperError::InvalidChrSize(size)),
+    };
+
+    Ok(Nrom {
+      prg_rom: Device::read_only(prg_rom.to_vec().into_boxed_slice()),
+      prg_ram: Device::new(vec![0; PRG_RAM_SIZE].into_boxed_slice()),
+      chr,
+      mirroring: cartridge.get_mirroring(),
+    })
+  }
+}
+
+impl Mapper for Nrom {
+  fn cpu_read(&self, addr: u16) -> Option<u8> {
+    match addr {
+      0x6000..=0x7FFF => self.prg_ram.read_addr((addr - PRG_RAM_ADDR) as usize).ok(),
+      // NROM-128 mirrors its single 16 KB bank into 0xC000-0xFFFF
+      0x8000..=0xFFFF => {
+        let offset = (addr - PRG_ROM_ADDR) as usize % self.prg_rom.mem_size();
+        self.prg_rom.read_addr(offset).ok()
+      }
+      _ => None,
+    }
+  }
+
+  fn cpu_write(&mut self, addr: u16, data: u8) {
+    if let 0x6000..=0x7FFF = addr {
+      let _ = self
+        .prg_ram
+        .write_addr((addr - PRG_RAM_ADDR) as usize, data);
+    }
+  }
+
+  fn ppu_read(&mut self, addr: u16) -> u8 {
+    self.chr.read_addr((addr & 0x1FFF) as usize).unwrap_or(0)
+  }
+
+  fn ppu_write(&mut self, addr: u16, data: u8) {
+    // CHR ROM ignores writes
+    let _ = self.chr.write_addr((addr & 0x1FFF) as usize, data);
+  }
+
+  fn get_mirroring(&self) -> Mirroring {
+    self.mirroring
+  }
+}
@@ -0,0 +1,73 @@
+#[cfg(test)]
+use bus::Bus;
+
+#[cfg(test)]
+use cartridge::Mirroring;
+
+#[cfg(test)]
+use data_flow::ReadWrite;
+
+#[cfg(test)]
+use mapper::from_cartridge;
+
+#[cfg(test)]
+use mapper::tests::build_nes2_cartridge;
+
+#[cfg(test)]
+fn setup(submapper: u8) -> Bus {
+  let mut bus = Bus::new();
+  bus.insert_cartridge(from_cartridge(build_nes2_cartridge(7, submapper, 8, 0)).unwrap());
+  bus
+}
+
+#[test]
+fn switches_32kb_prg_bank() {
+  let mut bus = setup(1);
+  bus.write_addr(0x8000, 0x02);
+  assert_eq!(bus.read_addr(0x8000), 4);
+  assert_eq!(bus.read_addr(0xC000), 5);
+}
+
+#[test]
+fn bit_4_selects_single_screen_page() {
+  let mut bus = setup(1);
+  assert_eq!(
+    bus.get_mapper().unwrap().get_mirroring(),
+    Mirroring::SingleScreenLower
+  );
+  bus.write_addr(0x8000, 0x10);
+  assert_eq!(
+    bus.get_mapper().unwrap().get_mirroring(),
+    Mirroring::SingleScreenUpper
+  );
+  bus.write_addr(0x8000, 0x00);
+  assert_eq!(
+    bus.get_mapper().unwrap().get_mirroring(),
+    Mirroring::SingleScreenLower
+  );
+}
+
+#[test]
+fn unspecified_submapper_has_no_bus_conflicts() {
+  let mut bus = setup(0);
+  bus.write_addr(0x8000, 0x13);
+  assert_eq!(bus.read_addr(0x8000), 6);
+  assert_eq!(
+    bus.get_mapper().unwrap().get_mirroring(),
+    Mirroring::SingleScreenUpper
+  );
+}
+
+#[test]
+fn amrom_bus_conflicts_and_with_rom() {
+  let mut bus = setup(2);
+  // Bank 0 starts with 16 KB of 0x00 followed by 16 KB of 0x01
+  bus.write_addr(0x8000, 0x13);
+  assert_eq!(bus.read_addr(0x8000), 0);
+  bus.write_addr(0xC000, 0x13);
+  assert_eq!(bus.read_addr(0x8000), 2);
+  assert_eq!(
+    bus.get_mapper().unwrap().get_mirroring(),
+    Mirroring::SingleScreenLower
+  );
+}
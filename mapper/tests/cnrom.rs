@@ -0,0 +1,51 @@
+#[cfg(test)]
+use bus::Bus;
+
+#[cfg(test)]
+use data_flow::ReadWrite;
+
+#[cfg(test)]
+use mapper::from_cartridge;
+
+#[cfg(test)]
+use mapper::tests::build_nes2_cartridge;
+
+#[cfg(test)]
+fn setup(submapper: u8) -> Bus {
+  let mut bus = Bus::new();
+  bus.insert_cartridge(from_cartridge(build_nes2_cartridge(3, submapper, 1, 4)).unwrap());
+  bus
+}
+
+#[test]
+fn mirrors_16kb_prg_rom() {
+  let bus = setup(1);
+  assert_eq!(bus.read_addr(0x8000), 0);
+  assert_eq!(bus.read_addr(0xC000), 0);
+}
+
+#[test]
+fn switches_8kb_chr_bank() {
+  let mut bus = setup(1);
+  bus.write_addr(0x8000, 2);
+  let mapper = bus.get_mut_mapper().unwrap();
+  assert_eq!(mapper.ppu_read(0x0000), 0x82);
+  assert_eq!(mapper.ppu_read(0x1FFF), 0x82);
+}
+
+#[test]
+fn chr_rom_is_read_only() {
+  let mut bus = setup(1);
+  let mapper = bus.get_mut_mapper().unwrap();
+  mapper.ppu_write(0x0000, 0x12);
+  assert_eq!(mapper.ppu_read(0x0000), 0x80);
+}
+
+#[test]
+fn bus_conflicts_and_with_rom() {
+  let mut bus = setup(2);
+  // The PRG ROM is filled with 0x00, so the ROM pulls every bit low
+  bus.write_addr(0x8000, 2);
+  let mapper = bus.get_mut_mapper().unwrap();
+  assert_eq!(mapper.ppu_read(0x0000), 0x80);
+}
@@ -0,0 +1,58 @@
+#[cfg(test)]
+use bus::Bus;
+
+#[cfg(test)]
+use cartridge::Cartridge;
+
+#[cfg(test)]
+use data_flow::ReadWrite;
+
+#[cfg(test)]
+use mapper::from_cartridge;
+
+// GxROM always has bus conflicts, so every 32 KB PRG bank holds 0b0011_NN11:
+// the register bits stay writable and bits 2-3 identify the bank
+#[cfg(test)]
+fn setup() -> Bus {
+  let mut rom = vec![
+    0x4E, 0x45, 0x53, 0x1A, 8, 4, 0x21, 0x40, 0, 0, 0, 0, 0, 0, 0, 0,
+  ];
+  for bank in 0..4 {
+    rom.extend(vec![0x33 | (bank << 2); 32 * 1024]);
+  }
+  for bank in 0..4 {
+    rom.extend(vec![0x80 | bank; 8 * 1024]);
+  }
+  let mut bus = Bus::new();
+  bus.insert_cartridge(from_cartridge(Cartridge::from_bytes(&rom).unwrap()).unwrap());
+  bus
+}
+
+#[test]
+fn switches_32kb_prg_bank() {
+  let mut bus = setup();
+  assert_eq!(bus.read_addr(0x8000), 0x33);
+  bus.write_addr(0x8000, 0x20);
+  assert_eq!(bus.read_addr(0x8000), 0x3B);
+  assert_eq!(bus.read_addr(0xFFFF), 0x3B);
+}
+
+#[test]
+fn switches_8kb_chr_bank() {
+  let mut bus = setup();
+  bus.write_addr(0x8000, 0x03);
+  let mapper = bus.get_mut_mapper().unwrap();
+  assert_eq!(mapper.ppu_read(0x0000), 0x83);
+  assert_eq!(mapper.ppu_read(0x1FFF), 0x83);
+}
+
+#[test]
+fn bus_conflicts_and_with_rom() {
+  let mut bus = setup();
+  bus.write_addr(0x8000, 0xCC);
+  assert_eq!(bus.read_addr(0x8000), 0x33);
+  assert_eq!(bus.get_mut_mapper().unwrap().ppu_read(0x0000), 0x80);
+  bus.write_addr(0x8000, 0xFF);
+  assert_eq!(bus.read_addr(0x8000), 0x3F);
+  assert_eq!(bus.get_mut_mapper().unwrap().ppu_read(0x0000), 0x83);
+}
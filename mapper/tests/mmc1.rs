@@ -0,0 +1,215 @@
+#[cfg(test)]
+use bus::Bus;
+
+#[cfg(test)]
+use cartridge::Mirroring;
+
+#[cfg(test)]
+use data_flow::ReadWrite;
+
+#[cfg(test)]
+use mapper::from_cartridge;
+
+#[cfg(test)]
+use mapper::tests::build_cartridge;
+
+#[cfg(test)]
+const CONTROL: u16 = 0x8000;
+
+#[cfg(test)]
+const CHR_BANK_0: u16 = 0xA000;
+
+#[cfg(test)]
+const CHR_BANK_1: u16 = 0xC000;
+
+#[cfg(test)]
+const PRG_BANK: u16 = 0xE000;
+
+#[cfg(test)]
+fn setup(prg_banks: u8, chr_banks: u8) -> Bus {
+  let mut bus = Bus::new();
+  bus.insert_cartridge(from_cartridge(build_cartridge(1, prg_banks, chr_banks)).unwrap());
+  bus
+}
+
+// Loads a 5-bit value one bit at a time, least significant bit first
+#[cfg(test)]
+fn write_serial(bus: &mut Bus, addr: u16, value: u8) {
+  for bit in 0..5 {
+    bus.write_addr(addr, (value >> bit) & 0x01);
+  }
+}
+
+#[cfg(test)]
+mod shift_register {
+  use super::*;
+  #[test]
+  fn register_updates_only_on_fifth_write() {
+    let mut bus = setup(8, 1);
+    for bit in 0..4 {
+      bus.write_addr(PRG_BANK, (0x03 >> bit) & 0x01);
+      assert_eq!(bus.read_addr(0x8000), 0);
+    }
+    bus.write_addr(PRG_BANK, 0);
+    assert_eq!(bus.read_addr(0x8000), 3);
+  }
+  #[test]
+  fn bit_7_clears_partial_load() {
+    let mut bus = setup(8, 1);
+    bus.write_addr(PRG_BANK, 1);
+    bus.write_addr(PRG_BANK, 1);
+    bus.write_addr(PRG_BANK, 0x80);
+    write_serial(&mut bus, PRG_BANK, 0x02);
+    assert_eq!(bus.read_addr(0x8000), 2);
+  }
+  #[test]
+  fn bit_7_restores_prg_mode_3() {
+    let mut bus = setup(8, 1);
+    write_serial(&mut bus, CONTROL, 0b0_10_00);
+    write_serial(&mut bus, PRG_BANK, 0x02);
+    assert_eq!(bus.read_addr(0x8000), 0);
+    bus.write_addr(CONTROL, 0x80);
+    assert_eq!(bus.read_addr(0x8000), 2);
+    assert_eq!(bus.read_addr(0xC000), 7);
+  }
+  #[test]
+  fn ignores_write_on_the_next_cycle() {
+    let mut bus = setup(8, 1);
+    // The second write of a read-modify-write is dropped
+    bus.write_addr(PRG_BANK, 1);
+    bus.clock();
+    bus.write_addr(PRG_BANK, 1);
+    bus.clock();
+    bus.clock();
+    for _ in 0..4 {
+      bus.write_addr(PRG_BANK, 0);
+      bus.clock();
+      bus.clock();
+    }
+    assert_eq!(bus.read_addr(0x8000), 1);
+  }
+  #[test]
+  fn any_address_in_register_range_selects_it() {
+    let mut bus = setup(8, 1);
+    write_serial(&mut bus, 0xFFFF, 0x05);
+    assert_eq!(bus.read_addr(0x8000), 5);
+  }
+}
+
+#[cfg(test)]
+mod prg_banks {
+  use super::*;
+  #[test]
+  fn power_on_fixes_last_bank_at_0xc000() {
+    let bus = setup(8, 1);
+    assert_eq!(bus.read_addr(0x8000), 0);
+    assert_eq!(bus.read_addr(0xFFFF), 7);
+  }
+  #[test]
+  fn mode_0_switches_32kb_ignoring_low_bit() {
+    let mut bus = setup(8, 1);
+    write_serial(&mut bus, CONTROL, 0b0_00_00);
+    write_serial(&mut bus, PRG_BANK, 0x05);
+    assert_eq!(bus.read_addr(0x8000), 4);
+    assert_eq!(bus.read_addr(0xC000), 5);
+  }
+  #[test]
+  fn mode_1_switches_32kb_ignoring_low_bit() {
+    let mut bus = setup(8, 1);
+    write_serial(&mut bus, CONTROL, 0b0_01_00);
+    write_serial(&mut bus, PRG_BANK, 0x03);
+    assert_eq!(bus.read_addr(0x8000), 2);
+    assert_eq!(bus.read_addr(0xC000), 3);
+  }
+  #[test]
+  fn mode_2_fixes_first_bank_at_0x8000() {
+    let mut bus = setup(8, 1);
+    write_serial(&mut bus, CONTROL, 0b0_10_00);
+    write_serial(&mut bus, PRG_BANK, 0x05);
+    assert_eq!(bus.read_addr(0x8000), 0);
+    assert_eq!(bus.read_addr(0xC000), 5);
+  }
+  #[test]
+  fn mode_3_fixes_last_bank_at_0xc000() {
+    let mut bus = setup(8, 1);
+    write_serial(&mut bus, CONTROL, 0b0_11_00);
+    write_serial(&mut bus, PRG_BANK, 0x05);
+    assert_eq!(bus.read_addr(0x8000), 5);
+    assert_eq!(bus.read_addr(0xC000), 7);
+  }
+  #[test]
+  fn chr_bank_bit_4_selects_outer_256kb_bank() {
+    let mut bus = setup(32, 0);
+    write_serial(&mut bus, CHR_BANK_0, 0x10);
+    write_serial(&mut bus, PRG_BANK, 0x01);
+    assert_eq!(bus.read_addr(0x8000), 17);
+    assert_eq!(bus.read_addr(0xC000), 31);
+  }
+}
+
+#[cfg(test)]
+mod prg_ram {
+  use super::*;
+  #[test]
+  fn enabled_at_power_on() {
+    let mut bus = setup(8, 1);
+    bus.write_addr(0x6000, 0xAB);
+    assert_eq!(bus.read_addr(0x6000), 0xAB);
+  }
+  #[test]
+  fn disabled_by_prg_bank_bit_4() {
+    let mut bus = setup(8, 1);
+    bus.write_addr(0x6000, 0xAB);
+    write_serial(&mut bus, PRG_BANK, 0x10);
+    bus.write_addr(0x6000, 0xCD);
+    assert_eq!(bus.read_addr(0x6000), 0x00);
+    write_serial(&mut bus, PRG_BANK, 0x00);
+    assert_eq!(bus.read_addr(0x6000), 0xAB);
+  }
+}
+
+#[cfg(test)]
+mod chr_banks {
+  use super::*;
+  #[test]
+  fn mode_8kb_ignores_low_bit() {
+    let mut bus = setup(2, 4);
+    write_serial(&mut bus, CONTROL, 0b0_11_00);
+    write_serial(&mut bus, CHR_BANK_0, 0x05);
+    let mapper = bus.get_mut_mapper().unwrap();
+    assert_eq!(mapper.ppu_read(0x0000), 0x82);
+    assert_eq!(mapper.ppu_read(0x1000), 0x82);
+  }
+  #[test]
+  fn mode_4kb_switches_each_half() {
+    let mut bus = setup(2, 4);
+    write_serial(&mut bus, CONTROL, 0b1_11_00);
+    write_serial(&mut bus, CHR_BANK_0, 0x06);
+    write_serial(&mut bus, CHR_BANK_1, 0x01);
+    let mapper = bus.get_mut_mapper().unwrap();
+    assert_eq!(mapper.ppu_read(0x0000), 0x83);
+    assert_eq!(mapper.ppu_read(0x1000), 0x80);
+  }
+  #[test]
+  fn chr_ram_is_writable() {
+    let mut bus = setup(2, 0);
+    let mapper = bus.get_mut_mapper().unwrap();
+    mapper.ppu_write(0x1234, 0xAB);
+    assert_eq!(mapper.ppu_read(0x1234), 0xAB);
+  }
+}
+
+#[test]
+fn control_selects_mirroring() {
+  let mut bus = setup(2, 1);
+  let modes = [
+    Mirroring::SingleScreenLower,
+    Mirroring::SingleScreenUpper,
+    Mirroring::Vertical,
+    Mirroring::Horizontal,
+  ];
+  for (value, mirroring) in modes.iter().enumerate() {
+    write_serial(&mut bus, CONTROL, 0b0_11_00 | value as u8);
+    assert_eq!(bus.get_mapper().unwrap().get_mirroring(), *mirroring);
+  }
+}
@@ -0,0 +1,233 @@
+#[cfg(test)]
+use bus::Bus;
+
+#[cfg(test)]
+use cartridge::{Cartridge, Mirroring};
+
+#[cfg(test)]
+use data_flow::ReadWrite;
+
+#[cfg(test)]
+use mapper::from_cartridge;
+
+// 16 PRG banks of 8 KB and 64 CHR banks of 1 KB, each filled with its bank number
+#[cfg(test)]
+fn setup(flags_6: u8) -> Bus {
+  let mut rom = vec![
+    0x4E,
+    0x45,
+    0x53,
+    0x1A,
+    8,
+    8,
+    0x40 | flags_6,
+    0,
+    0,
+    0,
+    0,
+    0,
+    0,
+    0,
+    0,
+    0,
+  ];
+  for bank in 0..16 {
+    rom.extend(vec![bank; 8 * 1024]);
+  }
+  for bank in 0..64 {
+    rom.extend(vec![0x80 | bank; 1024]);
+  }
+  let mut bus = Bus::new();
+  bus.insert_cartridge(from_cartridge(Cartridge::from_bytes(&rom).unwrap()).unwrap());
+  bus
+}
+
+#[cfg(test)]
+fn set_bank(bus: &mut Bus, bank_select: u8, bank: u8) {
+  bus.write_addr(0x8000, bank_select);
+  bus.write_addr(0x8001, bank);
+}
+
+// Holds A12 low long enough to pass the M2 filter, then raises it
+#[cfg(test)]
+fn a12_rising_edge(bus: &mut Bus) {
+  let mapper = bus.get_mut_mapper().unwrap();
+  mapper.ppu_read(0x0000);
+  for _ in 0..3 {
+    mapper.cpu_clock();
+  }
+  mapper.ppu_read(0x1000);
+}
+
+#[cfg(test)]
+mod prg_banks {
+  use super::*;
+  #[test]
+  fn mode_0_fixes_second_last_bank_at_0xc000() {
+    let mut bus = setup(0);
+    set_bank(&mut bus, 0x06, 3);
+    set_bank(&mut bus, 0x07, 5);
+    assert_eq!(bus.read_addr(0x8000), 3);
+    assert_eq!(bus.read_addr(0xA000), 5);
+    assert_eq!(bus.read_addr(0xC000), 14);
+    assert_eq!(bus.read_addr(0xE000), 15);
+  }
+  #[test]
+  fn mode_1_fixes_second_last_bank_at_0x8000() {
+    let mut bus = setup(0);
+    set_bank(&mut bus, 0x46, 3);
+    set_bank(&mut bus, 0x47, 5);
+    assert_eq!(bus.read_addr(0x8000), 14);
+    assert_eq!(bus.read_addr(0xA000), 5);
+    assert_eq!(bus.read_addr(0xC000), 3);
+    assert_eq!(bus.read_addr(0xE000), 15);
+  }
+}
+
+#[cfg(test)]
+mod chr_banks {
+  use super::*;
+  #[test]
+  fn maps_2kb_banks_low_and_1kb_banks_high() {
+    let mut bus = setup(0);
+    set_bank(&mut bus, 0x00, 9);
+    set_bank(&mut bus, 0x01, 12);
+    set_bank(&mut bus, 0x02, 20);
+    set_bank(&mut bus, 0x05, 23);
+    let mapper = bus.get_mut_mapper().unwrap();
+    assert_eq!(mapper.ppu_read(0x0000), 0x88);
+    assert_eq!(mapper.ppu_read(0x0400), 0x89);
+    assert_eq!(mapper.ppu_read(0x0800), 0x8C);
+    assert_eq!(mapper.ppu_read(0x0C00), 0x8D);
+    assert_eq!(mapper.ppu_read(0x1000), 0x94);
+    assert_eq!(mapper.ppu_read(0x1C00), 0x97);
+  }
+  #[test]
+  fn a12_inversion_swaps_halves() {
+    let mut bus = setup(0);
+    set_bank(&mut bus, 0x80, 8);
+    set_bank(&mut bus, 0x82, 20);
+    set_bank(&mut bus, 0x85, 23);
+    let mapper = bus.get_mut_mapper().unwrap();
+    assert_eq!(mapper.ppu_read(0x0000), 0x94);
+    assert_eq!(mapper.ppu_read(0x0C00), 0x97);
+    assert_eq!(mapper.ppu_read(0x1000), 0x88);
+    assert_eq!(mapper.ppu_read(0x1400), 0x89);
+  }
+}
+
+#[cfg(test)]
+mod prg_ram {
+  use super::*;
+  #[test]
+  fn readable_and_writable_when_enabled() {
+    let mut bus = setup(0);
+    bus.write_addr(0xA001, 0x80);
+    bus.write_addr(0x6000, 0xAB);
+    assert_eq!(bus.read_addr(0x6000), 0xAB);
+  }
+  #[test]
+  fn write_protect_ignores_writes() {
+    let mut bus = setup(0);
+    bus.write_addr(0xA001, 0x80);
+    bus.write_addr(0x6000, 0xAB);
+    bus.write_addr(0xA001, 0xC0);
+    bus.write_addr(0x6000, 0xCD);
+    assert_eq!(bus.read_addr(0x6000), 0xAB);
+  }
+  #[test]
+  fn disabled_chip_is_not_driven() {
+    let mut bus = setup(0);
+    bus.write_addr(0x6000, 0xAB);
+    bus.write_addr(0xA001, 0x00);
+    assert_eq!(bus.get_mapper().unwrap().cpu_read(0x6000), None);
+  }
+}
+
+#[cfg(test)]
+mod mirroring {
+  use super::*;
+  #[test]
+  fn register_selects_vertical_or_horizontal() {
+    let mut bus = setup(0);
+    bus.write_addr(0xA000, 0x00);
+    assert_eq!(
+      bus.get_mapper().unwrap().get_mirroring(),
+      Mirroring::Vertical
+    );
+    bus.write_addr(0xA000, 0x01);
+    assert_eq!(
+      bus.get_mapper().unwrap().get_mirroring(),
+      Mirroring::Horizontal
+    );
+  }
+  #[test]
+  fn four_screen_ignores_register() {
+    let mut bus = setup(0x08);
+    bus.write_addr(0xA000, 0x01);
+    assert_eq!(
+      bus.get_mapper().unwrap().get_mirroring(),
+      Mirroring::FourScreen
+    );
+  }
+}
+
+#[cfg(test)]
+mod irq {
+  use super::*;
+  #[test]
+  fn asserts_when_counter_reaches_zero() {
+    let mut bus = setup(0);
+    bus.write_addr(0xC000, 2);
+    bus.write_addr(0xC001, 0);
+    bus.write_addr(0xE001, 0);
+    // Reload to 2, then 1, then 0
+    a12_rising_edge(&mut bus);
+    a12_rising_edge(&mut bus);
+    assert!(!bus.is_irq_asserted());
+    a12_rising_edge(&mut bus);
+    assert!(bus.is_irq_asserted());
+  }
+  #[test]
+  fn does_not_assert_while_disabled() {
+    let mut bus = setup(0);
+    bus.write_addr(0xC000, 0);
+    bus.write_addr(0xC001, 0);
+    a12_rising_edge(&mut bus);
+    assert!(!bus.is_irq_asserted());
+  }
+  #[test]
+  fn disable_acknowledges_pending_irq() {
+    let mut bus = setup(0);
+    bus.write_addr(0xC000, 0);
+    bus.write_addr(0xE001, 0);
+    a12_rising_edge(&mut bus);
+    assert!(bus.is_irq_asserted());
+    bus.write_addr(0xE000, 0);
+    assert!(!bus.is_irq_asserted());
+  }
+  #[test]
+  fn filters_short_a12_low_pulses() {
+    let mut bus = setup(0);
+    bus.write_addr(0xC000, 0);
+    bus.write_addr(0xE001, 0);
+    let mapper = bus.get_mut_mapper().unwrap();
+    mapper.ppu_read(0x1000);
+    mapper.ppu_read(0x0000);
+    mapper.cpu_clock();
+    mapper.ppu_read(0x1000);
+    assert!(!mapper.is_irq_asserted());
+  }
+  #[test]
+  fn bus_clock_drives_filter() {
+    let mut bus = setup(0);
+    bus.write_addr(0xC000, 0);
+    bus.write_addr(0xE001, 0);
+    bus.get_mut_mapper().unwrap().ppu_read(0x0000);
+    for _ in 0..3 {
+      bus.clock();
+    }
+    bus.get_mut_mapper().unwrap().ppu_read(0x1000);
+    assert!(bus.is_irq_asserted());
+  }
+}
@@ -0,0 +1,73 @@
+mod axrom;
+mod cnrom;
+mod gxrom;
+mod mmc1;
+mod mmc3;
+mod nrom;
+mod uxrom;
+
+#[cfg(test)]
+use bus::Bus;
+
+#[cfg(test)]
+use cartridge::Cartridge;
+
+#[cfg(test)]
+use data_flow::ReadWrite;
+
+#[cfg(test)]
+use mapper::{from_cartridge, MapperError};
+
+// Builds an iNES image whose PRG banks are filled with their bank number
+#[cfg(test)]
+pub fn build_cartridge(mapper: u8, prg_banks: u8, chr_banks: u8) -> Cartridge {
+  build_nes2_cartridge(mapper, 0, prg_banks, chr_banks)
+}
+
+// Same as build_cartridge, with a NES 2.0 header when a submapper is given
+#[cfg(test)]
+pub fn build_nes2_cartridge(mapper: u8, submapper: u8, prg_banks: u8, chr_banks: u8) -> Cartridge {
+  let mut rom = vec![
+    0x4E,
+    0x45,
+    0x53,
+    0x1A,
+    prg_banks,
+    chr_banks,
+    (mapper << 4) | 0x01,
+    mapper & 0xF0,
+  ];
+  if submapper > 0 {
+    rom[7] |= 0x08;
+    rom.push(submapper << 4);
+    rom.extend(vec![0; 7]);
+  } else {
+    rom.extend(vec![0; 8]);
+  }
+  for bank in 0..prg_banks {
+    rom.extend(vec![bank; 16 * 1024]);
+  }
+  for bank in 0..chr_banks {
+    rom.extend(vec![0x80 | bank; 8 * 1024]);
+  }
+  Cartridge::from_bytes(&rom).unwrap()
+}
+
+#[test]
+fn rejects_unsupported_mapper() {
+  let result = from_cartridge(build_cartridge(0xFF, 1, 1));
+  assert_eq!(result.err(), Some(MapperError::Unsupported(0xFF)));
+}
+
+#[test]
+fn bus_reads_nestest_through_mapper() {
+  let cartridge = Cartridge::from_file("roms/nestest.nes").unwrap();
+  let mut bus = Bus::new();
+  bus.insert_cartridge(from_cartridge(cartridge).unwrap());
+  // JMP $C5F5 at the automation entry point, mirrored from 0x8000
+  assert_eq!(bus.read_addr(0xC000), 0x4C);
+  assert_eq!(bus.read_addr(0x8000), 0x4C);
+  assert_eq!(bus.read_addr(0xC001), 0xF5);
+  bus.write_addr(0xC000, 0x00);
+  assert_eq!(bus.read_addr(0xC000), 0x4C);
+}
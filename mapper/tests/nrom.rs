@@ -0,0 +1,66 @@
+#[cfg(test)]
+use cartridge::Mirroring;
+
+#[cfg(test)]
+use mapper::tests::build_cartridge;
+
+#[cfg(test)]
+use mapper::{from_cartridge, MapperError};
+
+#[test]
+fn nrom_128_mirrors_prg_rom() {
+  let mapper = from_cartridge(build_cartridge(0, 1, 1)).unwrap();
+  assert_eq!(mapper.cpu_read(0x8000), Some(0));
+  assert_eq!(mapper.cpu_read(0xC000), Some(0));
+  assert_eq!(mapper.cpu_read(0xFFFF), Some(0));
+}
+#[test]
+fn nrom_256_maps_both_banks() {
+  let mapper = from_cartridge(build_cartridge(0, 2, 1)).unwrap();
+  assert_eq!(mapper.cpu_read(0xBFFF), Some(0));
+  assert_eq!(mapper.cpu_read(0xC000), Some(1));
+}
+#[test]
+fn prg_rom_is_read_only() {
+  let mut mapper = from_cartridge(build_cartridge(0, 2, 1)).unwrap();
+  mapper.cpu_write(0xC000, 0xAB);
+  assert_eq!(mapper.cpu_read(0xC000), Some(1));
+}
+#[test]
+fn prg_ram_is_writable() {
+  let mut mapper = from_cartridge(build_cartridge(0, 1, 1)).unwrap();
+  mapper.cpu_write(0x6004, 0xAB);
+  assert_eq!(mapper.cpu_read(0x6004), Some(0xAB));
+}
+#[test]
+fn does_not_drive_expansion_area() {
+  let mapper = from_cartridge(build_cartridge(0, 1, 1)).unwrap();
+  assert_eq!(mapper.cpu_read(0x4020), None);
+  assert_eq!(mapper.cpu_read(0x5FFF), None);
+}
+#[test]
+fn chr_rom_is_read_only() {
+  let mut mapper = from_cartridge(build_cartridge(0, 1, 1)).unwrap();
+  mapper.ppu_write(0x0010, 0xAB);
+  assert_eq!(mapper.ppu_read(0x0010), 0x80);
+}
+#[test]
+fn chr_ram_is_writable() {
+  let mut mapper = from_cartridge(build_cartridge(0, 1, 0)).unwrap();
+  mapper.ppu_write(0x1FFF, 0xAB);
+  assert_eq!(mapper.ppu_read(0x1FFF), 0xAB);
+}
+#[test]
+fn reports_header_mirroring() {
+  let mapper = from_cartridge(build_cartridge(0, 1, 1)).unwrap();
+  assert_eq!(mapper.get_mirroring(), Mirroring::Vertical);
+  assert!(!mapper.is_irq_asserted());
+}
+#[test]
+fn rejects_unaddressable_prg_rom() {
+  let result = from_cartridge(build_cartridge(0, 3, 1));
+  assert_eq!(
+    result.err(),
+    Some(MapperError::InvalidPrgRomSize(48 * 1024))
+  );
+}
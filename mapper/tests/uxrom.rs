@@ -0,0 +1,61 @@
+#[cfg(test)]
+use bus::Bus;
+
+#[cfg(test)]
+use data_flow::ReadWrite;
+
+#[cfg(test)]
+use mapper::from_cartridge;
+
+#[cfg(test)]
+use mapper::tests::{build_cartridge, build_nes2_cartridge};
+
+#[cfg(test)]
+fn setup(submapper: u8) -> Bus {
+  let mut bus = Bus::new();
+  bus.insert_cartridge(from_cartridge(build_nes2_cartridge(2, submapper, 8, 0)).unwrap());
+  bus
+}
+
+#[test]
+fn fixes_last_bank_at_0xc000() {
+  let mut bus = setup(1);
+  assert_eq!(bus.read_addr(0xC000), 7);
+  bus.write_addr(0x8000, 3);
+  assert_eq!(bus.read_addr(0xFFFF), 7);
+}
+
+#[test]
+fn switches_bank_at_0x8000() {
+  let mut bus = setup(1);
+  bus.write_addr(0x8000, 3);
+  assert_eq!(bus.read_addr(0x8000), 3);
+  assert_eq!(bus.read_addr(0xBFFF), 3);
+}
+
+#[test]
+fn bus_conflicts_and_with_rom() {
+  let mut bus = setup(2);
+  // Bank 0 is filled with 0x00, so the ROM pulls every bit low
+  bus.write_addr(0x8000, 3);
+  assert_eq!(bus.read_addr(0x8000), 0);
+  // The fixed bank is filled with 0x07
+  bus.write_addr(0xC000, 0x0D);
+  assert_eq!(bus.read_addr(0x8000), 5);
+}
+
+#[test]
+fn ines_cartridge_has_bus_conflicts() {
+  let mut bus = Bus::new();
+  bus.insert_cartridge(from_cartridge(build_cartridge(2, 8, 0)).unwrap());
+  bus.write_addr(0x8000, 3);
+  assert_eq!(bus.read_addr(0x8000), 0);
+}
+
+#[test]
+fn chr_ram_is_writable() {
+  let mut bus = setup(0);
+  let mapper = bus.get_mut_mapper().unwrap();
+  mapper.ppu_write(0x0ABC, 0x12);
+  assert_eq!(mapper.ppu_read(0x0ABC), 0x12);
+}
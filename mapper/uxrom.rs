@@ -0,0 +1,80 @@
+// Source: https://wiki.nesdev.com/w/index.php/UxROM
+
+use cartridge::{Cartridge, Mirroring};
+use mapper::{Mapper, MapperError};
+
+const PRG_BANK_SIZE: usize = 16 * 1024; // 16 KB
+const CHR_SIZE: usize = 8 * 1024; // 8 KB
+
+pub struct Uxrom {
+  prg_rom: Vec<u8>,
+  chr: Vec<u8>,
+  chr_is_ram: bool,
+  mirroring: Mirroring,
+  bus_conflicts: bool,
+  prg_bank: u8, // 16 KB bank at 0x8000-0xBFFF
+}
+
+impl Uxrom {
+  pub fn new(cartridge: Cartridge) -> Result<Uxrom, MapperError> {
+    let prg_rom = cartridge.get_prg_rom().to_vec();
+    if !prg_rom.len().is_multiple_of(PRG_BANK_SIZE) {
+      return Err(MapperError::InvalidPrgRomSize(prg_rom.len()));
+    }
+
+    let chr_is_ram = cartridge.get_chr_rom().is_empty();
+    let chr = match chr_is_ram {
+      true => vec![0; CHR_SIZE],
+      false => cartridge.get_chr_rom().to_vec(),
+    };
+    if chr.len() != CHR_SIZE {
+      return Err(MapperError::InvalidChrSize(chr.len()));
+    }
+
+    Ok(Uxrom {
+      prg_rom,
+      chr,
+      chr_is_ram,
+      mirroring: cartridge.get_mirroring(),
+      // Submapper 1 marks boards without bus conflicts, 0 and 2 are treated as UNROM
+      bus_conflicts: cartridge.get_submapper() != 1,
+      prg_bank: 0,
+    })
+  }
+}
+
+impl Mapper for Uxrom {
+  fn cpu_read(&self, addr: u16) -> Option<u8> {
+    let bank_count = self.prg_rom.len() / PRG_BANK_SIZE;
+    let bank = match addr {
+      0x8000..=0xBFFF => self.prg_bank as usize % bank_count,
+      0xC000..=0xFFFF => bank_count - 1,
+      _ => return None,
+    };
+    Some(self.prg_rom[bank * PRG_BANK_SIZE + (addr as usize & (PRG_BANK_SIZE - 1))])
+  }
+
+  fn cpu_write(&mut self, addr: u16, data: u8) {
+    if addr >= 0x8000 {
+      // The ROM drives the bus at the same time as the CPU, the result is their AND
+      self.prg_bank = match self.bus_conflicts {
+        true => data & self.cpu_read(addr).unwrap_or(0xFF),
+        false => data,
+      };
+    }
+  }
+
+  fn ppu_read(&mut self, addr: u16) -> u8 {
+    self.chr[(addr & 0x1FFF) as usize]
+  }
+
+  fn ppu_write(&mut self, addr: u16, data: u8) {
+    if self.chr_is_ram {
+      self.chr[(addr & 0x1FFF) as usize] = data;
+    }
+  }
+
+  fn get_mirroring(&self) -> Mirroring {
+    self.mirroring
+  }
+}
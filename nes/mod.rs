@@ -0,0 +1,124 @@
+// Source: https://wiki.nesdev.com/w/index.php/Cycle_reference_chart
+
+mod tests;
+pub mod trace;
+
+use apu::Apu;
+use bus::Bus;
+use cartridge::{Cartridge, Region};
+use cpu::Cpu;
+use mapper::{from_cartridge, MapperError};
+use ppu::Ppu;
+
+// PPU dots per CPU cycle are counted in fifths, so PAL's 3.2 stays exact
+const DOT_FRACTION: u8 = 5;
+
+fn dot_fractions_per_cycle(region: Region) -> u8 {
+  match region {
+    Region::Pal => 16,                                  // 3.2 dots
+    Region::Ntsc | Region::Multi | Region::Dendy => 15, // 3 dots
+  }
+}
+
+// The whole console, driven by the CPU clock
+pub struct Nes {
+  cpu: Cpu, // Owns the bus, which owns the PPU and cartridge
+  region: Region,
+  dot_fractions: u8, // Part of a PPU dot carried over to the next CPU cycle
+}
+
+impl Nes {
+  pub fn with_bus(mut bus: Bus, region: Region) -> Nes {
+    bus.set_region(region);
+    Nes {
+      cpu: Cpu::with_bus(bus),
+      region,
+      dot_fractions: 0,
+    }
+  }
+
+  pub fn from_cartridge(cartridge: Cartridge) -> Result<Nes, MapperError> {
+    let region = cartridge.get_region();
+    let mut bus = Bus::new();
+    bus.insert_cartridge(from_cartridge(cartridge)?);
+    Ok(Nes::with_bus(bus, region))
+  }
+
+  pub fn get_cpu(&self) -> &Cpu {
+    &self.cpu
+  }
+
+  pub fn get_mut_cpu(&mut self) -> &mut Cpu {
+    &mut self.cpu
+  }
+
+  pub fn get_bus(&self) -> &Bus {
+    self.cpu.get_bus_ref()
+  }
+
+  pub fn get_mut_bus(&mut self) -> &mut Bus {
+    self.cpu.get_mut_bus_ref()
+  }
+
+  pub fn get_ppu(&self) -> &Ppu {
+    self.get_bus().get_ppu()
+  }
+
+  pub fn get_apu(&self) -> &Apu {
+    self.get_bus().get_apu()
+  }
+
+  pub fn get_region(&self) -> Region {
+    self.region
+  }
+
+  // Address of the JAM opcode that halted the CPU, if any
+  pub fn get_jam_addr(&self) -> Option<u16> {
+    match self.cpu.is_jammed() {
+      true => Some(*self.cpu.get_ppc()),
+      false => None,
+    }
+  }
+
+  pub fn reset(&mut self) {
+    self.cpu.reset();
+  }
+
+  // Advance the whole system by one CPU cycle
+  pub fn step_cycle(&mut self) {
+    self.cpu.clock();
+
+    let bus = self.cpu.get_mut_bus_ref();
+    bus.clock();
+    self.dot_fractions += dot_fractions_per_cycle(self.region);
+    while self.dot_fractions >= DOT_FRACTION {
+      bus.clock_ppu();
+      self.dot_fractions -= DOT_FRACTION;
+    }
+  }
+
+  // Run until the current instruction, or the next one at a boundary, has finished. Cycles DMA
+  // takes before the instruction starts are included
+  pub fn step_instruction(&mut self) {
+    self.step_cycle();
+    while !self.cpu.is_cycle_complete() || self.cpu.is_halted() {
+      self.step_cycle();
+    }
+  }
+
+  // Run until the PPU starts the next scanline
+  pub fn step_scanline(&mut self) {
+    let scanline = self.get_ppu().get_scanline();
+    while self.get_ppu().get_scanline() == scanline {
+      self.step_cycle();
+    }
+  }
+
+  // Run until the PPU starts the next frame
+  pub fn step_frame(&mut self) {
+    let frame = self.get_ppu().get_frame();
+    while self.get_ppu().get_frame() == frame {
+      self.step_cycle();
+    }
+  }
+}
@@ -0,0 +1,182 @@
+// Source: roms/instr_test/readme.txt and blargg's shared test ROM framework
+// Tests report through PRG RAM: a status byte at 0x6000, a signature and a text message
+
+#[cfg(test)]
+use cartridge::Cartridge;
+
+#[cfg(test)]
+use data_flow::ReadWrite;
+
+#[cfg(test)]
+use nes::Nes;
+
+#[cfg(test)]
+const STATUS_ADDR: u16 = 0x6000;
+
+#[cfg(test)]
+const SIGNATURE_ADDR: u16 = 0x6001;
+
+#[cfg(test)]
+const SIGNATURE: [u8; 3] = [0xDE, 0xB0, 0x61]; // Status and text are only valid once this is written
+
+#[cfg(test)]
+const TEXT_ADDR: u16 = 0x6004;
+
+#[cfg(test)]
+const STATUS_RUNNING: u8 = 0x80;
+
+#[cfg(test)]
+const STATUS_NEEDS_RESET: u8 = 0x81;
+
+#[cfg(test)]
+const RESET_DELAY_FRAMES: usize = 6; // The ROM asks for at least 100 ms before the reset
+
+#[cfg(test)]
+const FRAME_LIMIT: usize = 60 * 60;
+
+#[cfg(test)]
+fn peek(nes: &Nes, addr: u16) -> u8 {
+  nes.get_bus().read_addr(addr)
+}
+
+#[cfg(test)]
+fn has_signature(nes: &Nes) -> bool {
+  SIGNATURE
+    .iter()
+    .enumerate()
+    .all(|(offset, byte)| peek(nes, SIGNATURE_ADDR + offset as u16) == *byte)
+}
+
+#[cfg(test)]
+fn get_text(nes: &Nes) -> String {
+  (TEXT_ADDR..=0x7FFF)
+    .map(|addr| peek(nes, addr))
+    .take_while(|byte| *byte != 0)
+    .map(|byte| byte as char)
+    .collect()
+}
+
+// Runs a ROM under roms/ until it reports, returning its result code and message
+#[cfg(test)]
+fn run_test_rom(name: &str) -> (u8, String) {
+  let path = format!("{}/roms/{}", env!("CARGO_MANIFEST_DIR"), name);
+  let mut nes = Nes::from_cartridge(Cartridge::from_file(&path).unwrap()).unwrap();
+  nes.reset();
+
+  let mut started = false;
+  for _ in 0..FRAME_LIMIT {
+    nes.step_frame();
+    if let Some(addr) = nes.get_jam_addr() {
+      panic!("{}: CPU jammed at ${:04X}:\n{}", name, addr, get_text(&nes));
+    }
+    if !has_signature(&nes) {
+      continue;
+    }
+    match peek(&nes, STATUS_ADDR) {
+      STATUS_RUNNING => started = true,
+      STATUS_NEEDS_RESET => {
+        for _ in 0..RESET_DELAY_FRAMES {
+          nes.step_frame();
+        }
+        nes.reset();
+      }
+      status if started => return (status, get_text(&nes)),
+      _ => (),
+    }
+  }
+  panic!(
+    "{} did not finish within {} frames:\n{}",
+    name,
+    FRAME_LIMIT,
+    get_text(&nes)
+  );
+}
+
+#[cfg(test)]
+fn assert_passes(name: &str) {
+  let (status, text) = run_test_rom(name);
+  assert!(
+    status == 0,
+    "{} failed with code {}:\n{}",
+    name,
+    status,
+    text
+  );
+}
+
+#[cfg(test)]
+mod instr_test {
+  use super::*;
+  #[test]
+  fn basics() {
+    assert_passes("instr_test/rom_singles/01-basics.nes");
+  }
+  #[test]
+  fn implied() {
+    assert_passes("instr_test/rom_singles/02-implied.nes");
+  }
+  #[test]
+  fn immediate() {
+    assert_passes("instr_test/rom_singles/03-immediate.nes");
+  }
+  #[test]
+  fn zero_page() {
+    assert_passes("instr_test/rom_singles/04-zero_page.nes");
+  }
+  #[test]
+  fn zero_page_indexed() {
+    assert_passes("instr_test/rom_singles/05-zp_xy.nes");
+  }
+  #[test]
+  fn absolute() {
+    assert_passes("instr_test/rom_singles/06-absolute.nes");
+  }
+  #[test]
+  fn absolute_indexed() {
+    assert_passes("instr_test/rom_singles/07-abs_xy.nes");
+  }
+  #[test]
+  fn indirect_x() {
+    assert_passes("instr_test/rom_singles/08-ind_x.nes");
+  }
+  #[test]
+  fn indirect_y() {
+    assert_passes("instr_test/rom_singles/09-ind_y.nes");
+  }
+  #[test]
+  fn branches() {
+    assert_passes("instr_test/rom_singles/10-branches.nes");
+  }
+  #[test]
+  fn stack() {
+    assert_passes("instr_test/rom_singles/11-stack.nes");
+  }
+  #[test]
+  fn jmp_jsr() {
+    assert_passes("instr_test/rom_singles/12-jmp_jsr.nes");
+  }
+  #[test]
+  fn rts() {
+    assert_passes("instr_test/rom_singles/13-rts.nes");
+  }
+  #[test]
+  fn rti() {
+    assert_passes("instr_test/rom_singles/14-rti.nes");
+  }
+  #[test]
+  fn brk() {
+    assert_passes("instr_test/rom_singles/15-brk.nes");
+  }
+  #[test]
+  fn special() {
+    assert_passes("instr_test/rom_singles/16-special.nes");
+  }
+  #[test]
+  fn official_only() {
+    assert_passes("instr_test/official_only.nes");
+  }
+  #[test]
+  fn all_instructions() {
+    assert_passes("instr_test/all_instrs.nes");
+  }
+}
@@ -0,0 +1,226 @@
+mod blargg;
+mod nestest;
+
+#[cfg(test)]
+use cartridge::{Cartridge, Region};
+
+#[cfg(test)]
+use data_flow::ReadWrite;
+
+#[cfg(test)]
+use nes::trace::trace;
+
+#[cfg(test)]
+use nes::Nes;
+
+#[cfg(test)]
+const NMI_HANDLER: u16 = 0x9000;
+
+#[cfg(test)]
+const IRQ_HANDLER: u16 = 0xA000;
+
+// NROM cartridge filled with NOPs, flags 9 selects the region
+#[cfg(test)]
+fn setup(flags_9: u8) -> Nes {
+  let mut rom = vec![
+    0x4E, 0x45, 0x53, 0x1A, 2, 1, 0, 0, 0, flags_9, 0, 0, 0, 0, 0, 0,
+  ];
+  let mut prg_rom = vec![0xEA; 32 * 1024];
+  prg_rom[0x7FFA] = NMI_HANDLER as u8;
+  prg_rom[0x7FFB] = (NMI_HANDLER >> 8) as u8;
+  prg_rom[0x7FFC] = 0x00;
+  prg_rom[0x7FFD] = 0x80;
+  prg_rom[0x7FFE] = IRQ_HANDLER as u8;
+  prg_rom[0x7FFF] = (IRQ_HANDLER >> 8) as u8;
+  rom.extend(prg_rom);
+  rom.extend(vec![0; 8 * 1024]);
+
+  let mut nes = Nes::from_cartridge(Cartridge::from_bytes(&rom).unwrap()).unwrap();
+  nes.reset();
+  nes
+}
+
+// PPU dots since power on, ignoring the odd frame skip which needs rendering
+#[cfg(test)]
+fn get_dots(nes: &Nes) -> u64 {
+  let ppu = nes.get_ppu();
+  (ppu.get_frame() * 262 + ppu.get_scanline() as u64) * 341 + ppu.get_dot() as u64
+}
+
+#[cfg(test)]
+mod scheduling {
+  use super::*;
+  #[test]
+  fn reads_region_from_cartridge() {
+    assert_eq!(setup(0).get_region(), Region::Ntsc);
+    assert_eq!(setup(1).get_region(), Region::Pal);
+  }
+  #[test]
+  fn ntsc_runs_three_dots_per_cycle() {
+    let mut nes = setup(0);
+    for cycle in 1..=10 {
+      nes.step_cycle();
+      assert_eq!(get_dots(&nes), cycle * 3);
+    }
+  }
+  #[test]
+  fn pal_runs_sixteen_dots_per_five_cycles() {
+    let mut nes = setup(1);
+    let dots: Vec<u64> = (0..10)
+      .map(|_| {
+        nes.step_cycle();
+        get_dots(&nes)
+      })
+      .collect();
+    assert_eq!(dots, vec![3, 6, 9, 12, 16, 19, 22, 25, 28, 32]);
+  }
+  #[test]
+  fn cycle_counts_cpu_ticks() {
+    let mut nes = setup(0);
+    let tick = *nes.get_cpu().get_current_tick();
+    nes.step_cycle();
+    assert_eq!(*nes.get_cpu().get_current_tick(), tick + 1);
+  }
+  #[test]
+  fn instruction_runs_all_of_its_cycles() {
+    let mut nes = setup(0);
+    // Finish the reset sequence, then one two cycle NOP
+    nes.step_instruction();
+    let tick = *nes.get_cpu().get_current_tick();
+    let pc = *nes.get_cpu().get_pc();
+    nes.step_instruction();
+    assert_eq!(*nes.get_cpu().get_current_tick(), tick + 2);
+    assert_eq!(*nes.get_cpu().get_pc(), pc + 1);
+    assert!(nes.get_cpu().is_cycle_complete());
+  }
+  #[test]
+  fn scanline_stops_at_start_of_next_line() {
+    let mut nes = setup(0);
+    nes.step_scanline();
+    assert_eq!(nes.get_ppu().get_scanline(), 1);
+    assert!(nes.get_ppu().get_dot() < 3);
+    nes.step_scanline();
+    assert_eq!(nes.get_ppu().get_scanline(), 2);
+  }
+  #[test]
+  fn frame_stops_at_start_of_next_frame() {
+    let mut nes = setup(0);
+    nes.step_frame();
+    assert_eq!(nes.get_ppu().get_frame(), 1);
+    assert_eq!(nes.get_ppu().get_scanline(), 0);
+    // 89342 dots per frame
+    assert_eq!(*nes.get_cpu().get_current_tick(), 29781);
+  }
+  #[test]
+  fn pal_frame_is_312_scanlines() {
+    let mut nes = setup(1);
+    nes.step_frame();
+    assert_eq!(nes.get_ppu().get_scanline(), 0);
+    // 106392 dots per frame at 3.2 a cycle
+    assert_eq!(*nes.get_cpu().get_current_tick(), 33248);
+  }
+}
+
+#[cfg(test)]
+mod interrupts {
+  use super::*;
+  // Runs a program from RAM once reset is done, away from the NOPs that run through the handlers
+  fn run_from_ram(nes: &mut Nes, program: &[u8]) {
+    nes.step_instruction();
+    for (addr, byte) in program.iter().enumerate() {
+      nes.get_mut_bus().write_addr(addr as u16, *byte);
+    }
+    nes.get_mut_cpu().set_pc(0x0000);
+  }
+  #[test]
+  fn vblank_nmi_reaches_cpu() {
+    let mut nes = setup(0);
+    nes.get_mut_bus().write_addr(0x2000, 0x80);
+    while nes.get_ppu().get_scanline() != 241 || nes.get_ppu().get_dot() < 2 {
+      nes.step_instruction();
+    }
+    // The edge can land after the running NOP polled, which then lets one more run
+    nes.step_instruction();
+    if *nes.get_cpu().get_pc() != NMI_HANDLER {
+      nes.step_instruction();
+    }
+    assert_eq!(*nes.get_cpu().get_pc(), NMI_HANDLER);
+  }
+  #[test]
+  fn nmi_is_taken_once_per_vblank() {
+    // The line stays asserted until PPUSTATUS is read, only its edge raises an NMI
+    let mut nes = setup(0);
+    nes.get_mut_bus().write_addr(0x2000, 0x80);
+    let mut nmis = 0;
+    while nes.get_ppu().get_frame() < 2 {
+      let pc = *nes.get_cpu().get_pc();
+      nes.step_instruction();
+      // The NOPs also run through the handler's address without an NMI
+      if *nes.get_cpu().get_pc() == NMI_HANDLER && pc != NMI_HANDLER - 1 {
+        nmis += 1;
+      }
+    }
+    assert_eq!(nmis, 2);
+  }
+  #[test]
+  fn frame_counter_irq_reaches_cpu() {
+    let mut nes = setup(0);
+    // CLI, JMP $0001
+    run_from_ram(&mut nes, &[0x58, 0x4C, 0x01, 0x00]);
+    while *nes.get_cpu().get_pc() != IRQ_HANDLER {
+      assert!(*nes.get_cpu().get_current_tick() < 30_000);
+      nes.step_instruction();
+    }
+    assert!(*nes.get_cpu().get_current_tick() > 29_828);
+    assert_eq!(nes.get_apu().peek_status() & 0x40, 0x40);
+  }
+  #[test]
+  fn inhibited_frame_counter_irq_does_not() {
+    let mut nes = setup(0);
+    nes.get_mut_bus().write_addr(0x4017, 0x40);
+    // CLI, JMP $0001
+    run_from_ram(&mut nes, &[0x58, 0x4C, 0x01, 0x00]);
+    while *nes.get_cpu().get_current_tick() < 40_000 {
+      nes.step_instruction();
+      assert_ne!(*nes.get_cpu().get_pc(), IRQ_HANDLER);
+    }
+  }
+  #[test]
+  fn no_nmi_when_disabled() {
+    let mut nes = setup(0);
+    nes.step_frame();
+    assert_ne!(*nes.get_cpu().get_pc(), NMI_HANDLER);
+  }
+}
+
+#[cfg(test)]
+mod dma {
+  use super::*;
+  fn get_trace_cycle(nes: &Nes) -> usize {
+    trace(nes).rsplit("CYC:").next().unwrap().parse().unwrap()
+  }
+  #[test]
+  fn dmc_fetches_stall_cpu_in_trace() {
+    let mut nes = setup(0);
+    nes.step_instruction();
+    // Loop a one byte sample at the fastest rate, a fetch every 432 cycles
+    nes.get_mut_bus().write_addr(0x4010, 0x4F);
+    nes.get_mut_bus().write_addr(0x4013, 0x00);
+    nes.get_mut_bus().write_addr(0x4015, 0x10);
+    let mut cycle = get_trace_cycle(&nes);
+    let mut stalls = Vec::new();
+    for _ in 0..1000 {
+      nes.step_instruction();
+      let next_cycle = get_trace_cycle(&nes);
+      // Every instruction is a two cycle NOP
+      if next_cycle - cycle != 2 {
+        stalls.push(next_cycle - cycle - 2);
+      }
+      cycle = next_cycle;
+    }
+    // Reloads during the sample take four cycles, the first fetch may take three
+    assert!(stalls.len() > 2, "{:?}", stalls);
+    assert!(stalls[0] == 3 || stalls[0] == 4, "{:?}", stalls);
+    assert!(stalls[1..].iter().all(|stall| *stall == 4), "{:?}", stalls);
+  }
+}
@@ -0,0 +1,177 @@
+// Source: https://www.qmtpro.com/~nes/misc/nestest.txt
+
+#[cfg(test)]
+use cartridge::Cartridge;
+
+#[cfg(test)]
+use data_flow::ReadWrite;
+
+#[cfg(test)]
+use nes::trace::trace;
+
+#[cfg(test)]
+use nes::Nes;
+
+#[cfg(test)]
+use std::fs;
+
+#[cfg(test)]
+const AUTOMATION_START: u16 = 0xC000;
+
+#[cfg(test)]
+const AUTOMATION_END: u16 = 0xC66E; // Final RTS once every test has run
+
+// Failing tests store their code at 0x00, which is copied here before the unofficial tests
+#[cfg(test)]
+const OFFICIAL_RESULT_ADDR: u16 = 0x0010;
+
+#[cfg(test)]
+const UNOFFICIAL_RESULT_ADDR: u16 = 0x0011;
+
+#[cfg(test)]
+const UNOFFICIAL_START: u16 = 0xC6BD; // First unofficial opcode test
+
+#[cfg(test)]
+const INSTRUCTION_LIMIT: usize = 10_000;
+
+// Automation mode starts at 0xC000 once the reset sequence has taken the first 7 cycles
+#[cfg(test)]
+fn setup() -> Nes {
+  let path = format!("{}/roms/nestest.nes", env!("CARGO_MANIFEST_DIR"));
+  let mut nes = Nes::from_cartridge(Cartridge::from_file(&path).unwrap()).unwrap();
+  nes.reset();
+  nes.step_instruction();
+  nes.get_mut_cpu().set_pc(AUTOMATION_START);
+  nes
+}
+
+// Registers and PPU position of a log line, the instruction and disassembly are compared whole
+#[cfg(test)]
+fn get_fields(line: &str) -> Vec<(&str, &str)> {
+  let registers = line.find("A:").unwrap_or(line.len());
+  let mut fields = vec![("Instruction", line[..registers].trim_end())];
+  let keys = ["A:", "X:", "Y:", "P:", "SP:", "PPU:", "CYC:"];
+  for (index, key) in keys.iter().enumerate() {
+    let value = match line[registers..].find(key) {
+      Some(start) => {
+        let start = registers + start + key.len();
+        let end = match keys.get(index + 1) {
+          Some(next) => line[start..]
+            .find(next)
+            .map_or(line.len(), |end| start + end),
+          None => line.len(),
+        };
+        line[start..end].trim()
+      }
+      None => "",
+    };
+    fields.push((&key[..key.len() - 1], value));
+  }
+  fields
+}
+
+#[cfg(test)]
+fn compare(number: usize, expected: &str, actual: &str) -> Result<(), String> {
+  if expected == actual {
+    return Ok(());
+  }
+
+  let mut report = format!(
+    "nestest.log diverges at line {}\nexpected: {}\nactual:   {}\n",
+    number, expected, actual
+  );
+  for (expected, actual) in get_fields(expected).iter().zip(get_fields(actual).iter()) {
+    if expected.1 != actual.1 {
+      report.push_str(&format!(
+        "  {}: expected {}, got {}\n",
+        expected.0, expected.1, actual.1
+      ));
+    }
+  }
+  Err(report)
+}
+
+#[cfg(test)]
+mod reference_log {
+  use super::*;
+  #[test]
+  #[ignore = "roms/nestest.log only holds the first 14 lines of the reference log"]
+  fn matches_every_line() {
+    // The full log from the link above, one line for each instruction of the automation run
+    let path = format!("{}/roms/nestest.log", env!("CARGO_MANIFEST_DIR"));
+    let reference = fs::read_to_string(&path).expect("roms/nestest.log is missing");
+    let mut lines = reference.lines();
+    let mut nes = setup();
+    for number in 1..=INSTRUCTION_LIMIT {
+      let expected = match lines.next() {
+        Some(expected) => expected,
+        None => panic!("nestest.log ends before line {} of the run", number),
+      };
+      if let Err(report) = compare(number, expected, &trace(&nes)) {
+        panic!("{}", report);
+      }
+      // The log ends with the final RTS
+      if *nes.get_cpu().get_pc() == AUTOMATION_END {
+        return;
+      }
+      nes.step_instruction();
+    }
+    panic!("nestest did not finish");
+  }
+  #[test]
+  fn reports_changed_registers() {
+    let expected = "C72F  B0 04     BCS $C735                       A:00 X:00 Y:00 P:27 SP:FB PPU:  0, 93 CYC:31";
+    let actual = "C72F  B0 04     BCS $C735                       A:00 X:00 Y:00 P:26 SP:FB PPU:  0, 96 CYC:32";
+    let report = compare(9, expected, actual).unwrap_err();
+    assert!(report.starts_with("nestest.log diverges at line 9\n"));
+    assert!(report.contains("  P: expected 27, got 26\n"));
+    assert!(report.contains("  PPU: expected 0, 93, got 0, 96\n"));
+    assert!(report.contains("  CYC: expected 31, got 32\n"));
+    assert!(!report.contains("  A: expected"));
+    assert!(!report.contains("  Instruction: expected"));
+  }
+  #[test]
+  fn marks_unofficial_opcodes() {
+    let mut nes = setup();
+    while *nes.get_cpu().get_pc() != UNOFFICIAL_START {
+      nes.step_instruction();
+    }
+    let line = trace(&nes);
+    assert!(
+      line.starts_with("C6BD  04 A9    *NOP $A9 = 00  "),
+      "{}",
+      line
+    );
+  }
+}
+
+#[cfg(test)]
+mod automation {
+  use super::*;
+  #[test]
+  fn passes_official_and_unofficial_opcode_tests() {
+    let mut nes = setup();
+    let mut instructions = 0;
+    while *nes.get_cpu().get_pc() != AUTOMATION_END {
+      assert!(instructions < INSTRUCTION_LIMIT, "nestest did not finish");
+      if let Some(addr) = nes.get_jam_addr() {
+        panic!("CPU jammed at ${:04X}", addr);
+      }
+      nes.step_instruction();
+      instructions += 1;
+    }
+
+    let official = nes.get_bus().read_addr(OFFICIAL_RESULT_ADDR);
+    assert!(
+      official == 0x00,
+      "nestest failure code {:02X}h, see nestest.txt",
+      official
+    );
+    let unofficial = nes.get_bus().read_addr(UNOFFICIAL_RESULT_ADDR);
+    assert!(
+      unofficial == 0x00,
+      "nestest unofficial failure code {:02X}h, see nestest.txt",
+      unofficial
+    );
+  }
+}
@@ -0,0 +1,144 @@
+// Source: https://www.qmtpro.com/~nes/misc/nestest.log
+
+use cpu::instruction::{decode, AddressMode, Operation};
+use data_flow::ReadWrite;
+use nes::Nes;
+
+// The instruction at PC and the state before it runs, in the format of Nintendulator's nestest.log
+pub fn trace(nes: &Nes) -> String {
+  let cpu = nes.get_cpu();
+  let ppu = nes.get_ppu();
+  let pc = *cpu.get_pc();
+  let instruction = decode(peek(nes, pc), cpu.get_variant());
+  let length = instruction.get_instruction_bytes().max(1) as u16;
+  let bytes: Vec<String> = (0..length)
+    .map(|offset| format!("{:02X}", peek(nes, pc.wrapping_add(offset))))
+    .collect();
+
+  format!(
+    "{:04X}  {:<8} {:>1}{:<32}A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X} PPU:{:>3},{:>3} CYC:{}",
+    pc,
+    bytes.join(" "),
+    match instruction.is_official() {
+      true => "",
+      false => "*",
+    },
+    disassemble(nes, pc),
+    cpu.get_acc(),
+    cpu.get_x(),
+    cpu.get_y(),
+    cpu.get_status(),
+    cpu.get_stkp(),
+    ppu.get_scanline(),
+    ppu.get_dot(),
+    cpu.get_current_tick()
+  )
+}
+
+// Mnemonic and operand, with the addresses and values the operand resolves to
+pub fn disassemble(nes: &Nes, pc: u16) -> String {
+  let cpu = nes.get_cpu();
+  let instruction = decode(peek(nes, pc), cpu.get_variant());
+  let operation = instruction.get_operation();
+  let byte = peek(nes, pc.wrapping_add(1));
+  let word = peek_word(nes, pc.wrapping_add(1));
+  let x = *cpu.get_x();
+  let y = *cpu.get_y();
+
+  let operand = match instruction.get_address_mode() {
+    AddressMode::Accumulator => " A".to_string(),
+    AddressMode::Immediate => format!(" #${:02X}", byte),
+    AddressMode::Absolute => match operation {
+      Operation::JMP | Operation::JSR => format!(" ${:04X}", word),
+      _ => format!(" ${:04X} = {:02X}", word, peek(nes, word)),
+    },
+    AddressMode::AbsoluteX => {
+      let addr = word.wrapping_add(x as u16);
+      format!(" ${:04X},X @ {:04X} = {:02X}", word, addr, peek(nes, addr))
+    }
+    AddressMode::AbsoluteY => {
+      let addr = word.wrapping_add(y as u16);
+      format!(" ${:04X},Y @ {:04X} = {:02X}", word, addr, peek(nes, addr))
+    }
+    AddressMode::ZeroPage => format!(" ${:02X} = {:02X}", byte, peek(nes, byte as u16)),
+    AddressMode::ZeroPageX => {
+      let addr = byte.wrapping_add(x);
+      format!(
+        " ${:02X},X @ {:02X} = {:02X}",
+        byte,
+        addr,
+        peek(nes, addr as u16)
+      )
+    }
+    AddressMode::ZeroPageY => {
+      let addr = byte.wrapping_add(y);
+      format!(
+        " ${:02X},Y @ {:02X} = {:02X}",
+        byte,
+        addr,
+        peek(nes, addr as u16)
+      )
+    }
+    AddressMode::IndirectX => {
+      let ptr = byte.wrapping_add(x);
+      let addr = peek_zero_page_word(nes, ptr);
+      format!(
+        " (${:02X},X) @ {:02X} = {:04X} = {:02X}",
+        byte,
+        ptr,
+        addr,
+        peek(nes, addr)
+      )
+    }
+    AddressMode::IndirectY => {
+      let base = peek_zero_page_word(nes, byte);
+      let addr = base.wrapping_add(y as u16);
+      format!(
+        " (${:02X}),Y = {:04X} @ {:04X} = {:02X}",
+        byte,
+        base,
+        addr,
+        peek(nes, addr)
+      )
+    }
+    AddressMode::AbsoluteIndirect => {
+      // The pointer's high byte does not carry into the next page
+      let hi_addr = (word & 0xFF00) | (word.wrapping_add(1) & 0x00FF);
+      let addr = (peek(nes, hi_addr) as u16) << 8 | peek(nes, word) as u16;
+      format!(" (${:04X}) = {:04X}", word, addr)
+    }
+    AddressMode::ZeroPageIndirect => {
+      let addr = peek_zero_page_word(nes, byte);
+      format!(" (${:02X}) = {:04X} = {:02X}", byte, addr, peek(nes, addr))
+    }
+    AddressMode::AbsoluteIndexedIndirect => {
+      let addr = peek_word(nes, word.wrapping_add(x as u16));
+      format!(" (${:04X},X) = {:04X}", word, addr)
+    }
+    AddressMode::Relative => {
+      let target = pc.wrapping_add(2).wrapping_add(byte as i8 as u16);
+      format!(" ${:04X}", target)
+    }
+    AddressMode::Implied => String::new(),
+  };
+
+  // Nintendulator's name for ISC
+  let mnemonic = match operation {
+    Operation::ISC => "ISB".to_string(),
+    _ => operation.to_string(),
+  };
+  format!("{}{}", mnemonic, operand)
+}
+
+// Inspecting memory must not trigger register side effects, so the bus is only peeked
+fn peek(nes: &Nes, addr: u16) -> u8 {
+  nes.get_bus().read_addr(addr)
+}
+
+fn peek_word(nes: &Nes, addr: u16) -> u16 {
+  (peek(nes, addr.wrapping_add(1)) as u16) << 8 | peek(nes, addr) as u16
+}
+
+fn peek_zero_page_word(nes: &Nes, ptr: u8) -> u16 {
+  (peek(nes, ptr.wrapping_add(1) as u16) as u16) << 8 | peek(nes, ptr as u16) as u16
+}
@@ -0,0 +1,738 @@
+// Source: https://wiki.nesdev.com/w/index.php/PPU
+
+mod tests;
+
+use cartridge::{Mirroring, Region};
+use mapper::Mapper;
+
+pub const SCREEN_WIDTH: usize = 256;
+pub const SCREEN_HEIGHT: usize = 240;
+const DOTS_PER_SCANLINE: u16 = 341;
+const NTSC_PRE_RENDER_SCANLINE: u16 = 261;
+const PAL_PRE_RENDER_SCANLINE: u16 = 311;
+const NAMETABLE_SIZE: usize = 1024; // 1 KB
+const OAM_SIZE: usize = 256;
+const SPRITES_PER_LINE: usize = 8;
+const PALETTE_SIZE: usize = 32;
+const PALETTE_ADDR: u16 = 0x3F00;
+
+// PPUCTRL
+const CTRL_INCREMENT_32: u8 = 0x04;
+const CTRL_SPRITE_TABLE: u8 = 0x08;
+const CTRL_BACKGROUND_TABLE: u8 = 0x10;
+const CTRL_SPRITE_8X16: u8 = 0x20;
+const CTRL_NMI_ENABLE: u8 = 0x80;
+// PPUMASK
+const MASK_GREYSCALE: u8 = 0x01;
+const MASK_BACKGROUND_LEFT: u8 = 0x02;
+const MASK_SPRITES_LEFT: u8 = 0x04;
+const MASK_BACKGROUND: u8 = 0x08;
+const MASK_SPRITES: u8 = 0x10;
+// PPUSTATUS
+const STATUS_SPRITE_OVERFLOW: u8 = 0x20;
+const STATUS_SPRITE_0_HIT: u8 = 0x40;
+const STATUS_VBLANK: u8 = 0x80;
+// Sprite attributes
+const ATTRIBUTE_PALETTE: u8 = 0x03;
+const ATTRIBUTE_BEHIND_BACKGROUND: u8 = 0x20;
+const ATTRIBUTE_FLIP_HORIZONTAL: u8 = 0x40;
+const ATTRIBUTE_FLIP_VERTICAL: u8 = 0x80;
+
+pub struct Ppu {
+  // CPU visible registers
+  ctrl: u8,     // 0x2000 PPUCTRL: VPHBSINN
+  mask: u8,     // 0x2001 PPUMASK: BGRsbMmG
+  status: u8,   // 0x2002 PPUSTATUS: VSO-----
+  oam_addr: u8, // 0x2003 OAMADDR
+  // Internal registers
+  v: u16,          // Current VRAM address: yyy NN YYYYY XXXXX
+  t: u16,          // Temporary VRAM address, the top left onscreen tile
+  x: u8,           // Fine X scroll
+  w: bool,         // First or second write toggle for PPUSCROLL and PPUADDR
+  read_buffer: u8, // PPUDATA reads are delayed by one read
+  io_latch: u8,    // Value left on the PPU data bus by the last register access
+  // Memory
+  nametables: [u8; 4 * NAMETABLE_SIZE], // 2 KB on the console, 4 KB with four-screen boards
+  palette: [u8; PALETTE_SIZE],
+  oam: [u8; OAM_SIZE],
+  // Timing
+  region: Region,
+  scanline: u16, // 0-239 visible, 240 post-render, then vblank until the last, pre-render line
+  dot: u16,      // 0-340
+  frame: u64,
+  // Background fetches and shift registers
+  nametable_byte: u8,
+  attribute_byte: u8,
+  pattern_lo: u8,
+  pattern_hi: u8,
+  pattern_shift_lo: u16,
+  pattern_shift_hi: u16,
+  attribute_shift_lo: u16,
+  attribute_shift_hi: u16,
+  // Sprite evaluation for the next line
+  secondary_oam: [u8; SPRITES_PER_LINE * 4],
+  sprite_count: usize,    // Sprites found in secondary OAM
+  sprite_zero_next: bool, // Secondary OAM starts with sprite 0
+  // Sprites fetched for the current line
+  sprite_patterns_lo: [u8; SPRITES_PER_LINE],
+  sprite_patterns_hi: [u8; SPRITES_PER_LINE],
+  sprite_attributes: [u8; SPRITES_PER_LINE],
+  sprite_positions: [u8; SPRITES_PER_LINE],
+  sprite_line_count: usize,
+  sprite_zero_line: bool,
+  framebuffer: Box<[u8]>, // Palette indexes, 256x240
+}
+
+impl Ppu {
+  pub fn new() -> Ppu {
+    Ppu {
+      ctrl: 0,
+      mask: 0,
+      status: 0,
+      oam_addr: 0,
+      v: 0,
+      t: 0,
+      x: 0,
+      w: false,
+      read_buffer: 0,
+      io_latch: 0,
+      nametables: [0; 4 * NAMETABLE_SIZE],
+      palette: [0; PALETTE_SIZE],
+      oam: [0; OAM_SIZE],
+      region: Region::Ntsc,
+      scanline: 0,
+      dot: 0,
+      frame: 0,
+      nametable_byte: 0,
+      attribute_byte: 0,
+      pattern_lo: 0,
+      pattern_hi: 0,
+      pattern_shift_lo: 0,
+      pattern_shift_hi: 0,
+      attribute_shift_lo: 0,
+      attribute_shift_hi: 0,
+      secondary_oam: [0xFF; SPRITES_PER_LINE * 4],
+      sprite_count: 0,
+      sprite_zero_next: false,
+      sprite_patterns_lo: [0; SPRITES_PER_LINE],
+      sprite_patterns_hi: [0; SPRITES_PER_LINE],
+      sprite_attributes: [0; SPRITES_PER_LINE],
+      sprite_positions: [0; SPRITES_PER_LINE],
+      sprite_line_count: 0,
+      sprite_zero_line: false,
+      framebuffer: vec![0; SCREEN_WIDTH * SCREEN_HEIGHT].into_boxed_slice(),
+    }
+  }
+
+  // PAL and Dendy frames are 312 lines long, 262 on NTSC
+  pub fn set_region(&mut self, region: Region) {
+    self.region = region;
+  }
+
+  pub fn get_scanline(&self) -> u16 {
+    self.scanline
+  }
+
+  pub fn get_dot(&self) -> u16 {
+    self.dot
+  }
+
+  pub fn get_frame(&self) -> u64 {
+    self.frame
+  }
+
+  pub fn get_framebuffer(&self) -> &[u8] {
+    &self.framebuffer
+  }
+
+  pub fn get_oam(&self) -> &[u8] {
+    &self.oam
+  }
+
+  pub fn get_vram_addr(&self) -> u16 {
+    self.v
+  }
+
+  pub fn get_temp_vram_addr(&self) -> u16 {
+    self.t
+  }
+
+  pub fn get_fine_x(&self) -> u8 {
+    self.x
+  }
+
+  pub fn is_rendering_enabled(&self) -> bool {
+    self.mask & (MASK_BACKGROUND | MASK_SPRITES) > 0
+  }
+
+  // Level of the NMI output, the CPU triggers on it going high
+  pub fn is_nmi_asserted(&self) -> bool {
+    self.status & STATUS_VBLANK > 0 && self.ctrl & CTRL_NMI_ENABLE > 0
+  }
+
+  // Register value without the side effects of a CPU read
+  pub fn peek_register(&self, addr: u16) -> u8 {
+    match addr & 0x0007 {
+      2 => (self.status & 0xE0) | (self.io_latch & 0x1F),
+      4 => self.read_oam_data(),
+      7 => self.read_buffer,
+      _ => self.io_latch,
+    }
+  }
+
+  pub fn read_register(&mut self, addr: u16, mapper: Option<&mut dyn Mapper>) -> u8 {
+    let mut mapper = mapper;
+    let data = match addr & 0x0007 {
+      2 => {
+        let data = (self.status & 0xE0) | (self.io_latch & 0x1F);
+        self.status &= !STATUS_VBLANK;
+        self.w = false;
+        data
+      }
+      4 => self.read_oam_data(),
+      7 => {
+        let addr = self.v & 0x3FFF;
+        let data = match addr {
+          // Palette reads are immediate, the buffer is filled from the nametable underneath
+          0x3F00..=0x3FFF => {
+            self.read_buffer = self.read(addr - 0x1000, &mut mapper);
+            (self.read(addr, &mut mapper) & 0x3F) | (self.io_latch & 0xC0)
+          }
+          _ => {
+            let data = self.read_buffer;
+            self.read_buffer = self.read(addr, &mut mapper);
+            data
+          }
+        };
+        self.increment_vram_addr();
+        data
+      }
+      _ => self.io_latch,
+    };
+    self.io_latch = data;
+    data
+  }
+
+  pub fn write_register(&mut self, addr: u16, data: u8, mapper: Option<&mut dyn Mapper>) {
+    let mut mapper = mapper;
+    self.io_latch = data;
+    match addr & 0x0007 {
+      0 => {
+        // Enabling NMI during vblank raises the line, and so another NMI, immediately
+        self.ctrl = data;
+        self.t = (self.t & 0xF3FF) | ((data as u16 & 0x03) << 10);
+      }
+      1 => self.mask = data,
+      3 => self.oam_addr = data,
+      4 => {
+        self.oam[self.oam_addr as usize] = data;
+        self.oam_addr = self.oam_addr.wrapping_add(1);
+      }
+      5 => {
+        if !self.w {
+          self.t = (self.t & 0xFFE0) | (data as u16 >> 3);
+          self.x = data & 0x07;
+        } else {
+          self.t = (self.t & 0x8C1F) | ((data as u16 & 0x07) << 12) | ((data as u16 & 0xF8) << 2);
+        }
+        self.w = !self.w;
+      }
+      6 => {
+        if !self.w {
+          self.t = (self.t & 0x80FF) | ((data as u16 & 0x3F) << 8);
+        } else {
+          self.t = (self.t & 0xFF00) | data as u16;
+          self.v = self.t;
+        }
+        self.w = !self.w;
+      }
+      7 => {
+        let addr = self.v & 0x3FFF;
+        self.write(addr, data, &mut mapper);
+        self.increment_vram_addr();
+      }
+      // PPUSTATUS is read-only
+      _ => (),
+    }
+  }
+
+  fn read_oam_data(&self) -> u8 {
+    let data = self.oam[self.oam_addr as usize];
+    // Bits 2-4 of the sprite attribute byte do not exist
+    match self.oam_addr & 0x03 {
+      2 => data & 0xE3,
+      _ => data,
+    }
+  }
+
+  fn increment_vram_addr(&mut self) {
+    let increment = match self.ctrl & CTRL_INCREMENT_32 > 0 {
+      true => 32,
+      false => 1,
+    };
+    self.v = self.v.wrapping_add(increment) & 0x7FFF;
+  }
+
+  // PPU address space: pattern tables on the cartridge, nametables and palette inside
+  fn read(&mut self, addr: u16, mapper: &mut Option<&mut dyn Mapper>) -> u8 {
+    match addr & 0x3FFF {
+      0x0000..=0x1FFF => match mapper {
+        Some(mapper) => mapper.ppu_read(addr),
+        None => 0,
+      },
+      0x2000..=0x3EFF => self.nametables[get_nametable_offset(addr, get_mirroring(mapper))],
+      _ => {
+        let data = self.palette[get_palette_offset(addr)];
+        match self.mask & MASK_GREYSCALE > 0 {
+          true => data & 0x30,
+          false => data,
+        }
+      }
+    }
+  }
+
+  fn write(&mut self, addr: u16, data: u8, mapper: &mut Option<&mut dyn Mapper>) {
+    match addr & 0x3FFF {
+      0x0000..=0x1FFF => {
+        if let Some(mapper) = mapper {
+          mapper.ppu_write(addr, data);
+        }
+      }
+      0x2000..=0x3EFF => {
+        let offset = get_nametable_offset(addr, get_mirroring(mapper));
+        self.nametables[offset] = data;
+      }
+      _ => self.palette[get_palette_offset(addr)] = data & 0x3F,
+    }
+  }
+
+  // Advance by one dot
+  pub fn clock(&mut self, mapper: Option<&mut dyn Mapper>) {
+    let mut mapper = mapper;
+    let pre_render_scanline = get_pre_render_scanline(self.region);
+    let rendering_line =
+      self.scanline < SCREEN_HEIGHT as u16 || self.scanline == pre_render_scanline;
+
+    if self.is_rendering_enabled() && rendering_line {
+      self.fetch_background(&mut mapper);
+      self.fetch_sprites(&mut mapper);
+    }
+
+    if self.scanline < SCREEN_HEIGHT as u16 && (1..=SCREEN_WIDTH as u16).contains(&self.dot) {
+      self.render_pixel(&mut mapper);
+    }
+
+    if self.scanline == get_vblank_scanline(self.region) && self.dot == 1 {
+      self.status |= STATUS_VBLANK;
+    }
+
+    if self.scanline == pre_render_scanline && self.dot == 1 {
+      self.status &= !(STATUS_VBLANK | STATUS_SPRITE_0_HIT | STATUS_SPRITE_OVERFLOW);
+    }
+
+    self.advance_dot();
+  }
+
+  fn advance_dot(&mut self) {
+    // Odd NTSC frames skip the last dot of the pre-render line while rendering
+    let pre_render_scanline = get_pre_render_scanline(self.region);
+    let skips_dot = match self.region {
+      Region::Ntsc | Region::Multi => true,
+      Region::Pal | Region::Dendy => false,
+    };
+    if skips_dot
+      && self.scanline == pre_render_scanline
+      && self.dot == DOTS_PER_SCANLINE - 2
+      && self.frame % 2 == 1
+      && self.is_rendering_enabled()
+    {
+      self.dot += 1;
+    }
+
+    self.dot += 1;
+    if self.dot == DOTS_PER_SCANLINE {
+      self.dot = 0;
+      self.scanline += 1;
+      if self.scanline > pre_render_scanline {
+        self.scanline = 0;
+        self.frame += 1;
+      }
+    }
+  }
+
+  fn fetch_background(&mut self, mapper: &mut Option<&mut dyn Mapper>) {
+    let dot = self.dot;
+    let fetching = (1..=256).contains(&dot) || (321..=336).contains(&dot);
+
+    if (2..=257).contains(&dot) || (322..=337).contains(&dot) {
+      self.shift_background();
+    }
+
+    if fetching {
+      match (dot - 1) % 8 {
+        0 => {
+          self.load_background_shifters();
+          let addr = 0x2000 | (self.v & 0x0FFF);
+          self.nametable_byte = self.read(addr, mapper);
+        }
+        2 => {
+          let addr = 0x23C0 | (self.v & 0x0C00) | ((self.v >> 4) & 0x38) | ((self.v >> 2) & 0x07);
+          let shift = ((self.v >> 4) & 0x04) | (self.v & 0x02);
+          self.attribute_byte = (self.read(addr, mapper) >> shift) & 0x03;
+        }
+        4 => {
+          let addr = self.get_background_pattern_addr();
+          self.pattern_lo = self.read(addr, mapper);
+        }
+        6 => {
+          let addr = self.get_background_pattern_addr() + 8;
+          self.pattern_hi = self.read(addr, mapper);
+        }
+        7 => self.increment_coarse_x(),
+        _ => (),
+      }
+    }
+
+    match dot {
+      256 => self.increment_y(),
+      257 => {
+        self.load_background_shifters();
+        // Copy the horizontal position from t
+        self.v = (self.v & 0xFBE0) | (self.t & 0x041F);
+      }
+      // Unused nametable fetches at the end of the line
+      337 | 339 => {
+        let addr = 0x2000 | (self.v & 0x0FFF);
+        self.nametable_byte = self.read(addr, mapper);
+      }
+      _ => (),
+    }
+
+    // Copy the vertical position from t on the pre-render line
+    if self.scanline == get_pre_render_scanline(self.region) && (280..=304).contains(&dot) {
+      self.v = (self.v & 0x841F) | (self.t & 0x7BE0);
+    }
+  }
+
+  fn get_background_pattern_addr(&self) -> u16 {
+    let table = match self.ctrl & CTRL_BACKGROUND_TABLE > 0 {
+      true => 0x1000,
+      false => 0x0000,
+    };
+    let fine_y = (self.v >> 12) & 0x07;
+    table | ((self.nametable_byte as u16) << 4) | fine_y
+  }
+
+  fn shift_background(&mut self) {
+    self.pattern_shift_lo <<= 1;
+    self.pattern_shift_hi <<= 1;
+    self.attribute_shift_lo <<= 1;
+    self.attribute_shift_hi <<= 1;
+  }
+
+  // The next tile enters the low byte of the shift registers
+  fn load_background_shifters(&mut self) {
+    self.pattern_shift_lo = (self.pattern_shift_lo & 0xFF00) | self.pattern_lo as u16;
+    self.pattern_shift_hi = (self.pattern_shift_hi & 0xFF00) | self.pattern_hi as u16;
+    let attribute_lo = match self.attribute_byte & 0x01 > 0 {
+      true => 0xFF,
+      false => 0x00,
+    };
+    let attribute_hi = match self.attribute_byte & 0x02 > 0 {
+      true => 0xFF,
+      false => 0x00,
+    };
+    self.attribute_shift_lo = (self.attribute_shift_lo & 0xFF00) | attribute_lo;
+    self.attribute_shift_hi = (self.attribute_shift_hi & 0xFF00) | attribute_hi;
+  }
+
+  // Source: https://wiki.nesdev.com/w/index.php/PPU_scrolling#Coarse_X_increment
+  fn increment_coarse_x(&mut self) {
+    if self.v & 0x001F == 31 {
+      self.v &= !0x001F;
+      self.v ^= 0x0400;
+    } else {
+      self.v += 1;
+    }
+  }
+
+  // Source: https://wiki.nesdev.com/w/index.php/PPU_scrolling#Y_increment
+  fn increment_y(&mut self) {
+    if self.v & 0x7000 != 0x7000 {
+      self.v += 0x1000;
+      return;
+    }
+
+    self.v &= !0x7000;
+    let mut coarse_y = (self.v & 0x03E0) >> 5;
+    if coarse_y == 29 {
+      coarse_y = 0;
+      self.v ^= 0x0800;
+    } else if coarse_y == 31 {
+      // Attribute rows wrap without switching nametables
+      coarse_y = 0;
+    } else {
+      coarse_y += 1;
+    }
+    self.v = (self.v & !0x03E0) | (coarse_y << 5);
+  }
+
+  fn get_sprite_height(&self) -> u16 {
+    match self.ctrl & CTRL_SPRITE_8X16 > 0 {
+      true => 16,
+      false => 8,
+    }
+  }
+
+  fn is_sprite_on_line(&self, y: u8) -> bool {
+    let row = self.scanline.wrapping_sub(y as u16);
+    row < self.get_sprite_height()
+  }
+
+  // Source: https://wiki.nesdev.com/w/index.php/PPU_sprite_evaluation
+  // Copies the sprites on this line into secondary OAM for the next one
+  fn evaluate_sprites(&mut self) {
+    self.secondary_oam = [0xFF; SPRITES_PER_LINE * 4];
+    self.sprite_count = 0;
+    self.sprite_zero_next = false;
+
+    let mut n = 0;
+    while n < OAM_SIZE / 4 && self.sprite_count < SPRITES_PER_LINE {
+      let y = self.oam[n * 4];
+      if self.is_sprite_on_line(y) {
+        let slot = self.sprite_count * 4;
+        self.secondary_oam[slot..slot + 4].copy_from_slice(&self.oam[n * 4..n * 4 + 4]);
+        self.sprite_zero_next |= n == 0;
+        self.sprite_count += 1;
+      }
+      n += 1;
+    }
+
+    // With secondary OAM full, the hardware increments the byte offset along with the sprite
+    // index, so it compares tile numbers, attributes and X positions as if they were Y
+    let mut m = 0;
+    while n < OAM_SIZE / 4 {
+      let y = self.oam[n * 4 + m];
+      if self.is_sprite_on_line(y) {
+        self.status |= STATUS_SPRITE_OVERFLOW;
+        break;
+      }
+      n += 1;
+      m = (m + 1) & 0x03;
+    }
+  }
+
+  fn fetch_sprites(&mut self, mapper: &mut Option<&mut dyn Mapper>) {
+    let dot = self.dot;
+    if !(257..=320).contains(&dot) {
+      return;
+    }
+
+    self.oam_addr = 0;
+    if dot == 257 {
+      match self.scanline == get_pre_render_scanline(self.region) {
+        true => {
+          self.sprite_count = 0;
+          self.sprite_zero_next = false;
+        }
+        false => self.evaluate_sprites(),
+      }
+    }
+
+    let slot = ((dot - 257) / 8) as usize;
+    match (dot - 257) % 8 {
+      // Garbage nametable fetches
+      0 | 2 => {
+        let addr = 0x2000 | (self.v & 0x0FFF);
+        self.read(addr, mapper);
+      }
+      4 => {
+        let addr = self.get_sprite_pattern_addr(slot);
+        let data = self.read(addr, mapper);
+        self.sprite_patterns_lo[slot] = self.load_sprite_pattern(slot, data);
+      }
+      6 => {
+        let addr = self.get_sprite_pattern_addr(slot) + 8;
+        let data = self.read(addr, mapper);
+        self.sprite_patterns_hi[slot] = self.load_sprite_pattern(slot, data);
+      }
+      7 => {
+        let attributes = self.secondary_oam[slot * 4 + 2];
+        self.sprite_attributes[slot] = attributes;
+        self.sprite_positions[slot] = self.secondary_oam[slot * 4 + 3];
+        if slot == SPRITES_PER_LINE - 1 {
+          self.sprite_line_count = self.sprite_count;
+          self.sprite_zero_line = self.sprite_zero_next;
+        }
+      }
+      _ => (),
+    }
+  }
+
+  fn get_sprite_pattern_addr(&self, slot: usize) -> u16 {
+    let y = self.secondary_oam[slot * 4];
+    let tile = self.secondary_oam[slot * 4 + 1] as u16;
+    let attributes = self.secondary_oam[slot * 4 + 2];
+    let height = self.get_sprite_height();
+
+    let mut row = self.scanline.wrapping_sub(y as u16) & (height - 1);
+    if attributes & ATTRIBUTE_FLIP_VERTICAL > 0 {
+      row = height - 1 - row;
+    }
+
+    match height {
+      // 8x16 sprites take the pattern table from bit 0 of the tile number
+      16 => {
+        let table = (tile & 0x01) * 0x1000;
+        let tile = (tile & 0xFE) + row / 8;
+        table | (tile << 4) | (row & 0x07)
+      }
+      _ => {
+        let table = match self.ctrl & CTRL_SPRITE_TABLE > 0 {
+          true => 0x1000,
+          false => 0x0000,
+        };
+        table | (tile << 4) | row
+      }
+    }
+  }
+
+  // Unused slots fetch tile 0xFF but stay transparent, flipped sprites are stored mirrored
+  fn load_sprite_pattern(&self, slot: usize, data: u8) -> u8 {
+    if slot >= self.sprite_count {
+      return 0;
+    }
+    match self.secondary_oam[slot * 4 + 2] & ATTRIBUTE_FLIP_HORIZONTAL > 0 {
+      true => data.reverse_bits(),
+      false => data,
+    }
+  }
+
+  // Returns the palette index of the frontmost opaque sprite, whether it is behind the
+  // background, and whether it is sprite 0
+  fn get_sprite_pixel(&self, x: usize) -> Option<(u8, bool, bool)> {
+    let visible = self.mask & MASK_SPRITES > 0 && (x >= 8 || self.mask & MASK_SPRITES_LEFT > 0);
+    if !visible {
+      return None;
+    }
+
+    for slot in 0..self.sprite_line_count {
+      let column = x.wrapping_sub(self.sprite_positions[slot] as usize);
+      if column >= 8 {
+        continue;
+      }
+      let bit = 0x80 >> column;
+      let pixel = ((self.sprite_patterns_hi[slot] & bit > 0) as u8) << 1
+        | (self.sprite_patterns_lo[slot] & bit > 0) as u8;
+      if pixel == 0 {
+        continue;
+      }
+      let attributes = self.sprite_attributes[slot];
+      let color = 0x10 | (attributes & ATTRIBUTE_PALETTE) << 2 | pixel;
+      let behind = attributes & ATTRIBUTE_BEHIND_BACKGROUND > 0;
+      return Some((color, behind, slot == 0 && self.sprite_zero_line));
+    }
+    None
+  }
+
+  fn get_background_pixel(&self, x: usize) -> u8 {
+    let visible =
+      self.mask & MASK_BACKGROUND > 0 && (x >= 8 || self.mask & MASK_BACKGROUND_LEFT > 0);
+    if !visible {
+      return 0;
+    }
+
+    let bit = 0x8000 >> self.x;
+    let pixel =
+      ((self.pattern_shift_hi & bit > 0) as u8) << 1 | (self.pattern_shift_lo & bit > 0) as u8;
+    if pixel == 0 {
+      return 0;
+    }
+    let palette =
+      ((self.attribute_shift_hi & bit > 0) as u8) << 1 | (self.attribute_shift_lo & bit > 0) as u8;
+    palette << 2 | pixel
+  }
+
+  // Combines background and sprites, and detects sprite 0 hits
+  fn get_pixel(&mut self, x: usize) -> u8 {
+    let background = self.get_background_pixel(x);
+    let sprite = match self.get_sprite_pixel(x) {
+      Some(sprite) => sprite,
+      None => return background,
+    };
+
+    let (color, behind, is_sprite_zero) = sprite;
+    if background == 0 {
+      return color;
+    }
+    // Sprite 0 hits never happen at x=255
+    if is_sprite_zero && x != SCREEN_WIDTH - 1 {
+      self.status |= STATUS_SPRITE_0_HIT;
+    }
+    match behind {
+      true => background,
+      false => color,
+    }
+  }
+
+  fn render_pixel(&mut self, mapper: &mut Option<&mut dyn Mapper>) {
+    let x = (self.dot - 1) as usize;
+    let y = self.scanline as usize;
+
+    let color = match self.is_rendering_enabled() {
+      true => self.get_pixel(x) as u16,
+      // With rendering off, the backdrop shows unless v points into the palette
+      false => match self.v & 0x3F00 == PALETTE_ADDR {
+        true => self.v & 0x1F,
+        false => 0,
+      },
+    };
+    self.framebuffer[y * SCREEN_WIDTH + x] = self.read(PALETTE_ADDR | color, mapper);
+  }
+}
+
+fn get_mirroring(mapper: &Option<&mut dyn Mapper>) -> Mirroring {
+  match mapper {
+    Some(mapper) => mapper.get_mirroring(),
+    None => Mirroring::Horizontal,
+  }
+}
+
+// Source: https://wiki.nesdev.com/w/index.php/Mirroring#Nametable_Mirroring
+fn get_nametable_offset(addr: u16, mirroring: Mirroring) -> usize {
+  let addr = (addr as usize - 0x2000) & 0x0FFF;
+  let table = addr / NAMETABLE_SIZE;
+  let physical = match mirroring {
+    Mirroring::Horizontal => table >> 1,
+    Mirroring::Vertical => table & 0x01,
+    Mirroring::SingleScreenLower => 0,
+    Mirroring::SingleScreenUpper => 1,
+    Mirroring::FourScreen => table,
+  };
+  physical * NAMETABLE_SIZE + (addr & (NAMETABLE_SIZE - 1))
+}
+
+// 0x3F10, 0x3F14, 0x3F18 and 0x3F1C mirror the backdrop entries below them
+fn get_palette_offset(addr: u16) -> usize {
+  let offset = addr as usize & (PALETTE_SIZE - 1);
+  match offset & 0x13 {
+    0x10 => offset & 0x0F,
+    _ => offset,
+  }
+}
+
+// PAL starts vblank after one post-render line like NTSC and makes it 70 lines long, Dendy
+// keeps NTSC's 20 lines of vblank after 51 post-render lines
+fn get_vblank_scanline(region: Region) -> u16 {
+  match region {
+    Region::Ntsc | Region::Multi | Region::Pal => 241,
+    Region::Dendy => 291,
+  }
+}
+
+fn get_pre_render_scanline(region: Region) -> u16 {
+  match region {
+    Region::Ntsc | Region::Multi => NTSC_PRE_RENDER_SCANLINE,
+    Region::Pal | Region::Dendy => PAL_PRE_RENDER_SCANLINE,
+  }
+}
@@ -0,0 +1,58 @@
+#[cfg(test)]
+use std::env;
+
+#[cfg(test)]
+use std::fs;
+
+#[cfg(test)]
+use ppu::SCREEN_WIDTH;
+
+// One character per palette index
+#[cfg(test)]
+const SYMBOLS: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz+/";
+
+#[cfg(test)]
+fn encode(framebuffer: &[u8]) -> String {
+  let mut image = String::new();
+  for row in framebuffer.chunks(SCREEN_WIDTH) {
+    image.extend(
+      row
+        .iter()
+        .map(|color| SYMBOLS[(color & 0x3F) as usize] as char),
+    );
+    image.push('\n');
+  }
+  image
+}
+
+// Compares a frame with ppu/tests/golden/<name>.txt, which holds one line per scanline and
+// one character per pixel. Run the tests with UPDATE_GOLDEN=1 to rewrite the images
+#[cfg(test)]
+pub fn assert_golden(name: &str, framebuffer: &[u8]) {
+  let path = format!(
+    "{}/ppu/tests/golden/{}.txt",
+    env!("CARGO_MANIFEST_DIR"),
+    name
+  );
+  let actual = encode(framebuffer);
+  if env::var("UPDATE_GOLDEN").is_ok() {
+    fs::write(&path, &actual).unwrap();
+    return;
+  }
+
+  let expected = fs::read_to_string(&path)
+    .unwrap_or_else(|_| panic!("Missing golden image {}, run with UPDATE_GOLDEN=1", path));
+  for (y, (expected, actual)) in expected.lines().zip(actual.lines()).enumerate() {
+    let column = expected
+      .chars()
+      .zip(actual.chars())
+      .position(|(expected, actual)| expected != actual);
+    if let Some(x) = column {
+      panic!(
+        "{} differs from its golden image at x={} y={}\nexpected: {}\nactual:   {}",
+        name, x, y, expected, actual
+      );
+    }
+  }
+  assert_eq!(expected.lines().count(), actual.lines().count());
+}
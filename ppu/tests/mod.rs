@@ -0,0 +1,430 @@
+mod golden;
+mod sprites;
+
+#[cfg(test)]
+use cartridge::{Mirroring, Region};
+
+#[cfg(test)]
+use mapper::tests::build_cartridge;
+
+#[cfg(test)]
+use mapper::{from_cartridge, Mapper};
+
+#[cfg(test)]
+use ppu::{get_nametable_offset, Ppu, SCREEN_WIDTH};
+
+// NROM board with CHR RAM, so tests can write their own patterns
+#[cfg(test)]
+fn setup() -> (Ppu, Box<dyn Mapper>) {
+  (
+    Ppu::new(),
+    from_cartridge(build_cartridge(0, 1, 0)).unwrap(),
+  )
+}
+
+#[cfg(test)]
+fn write(ppu: &mut Ppu, mapper: &mut Box<dyn Mapper>, addr: u16, data: u8) {
+  ppu.write_register(addr, data, Some(&mut **mapper));
+}
+
+#[cfg(test)]
+fn read(ppu: &mut Ppu, mapper: &mut Box<dyn Mapper>, addr: u16) -> u8 {
+  ppu.read_register(addr, Some(&mut **mapper))
+}
+
+#[cfg(test)]
+fn set_vram_addr(ppu: &mut Ppu, mapper: &mut Box<dyn Mapper>, addr: u16) {
+  write(ppu, mapper, 0x2006, (addr >> 8) as u8);
+  write(ppu, mapper, 0x2006, addr as u8);
+}
+
+#[cfg(test)]
+fn run_frame(ppu: &mut Ppu, mapper: &mut Box<dyn Mapper>) {
+  let frame = ppu.get_frame();
+  while ppu.get_frame() == frame {
+    ppu.clock(Some(&mut **mapper));
+  }
+}
+
+#[cfg(test)]
+fn run_until(ppu: &mut Ppu, mapper: &mut Box<dyn Mapper>, scanline: u16, dot: u16) {
+  while ppu.get_scanline() != scanline || ppu.get_dot() != dot {
+    ppu.clock(Some(&mut **mapper));
+  }
+}
+
+#[cfg(test)]
+mod registers {
+  use super::*;
+  #[test]
+  fn ctrl_selects_nametable_in_t() {
+    let (mut ppu, mut mapper) = setup();
+    write(&mut ppu, &mut mapper, 0x2000, 0x03);
+    assert_eq!(ppu.get_temp_vram_addr(), 0x0C00);
+  }
+  #[test]
+  fn scroll_writes_t_and_fine_x() {
+    let (mut ppu, mut mapper) = setup();
+    write(&mut ppu, &mut mapper, 0x2005, 0x7D);
+    assert_eq!(ppu.get_temp_vram_addr(), 0x000F);
+    assert_eq!(ppu.get_fine_x(), 0x05);
+    write(&mut ppu, &mut mapper, 0x2005, 0x5E);
+    assert_eq!(ppu.get_temp_vram_addr(), 0x616F);
+  }
+  #[test]
+  fn second_addr_write_copies_t_to_v() {
+    let (mut ppu, mut mapper) = setup();
+    write(&mut ppu, &mut mapper, 0x2006, 0x3D);
+    assert_eq!(ppu.get_temp_vram_addr(), 0x3D00);
+    assert_eq!(ppu.get_vram_addr(), 0x0000);
+    write(&mut ppu, &mut mapper, 0x2006, 0xF0);
+    assert_eq!(ppu.get_vram_addr(), 0x3DF0);
+  }
+  #[test]
+  fn addr_first_write_clears_bit_14() {
+    let (mut ppu, mut mapper) = setup();
+    set_vram_addr(&mut ppu, &mut mapper, 0xFFFF);
+    assert_eq!(ppu.get_vram_addr(), 0x3FFF);
+  }
+  #[test]
+  fn status_read_resets_write_toggle() {
+    let (mut ppu, mut mapper) = setup();
+    write(&mut ppu, &mut mapper, 0x2006, 0x21);
+    read(&mut ppu, &mut mapper, 0x2002);
+    set_vram_addr(&mut ppu, &mut mapper, 0x2345);
+    assert_eq!(ppu.get_vram_addr(), 0x2345);
+  }
+  #[test]
+  fn data_reads_are_buffered() {
+    let (mut ppu, mut mapper) = setup();
+    set_vram_addr(&mut ppu, &mut mapper, 0x2000);
+    write(&mut ppu, &mut mapper, 0x2007, 0xAA);
+    write(&mut ppu, &mut mapper, 0x2007, 0xBB);
+    set_vram_addr(&mut ppu, &mut mapper, 0x2000);
+    assert_eq!(read(&mut ppu, &mut mapper, 0x2007), 0x00);
+    assert_eq!(read(&mut ppu, &mut mapper, 0x2007), 0xAA);
+    assert_eq!(read(&mut ppu, &mut mapper, 0x2007), 0xBB);
+  }
+  #[test]
+  fn palette_reads_are_immediate() {
+    let (mut ppu, mut mapper) = setup();
+    set_vram_addr(&mut ppu, &mut mapper, 0x2F01);
+    write(&mut ppu, &mut mapper, 0x2007, 0x55);
+    set_vram_addr(&mut ppu, &mut mapper, 0x3F01);
+    write(&mut ppu, &mut mapper, 0x2007, 0x21);
+    set_vram_addr(&mut ppu, &mut mapper, 0x3F01);
+    assert_eq!(read(&mut ppu, &mut mapper, 0x2007), 0x21);
+    // The buffer holds the nametable byte hidden under the palette
+    assert_eq!(ppu.peek_register(0x2007), 0x55);
+  }
+  #[test]
+  fn backdrop_entries_are_mirrored() {
+    let (mut ppu, mut mapper) = setup();
+    for entry in [0x10, 0x14, 0x18, 0x1C].iter() {
+      set_vram_addr(&mut ppu, &mut mapper, 0x3F00 + entry);
+      write(&mut ppu, &mut mapper, 0x2007, *entry as u8);
+      set_vram_addr(&mut ppu, &mut mapper, 0x3F00 + (entry & 0x0F));
+      assert_eq!(read(&mut ppu, &mut mapper, 0x2007), *entry as u8);
+    }
+  }
+  #[test]
+  fn data_increments_by_32() {
+    let (mut ppu, mut mapper) = setup();
+    write(&mut ppu, &mut mapper, 0x2000, 0x04);
+    set_vram_addr(&mut ppu, &mut mapper, 0x2000);
+    write(&mut ppu, &mut mapper, 0x2007, 0x00);
+    assert_eq!(ppu.get_vram_addr(), 0x2020);
+    read(&mut ppu, &mut mapper, 0x2007);
+    assert_eq!(ppu.get_vram_addr(), 0x2040);
+  }
+  #[test]
+  fn data_reaches_pattern_tables() {
+    let (mut ppu, mut mapper) = setup();
+    set_vram_addr(&mut ppu, &mut mapper, 0x1234);
+    write(&mut ppu, &mut mapper, 0x2007, 0x99);
+    assert_eq!(mapper.ppu_read(0x1234), 0x99);
+  }
+  #[test]
+  fn oam_data_writes_increment_oam_addr() {
+    let (mut ppu, mut mapper) = setup();
+    write(&mut ppu, &mut mapper, 0x2003, 0x10);
+    for data in 0..4 {
+      write(&mut ppu, &mut mapper, 0x2004, 0xF0 | data);
+    }
+    assert_eq!(&ppu.get_oam()[0x10..0x14], &[0xF0, 0xF1, 0xF2, 0xF3]);
+    write(&mut ppu, &mut mapper, 0x2003, 0x12);
+    // Attribute bits 2-4 are not implemented
+    assert_eq!(read(&mut ppu, &mut mapper, 0x2004), 0xE2);
+  }
+  #[test]
+  fn write_only_registers_read_io_latch() {
+    let (mut ppu, mut mapper) = setup();
+    write(&mut ppu, &mut mapper, 0x2001, 0xA5);
+    assert_eq!(read(&mut ppu, &mut mapper, 0x2000), 0xA5);
+    assert_eq!(read(&mut ppu, &mut mapper, 0x2002) & 0x1F, 0x05);
+  }
+}
+
+#[cfg(test)]
+mod nametables {
+  use super::*;
+  #[test]
+  fn horizontal_mirroring() {
+    let mirroring = Mirroring::Horizontal;
+    assert_eq!(get_nametable_offset(0x2000, mirroring), 0x000);
+    assert_eq!(get_nametable_offset(0x2400, mirroring), 0x000);
+    assert_eq!(get_nametable_offset(0x2800, mirroring), 0x400);
+    assert_eq!(get_nametable_offset(0x2FFF, mirroring), 0x7FF);
+  }
+  #[test]
+  fn vertical_mirroring() {
+    let mirroring = Mirroring::Vertical;
+    assert_eq!(get_nametable_offset(0x2000, mirroring), 0x000);
+    assert_eq!(get_nametable_offset(0x2400, mirroring), 0x400);
+    assert_eq!(get_nametable_offset(0x2800, mirroring), 0x000);
+    assert_eq!(get_nametable_offset(0x2FFF, mirroring), 0x7FF);
+  }
+  #[test]
+  fn single_screen_mirroring() {
+    for addr in [0x2000, 0x2400, 0x2800, 0x2C00].iter() {
+      assert_eq!(
+        get_nametable_offset(*addr, Mirroring::SingleScreenLower),
+        0x000
+      );
+      assert_eq!(
+        get_nametable_offset(*addr, Mirroring::SingleScreenUpper),
+        0x400
+      );
+    }
+  }
+  #[test]
+  fn four_screen_mirroring() {
+    let mirroring = Mirroring::FourScreen;
+    assert_eq!(get_nametable_offset(0x2C00, mirroring), 0xC00);
+  }
+  #[test]
+  fn upper_range_mirrors_0x2000() {
+    assert_eq!(get_nametable_offset(0x3123, Mirroring::Vertical), 0x123);
+    assert_eq!(get_nametable_offset(0x3EFF, Mirroring::Vertical), 0x6FF);
+  }
+  #[test]
+  fn follow_mapper_mirroring() {
+    let (mut ppu, mut mapper) = setup();
+    // build_cartridge uses vertical mirroring
+    set_vram_addr(&mut ppu, &mut mapper, 0x2801);
+    write(&mut ppu, &mut mapper, 0x2007, 0x77);
+    set_vram_addr(&mut ppu, &mut mapper, 0x2001);
+    read(&mut ppu, &mut mapper, 0x2007);
+    assert_eq!(read(&mut ppu, &mut mapper, 0x2007), 0x77);
+  }
+}
+
+#[cfg(test)]
+mod timing {
+  use super::*;
+  #[test]
+  fn vblank_starts_at_scanline_241_dot_1() {
+    let (mut ppu, mut mapper) = setup();
+    run_until(&mut ppu, &mut mapper, 241, 1);
+    assert_eq!(ppu.peek_register(0x2002) & 0x80, 0x00);
+    ppu.clock(Some(&mut *mapper));
+    assert_eq!(ppu.peek_register(0x2002) & 0x80, 0x80);
+  }
+  #[test]
+  fn status_read_clears_vblank() {
+    let (mut ppu, mut mapper) = setup();
+    run_until(&mut ppu, &mut mapper, 242, 0);
+    assert_eq!(read(&mut ppu, &mut mapper, 0x2002) & 0x80, 0x80);
+    assert_eq!(read(&mut ppu, &mut mapper, 0x2002) & 0x80, 0x00);
+  }
+  #[test]
+  fn pre_render_line_clears_vblank() {
+    let (mut ppu, mut mapper) = setup();
+    run_until(&mut ppu, &mut mapper, 261, 2);
+    assert_eq!(ppu.peek_register(0x2002) & 0x80, 0x00);
+  }
+  #[test]
+  fn nmi_raised_at_vblank_when_enabled() {
+    let (mut ppu, mut mapper) = setup();
+    write(&mut ppu, &mut mapper, 0x2000, 0x80);
+    run_until(&mut ppu, &mut mapper, 241, 1);
+    assert!(!ppu.is_nmi_asserted());
+    ppu.clock(Some(&mut *mapper));
+    assert!(ppu.is_nmi_asserted());
+    // Reading PPUSTATUS clears vblank and releases the line
+    ppu.read_register(0x2002, Some(&mut *mapper));
+    assert!(!ppu.is_nmi_asserted());
+  }
+  #[test]
+  fn no_nmi_when_disabled() {
+    let (mut ppu, mut mapper) = setup();
+    run_until(&mut ppu, &mut mapper, 250, 0);
+    assert!(!ppu.is_nmi_asserted());
+  }
+  #[test]
+  fn enabling_nmi_during_vblank_raises_nmi() {
+    let (mut ppu, mut mapper) = setup();
+    run_until(&mut ppu, &mut mapper, 250, 0);
+    write(&mut ppu, &mut mapper, 0x2000, 0x80);
+    assert!(ppu.is_nmi_asserted());
+    write(&mut ppu, &mut mapper, 0x2000, 0x00);
+    assert!(!ppu.is_nmi_asserted());
+  }
+  #[test]
+  fn frame_is_341_by_262_dots() {
+    let (mut ppu, mut mapper) = setup();
+    let mut dots = 0;
+    while ppu.get_frame() == 0 {
+      ppu.clock(Some(&mut *mapper));
+      dots += 1;
+    }
+    assert_eq!(dots, 341 * 262);
+  }
+  #[test]
+  fn odd_frames_skip_a_dot_while_rendering() {
+    let (mut ppu, mut mapper) = setup();
+    write(&mut ppu, &mut mapper, 0x2001, 0x08);
+    run_frame(&mut ppu, &mut mapper);
+    let mut dots = 0;
+    while ppu.get_frame() == 1 {
+      ppu.clock(Some(&mut *mapper));
+      dots += 1;
+    }
+    assert_eq!(dots, 341 * 262 - 1);
+  }
+  #[test]
+  fn pal_frame_is_341_by_312_dots_without_skip() {
+    let (mut ppu, mut mapper) = setup();
+    ppu.set_region(Region::Pal);
+    write(&mut ppu, &mut mapper, 0x2001, 0x08);
+    run_frame(&mut ppu, &mut mapper);
+    let mut dots = 0;
+    while ppu.get_frame() == 1 {
+      ppu.clock(Some(&mut *mapper));
+      dots += 1;
+    }
+    assert_eq!(dots, 341 * 312);
+  }
+  #[test]
+  fn pal_vblank_lasts_70_lines() {
+    let (mut ppu, mut mapper) = setup();
+    ppu.set_region(Region::Pal);
+    run_until(&mut ppu, &mut mapper, 241, 2);
+    assert_eq!(ppu.peek_register(0x2002) & 0x80, 0x80);
+    run_until(&mut ppu, &mut mapper, 310, 340);
+    assert_eq!(ppu.peek_register(0x2002) & 0x80, 0x80);
+    run_until(&mut ppu, &mut mapper, 311, 2);
+    assert_eq!(ppu.peek_register(0x2002) & 0x80, 0x00);
+  }
+  #[test]
+  fn dendy_vblank_starts_after_51_lines() {
+    let (mut ppu, mut mapper) = setup();
+    ppu.set_region(Region::Dendy);
+    run_until(&mut ppu, &mut mapper, 290, 340);
+    assert_eq!(ppu.peek_register(0x2002) & 0x80, 0x00);
+    run_until(&mut ppu, &mut mapper, 291, 2);
+    assert_eq!(ppu.peek_register(0x2002) & 0x80, 0x80);
+  }
+}
+
+// Tile 1 is solid color 1, placed top left with palette 1. Backdrop 0x0F, palette 1 color 1 is 0x16
+#[cfg(test)]
+fn setup_background() -> (Ppu, Box<dyn Mapper>) {
+  let (mut ppu, mut mapper) = setup();
+  for row in 0..8 {
+    mapper.ppu_write(0x0010 + row, 0xFF);
+  }
+  set_vram_addr(&mut ppu, &mut mapper, 0x2000);
+  write(&mut ppu, &mut mapper, 0x2007, 0x01);
+  set_vram_addr(&mut ppu, &mut mapper, 0x23C0);
+  write(&mut ppu, &mut mapper, 0x2007, 0x01);
+  set_vram_addr(&mut ppu, &mut mapper, 0x3F00);
+  write(&mut ppu, &mut mapper, 0x2007, 0x0F);
+  set_vram_addr(&mut ppu, &mut mapper, 0x3F05);
+  write(&mut ppu, &mut mapper, 0x2007, 0x16);
+  // Scroll back to the top left of the first nametable
+  write(&mut ppu, &mut mapper, 0x2000, 0x00);
+  write(&mut ppu, &mut mapper, 0x2005, 0x00);
+  write(&mut ppu, &mut mapper, 0x2005, 0x00);
+  (ppu, mapper)
+}
+
+#[cfg(test)]
+fn get_row(ppu: &Ppu, y: usize) -> &[u8] {
+  &ppu.get_framebuffer()[y * SCREEN_WIDTH..(y + 1) * SCREEN_WIDTH]
+}
+
+#[cfg(test)]
+mod background {
+  use super::*;
+  #[test]
+  fn renders_tile_with_attribute_palette() {
+    let (mut ppu, mut mapper) = setup_background();
+    write(&mut ppu, &mut mapper, 0x2001, 0x0A);
+    run_frame(&mut ppu, &mut mapper);
+    run_frame(&mut ppu, &mut mapper);
+    for y in 0..8 {
+      assert_eq!(
+        &get_row(&ppu, y)[0..9],
+        &[0x16, 0x16, 0x16, 0x16, 0x16, 0x16, 0x16, 0x16, 0x0F]
+      );
+    }
+    assert!(get_row(&ppu, 8).iter().all(|color| *color == 0x0F));
+    assert!(get_row(&ppu, 239).iter().all(|color| *color == 0x0F));
+  }
+  #[test]
+  fn fine_x_scroll_shifts_pixels() {
+    let (mut ppu, mut mapper) = setup_background();
+    write(&mut ppu, &mut mapper, 0x2005, 0x03);
+    write(&mut ppu, &mut mapper, 0x2005, 0x00);
+    write(&mut ppu, &mut mapper, 0x2001, 0x0A);
+    run_frame(&mut ppu, &mut mapper);
+    run_frame(&mut ppu, &mut mapper);
+    assert_eq!(
+      &get_row(&ppu, 0)[0..6],
+      &[0x16, 0x16, 0x16, 0x16, 0x16, 0x0F]
+    );
+  }
+  #[test]
+  fn coarse_y_scroll_moves_rows() {
+    let (mut ppu, mut mapper) = setup_background();
+    write(&mut ppu, &mut mapper, 0x2005, 0x00);
+    write(&mut ppu, &mut mapper, 0x2005, 0x04);
+    write(&mut ppu, &mut mapper, 0x2001, 0x0A);
+    run_frame(&mut ppu, &mut mapper);
+    run_frame(&mut ppu, &mut mapper);
+    assert_eq!(get_row(&ppu, 3)[0], 0x16);
+    assert_eq!(get_row(&ppu, 4)[0], 0x0F);
+  }
+  #[test]
+  fn left_column_can_be_hidden() {
+    let (mut ppu, mut mapper) = setup_background();
+    write(&mut ppu, &mut mapper, 0x2001, 0x08);
+    run_frame(&mut ppu, &mut mapper);
+    run_frame(&mut ppu, &mut mapper);
+    assert!(get_row(&ppu, 0)[0..8].iter().all(|color| *color == 0x0F));
+  }
+  #[test]
+  fn disabled_rendering_shows_backdrop() {
+    let (mut ppu, mut mapper) = setup_background();
+    set_vram_addr(&mut ppu, &mut mapper, 0x2000);
+    run_frame(&mut ppu, &mut mapper);
+    assert!(ppu.get_framebuffer().iter().all(|color| *color == 0x0F));
+  }
+  #[test]
+  fn disabled_rendering_shows_palette_entry_at_v() {
+    let (mut ppu, mut mapper) = setup_background();
+    set_vram_addr(&mut ppu, &mut mapper, 0x3F05);
+    run_frame(&mut ppu, &mut mapper);
+    assert!(ppu.get_framebuffer().iter().all(|color| *color == 0x16));
+  }
+  #[test]
+  fn greyscale_masks_colors() {
+    let (mut ppu, mut mapper) = setup_background();
+    write(&mut ppu, &mut mapper, 0x2001, 0x0B);
+    run_frame(&mut ppu, &mut mapper);
+    run_frame(&mut ppu, &mut mapper);
+    assert_eq!(get_row(&ppu, 0)[0], 0x10);
+    assert_eq!(get_row(&ppu, 0)[8], 0x00);
+  }
+}
@@ -0,0 +1,372 @@
+#[cfg(test)]
+use mapper::Mapper;
+
+#[cfg(test)]
+use ppu::tests::golden::assert_golden;
+
+#[cfg(test)]
+use ppu::tests::{get_row, read, run_frame, run_until, set_vram_addr, setup, write};
+
+#[cfg(test)]
+use ppu::Ppu;
+
+#[cfg(test)]
+const SOLID_TILE: u8 = 0x01;
+
+// Lower left triangle, so every flip is visible. Colors 1 and 3 in the top half, 1 below
+#[cfg(test)]
+const TRIANGLE_TILE: u8 = 0x02;
+
+// Pair of tiles for 8x16 sprites: solid color 2 on top, the triangle below
+#[cfg(test)]
+const TALL_TILE: u8 = 0x04;
+
+#[cfg(test)]
+fn write_tile(mapper: &mut Box<dyn Mapper>, addr: u16, lo: [u8; 8], hi: [u8; 8]) {
+  for row in 0..8 {
+    mapper.ppu_write(addr + row as u16, lo[row]);
+    mapper.ppu_write(addr + 8 + row as u16, hi[row]);
+  }
+}
+
+#[cfg(test)]
+fn write_palette(ppu: &mut Ppu, mapper: &mut Box<dyn Mapper>, addr: u16, colors: &[u8]) {
+  set_vram_addr(ppu, mapper, addr);
+  for color in colors.iter() {
+    write(ppu, mapper, 0x2007, *color);
+  }
+}
+
+// Writes sprites as (y, tile, attributes, x) starting at OAM entry 0
+#[cfg(test)]
+fn write_sprites(ppu: &mut Ppu, mapper: &mut Box<dyn Mapper>, sprites: &[(u8, u8, u8, u8)]) {
+  write(ppu, mapper, 0x2003, 0x00);
+  for &(y, tile, attributes, x) in sprites.iter() {
+    for data in [y, tile, attributes, x].iter() {
+      write(ppu, mapper, 0x2004, *data);
+    }
+  }
+  // Park every other sprite below the screen
+  for _ in sprites.len() * 4..256 {
+    write(ppu, mapper, 0x2004, 0xFF);
+  }
+}
+
+// The same patterns in both tables, a block of solid background tiles at tiles (8, 4) to
+// (15, 11), backdrop 0x0F, background color 0x00 and two sprite palettes
+#[cfg(test)]
+fn setup_scene() -> (Ppu, Box<dyn Mapper>) {
+  let (mut ppu, mut mapper) = setup();
+  let triangle = [0x80, 0xC0, 0xE0, 0xF0, 0xF8, 0xFC, 0xFE, 0xFF];
+  for table in [0x0000, 0x1000].iter() {
+    write_tile(
+      &mut mapper,
+      table + 0x10 * SOLID_TILE as u16,
+      [0xFF; 8],
+      [0x00; 8],
+    );
+    write_tile(
+      &mut mapper,
+      table + 0x10 * TRIANGLE_TILE as u16,
+      triangle,
+      [0x80, 0xC0, 0xE0, 0xF0, 0, 0, 0, 0],
+    );
+    write_tile(
+      &mut mapper,
+      table + 0x10 * TALL_TILE as u16,
+      [0x00; 8],
+      [0xFF; 8],
+    );
+    write_tile(
+      &mut mapper,
+      table + 0x10 * (TALL_TILE as u16 + 1),
+      triangle,
+      [0x00; 8],
+    );
+  }
+
+  for row in 4..12 {
+    set_vram_addr(&mut ppu, &mut mapper, 0x2000 + row * 32 + 8);
+    for _ in 8..16 {
+      write(&mut ppu, &mut mapper, 0x2007, SOLID_TILE);
+    }
+  }
+  write_palette(&mut ppu, &mut mapper, 0x3F00, &[0x0F, 0x00]);
+  write_palette(
+    &mut ppu,
+    &mut mapper,
+    0x3F11,
+    &[0x16, 0x27, 0x18, 0x0F, 0x1A, 0x2A, 0x3A],
+  );
+
+  write(&mut ppu, &mut mapper, 0x2000, 0x00);
+  set_vram_addr(&mut ppu, &mut mapper, 0x2000);
+  (ppu, mapper)
+}
+
+#[cfg(test)]
+fn render(ppu: &mut Ppu, mapper: &mut Box<dyn Mapper>, ctrl: u8, mask: u8) {
+  write(ppu, mapper, 0x2000, ctrl);
+  write(ppu, mapper, 0x2001, mask);
+  run_frame(ppu, mapper);
+  run_frame(ppu, mapper);
+}
+
+#[cfg(test)]
+mod golden_images {
+  use super::*;
+  #[test]
+  fn sprites_8x8() {
+    let (mut ppu, mut mapper) = setup_scene();
+    write_sprites(
+      &mut ppu,
+      &mut mapper,
+      &[
+        (40, TRIANGLE_TILE, 0x00, 16),
+        (40, TRIANGLE_TILE, 0x40, 32),
+        (40, TRIANGLE_TILE, 0x80, 48),
+        (40, TRIANGLE_TILE, 0xC1, 200),
+        // In front of and behind the background block
+        (48, TRIANGLE_TILE, 0x00, 68),
+        (64, TRIANGLE_TILE, 0x21, 100),
+        // Overlapping, the lower OAM index wins
+        (120, SOLID_TILE, 0x01, 120),
+        (124, TRIANGLE_TILE, 0x00, 124),
+        // Partly off the right edge
+        (180, TRIANGLE_TILE, 0x00, 252),
+      ],
+    );
+    render(&mut ppu, &mut mapper, 0x08, 0x1E);
+    assert_golden("sprites_8x8", ppu.get_framebuffer());
+  }
+  #[test]
+  fn sprites_8x16() {
+    let (mut ppu, mut mapper) = setup_scene();
+    write_sprites(
+      &mut ppu,
+      &mut mapper,
+      &[
+        (40, TALL_TILE, 0x00, 16),
+        (40, TALL_TILE, 0x80, 32),
+        // Odd tile numbers use the pattern table at 0x1000
+        (40, TALL_TILE | 0x01, 0x41, 48),
+        (56, TALL_TILE, 0x20, 72),
+      ],
+    );
+    render(&mut ppu, &mut mapper, 0x20, 0x1E);
+    assert_golden("sprites_8x16", ppu.get_framebuffer());
+  }
+  #[test]
+  fn only_eight_sprites_per_line() {
+    let (mut ppu, mut mapper) = setup_scene();
+    let sprites: Vec<(u8, u8, u8, u8)> = (0..10)
+      .map(|sprite| (160 + sprite / 5, SOLID_TILE, sprite & 0x01, 24 * sprite))
+      .collect();
+    write_sprites(&mut ppu, &mut mapper, &sprites);
+    render(&mut ppu, &mut mapper, 0x00, 0x1E);
+    assert_golden("sprites_overflow", ppu.get_framebuffer());
+  }
+  #[test]
+  fn left_column_clipping() {
+    let (mut ppu, mut mapper) = setup_scene();
+    write_sprites(
+      &mut ppu,
+      &mut mapper,
+      &[(20, SOLID_TILE, 0x00, 0), (40, SOLID_TILE, 0x00, 4)],
+    );
+    render(&mut ppu, &mut mapper, 0x00, 0x1A);
+    assert_golden("sprites_left_clip", ppu.get_framebuffer());
+  }
+}
+
+#[cfg(test)]
+mod rendering {
+  use super::*;
+  #[test]
+  fn sprite_appears_one_line_below_y() {
+    let (mut ppu, mut mapper) = setup_scene();
+    write_sprites(&mut ppu, &mut mapper, &[(30, SOLID_TILE, 0x00, 0x10)]);
+    render(&mut ppu, &mut mapper, 0x00, 0x1E);
+    assert_eq!(get_row(&ppu, 30)[0x10], 0x0F);
+    assert_eq!(
+      &get_row(&ppu, 31)[0x0F..0x19],
+      &[0x0F, 0x16, 0x16, 0x16, 0x16, 0x16, 0x16, 0x16, 0x16, 0x0F]
+    );
+    assert_eq!(get_row(&ppu, 38)[0x10], 0x16);
+    assert_eq!(get_row(&ppu, 39)[0x10], 0x0F);
+  }
+  #[test]
+  fn horizontal_flip() {
+    let (mut ppu, mut mapper) = setup_scene();
+    write_sprites(&mut ppu, &mut mapper, &[(30, TRIANGLE_TILE, 0x40, 0x10)]);
+    render(&mut ppu, &mut mapper, 0x00, 0x1E);
+    assert_eq!(
+      &get_row(&ppu, 31)[0x10..0x18],
+      &[0x0F, 0x0F, 0x0F, 0x0F, 0x0F, 0x0F, 0x0F, 0x18]
+    );
+  }
+  #[test]
+  fn vertical_flip() {
+    let (mut ppu, mut mapper) = setup_scene();
+    write_sprites(&mut ppu, &mut mapper, &[(30, TRIANGLE_TILE, 0x80, 0x10)]);
+    render(&mut ppu, &mut mapper, 0x00, 0x1E);
+    assert!(get_row(&ppu, 31)[0x10..0x18]
+      .iter()
+      .all(|color| *color == 0x16));
+    assert_eq!(&get_row(&ppu, 38)[0x10..0x12], &[0x18, 0x0F]);
+  }
+  #[test]
+  fn background_hides_sprite_behind_it() {
+    let (mut ppu, mut mapper) = setup_scene();
+    write_sprites(&mut ppu, &mut mapper, &[(40, SOLID_TILE, 0x20, 60)]);
+    render(&mut ppu, &mut mapper, 0x00, 0x1E);
+    // Only the part left of the background block at x=64 shows
+    assert_eq!(
+      &get_row(&ppu, 41)[60..66],
+      &[0x16, 0x16, 0x16, 0x16, 0x00, 0x00]
+    );
+  }
+  #[test]
+  fn sprites_disabled() {
+    let (mut ppu, mut mapper) = setup_scene();
+    write_sprites(&mut ppu, &mut mapper, &[(30, SOLID_TILE, 0x00, 0x10)]);
+    render(&mut ppu, &mut mapper, 0x00, 0x0E);
+    assert_eq!(get_row(&ppu, 31)[0x10], 0x0F);
+  }
+}
+
+#[cfg(test)]
+mod sprite_zero_hit {
+  use super::*;
+  fn setup_hit(sprite: (u8, u8, u8, u8), mask: u8) -> (Ppu, Box<dyn Mapper>) {
+    let (mut ppu, mut mapper) = setup_scene();
+    write_sprites(&mut ppu, &mut mapper, &[sprite]);
+    write(&mut ppu, &mut mapper, 0x2001, mask);
+    (ppu, mapper)
+  }
+  fn is_hit(ppu: &Ppu) -> bool {
+    ppu.peek_register(0x2002) & 0x40 > 0
+  }
+  #[test]
+  fn set_on_first_overlapping_opaque_pixel() {
+    // Background block starts at (64, 32), the sprite's first opaque pixel is at (70, 41)
+    let (mut ppu, mut mapper) = setup_hit((40, SOLID_TILE, 0x00, 70), 0x1E);
+    run_until(&mut ppu, &mut mapper, 41, 71);
+    assert!(!is_hit(&ppu));
+    ppu.clock(Some(&mut *mapper));
+    assert!(is_hit(&ppu));
+  }
+  #[test]
+  fn set_behind_background() {
+    let (mut ppu, mut mapper) = setup_hit((40, SOLID_TILE, 0x20, 70), 0x1E);
+    run_until(&mut ppu, &mut mapper, 42, 0);
+    assert!(is_hit(&ppu));
+  }
+  #[test]
+  fn needs_opaque_background() {
+    let (mut ppu, mut mapper) = setup_hit((100, SOLID_TILE, 0x00, 70), 0x1E);
+    run_until(&mut ppu, &mut mapper, 240, 0);
+    assert!(!is_hit(&ppu));
+  }
+  #[test]
+  fn only_sprite_zero_hits() {
+    let (mut ppu, mut mapper) = setup_scene();
+    write_sprites(
+      &mut ppu,
+      &mut mapper,
+      &[(100, SOLID_TILE, 0x00, 0), (40, SOLID_TILE, 0x00, 70)],
+    );
+    write(&mut ppu, &mut mapper, 0x2001, 0x1E);
+    run_until(&mut ppu, &mut mapper, 240, 0);
+    assert!(!is_hit(&ppu));
+  }
+  #[test]
+  fn not_in_clipped_left_column() {
+    let (mut ppu, mut mapper) = setup_scene();
+    // Background tile at (0, 0)
+    set_vram_addr(&mut ppu, &mut mapper, 0x2000);
+    write(&mut ppu, &mut mapper, 0x2007, SOLID_TILE);
+    write_sprites(&mut ppu, &mut mapper, &[(0, SOLID_TILE, 0x00, 0)]);
+    set_vram_addr(&mut ppu, &mut mapper, 0x2000);
+    write(&mut ppu, &mut mapper, 0x2001, 0x18);
+    run_until(&mut ppu, &mut mapper, 240, 0);
+    assert!(!is_hit(&ppu));
+    write(&mut ppu, &mut mapper, 0x2001, 0x1E);
+    run_frame(&mut ppu, &mut mapper);
+    run_until(&mut ppu, &mut mapper, 240, 0);
+    assert!(is_hit(&ppu));
+  }
+  #[test]
+  fn never_at_x_255() {
+    let (mut ppu, mut mapper) = setup_scene();
+    set_vram_addr(&mut ppu, &mut mapper, 0x201F);
+    write(&mut ppu, &mut mapper, 0x2007, SOLID_TILE);
+    write_sprites(&mut ppu, &mut mapper, &[(0, SOLID_TILE, 0x00, 255)]);
+    set_vram_addr(&mut ppu, &mut mapper, 0x2000);
+    write(&mut ppu, &mut mapper, 0x2001, 0x1E);
+    run_until(&mut ppu, &mut mapper, 240, 0);
+    assert!(!is_hit(&ppu));
+  }
+  #[test]
+  fn cleared_on_pre_render_line() {
+    let (mut ppu, mut mapper) = setup_hit((40, SOLID_TILE, 0x00, 70), 0x1E);
+    run_until(&mut ppu, &mut mapper, 261, 1);
+    assert!(is_hit(&ppu));
+    ppu.clock(Some(&mut *mapper));
+    assert!(!is_hit(&ppu));
+  }
+}
+
+#[cfg(test)]
+mod sprite_overflow {
+  use super::*;
+  fn is_overflow(ppu: &Ppu) -> bool {
+    ppu.peek_register(0x2002) & 0x20 > 0
+  }
+  fn run_with_sprites(sprites: &[(u8, u8, u8, u8)]) -> Ppu {
+    let (mut ppu, mut mapper) = setup_scene();
+    write_sprites(&mut ppu, &mut mapper, sprites);
+    write(&mut ppu, &mut mapper, 0x2001, 0x18);
+    run_until(&mut ppu, &mut mapper, 240, 0);
+    ppu
+  }
+  #[test]
+  fn eight_sprites_do_not_overflow() {
+    let ppu = run_with_sprites(&[(50, SOLID_TILE, 0x00, 0); 8]);
+    assert!(!is_overflow(&ppu));
+  }
+  #[test]
+  fn ninth_sprite_overflows() {
+    let ppu = run_with_sprites(&[(50, SOLID_TILE, 0x00, 0); 9]);
+    assert!(is_overflow(&ppu));
+  }
+  #[test]
+  fn hardware_bug_misses_ninth_sprite() {
+    // After sprite 8 misses, sprite 9's tile number is read as its Y position
+    let mut sprites = vec![(50, SOLID_TILE, 0x00, 0); 8];
+    sprites.push((200, SOLID_TILE, 0x00, 0));
+    sprites.push((50, SOLID_TILE, 0x00, 0));
+    let ppu = run_with_sprites(&sprites);
+    assert!(!is_overflow(&ppu));
+  }
+  #[test]
+  fn hardware_bug_reports_false_overflow() {
+    let mut sprites = vec![(50, SOLID_TILE, 0x00, 0); 8];
+    sprites.push((200, SOLID_TILE, 0x00, 0));
+    // Off screen, but its tile number matches line 50
+    sprites.push((200, 50, 0x00, 0));
+    let ppu = run_with_sprites(&sprites);
+    assert!(is_overflow(&ppu));
+  }
+  #[test]
+  fn status_read_does_not_clear_overflow() {
+    let (mut ppu, mut mapper) = setup_scene();
+    write_sprites(&mut ppu, &mut mapper, &[(50, SOLID_TILE, 0x00, 0); 9]);
+    write(&mut ppu, &mut mapper, 0x2001, 0x18);
+    run_until(&mut ppu, &mut mapper, 240, 0);
+    read(&mut ppu, &mut mapper, 0x2002);
+    assert!(is_overflow(&ppu));
+    run_until(&mut ppu, &mut mapper, 261, 2);
+    assert!(!is_overflow(&ppu));
+  }
+}
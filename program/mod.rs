@@ -0,0 +1,96 @@
+// Runs a flat binary on the CPU alone, without any NES hardware around it. Test suites such as
+// Klaus Dormann's report their result by trapping: jumping or branching to themselves forever
+
+mod tests;
+
+use bus::Bus;
+use cpu::{Cpu, Variant};
+use data_flow::ReadWrite;
+use std::error;
+use std::fmt;
+
+const MEMORY_SIZE: usize = 0x10000;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ProgramError {
+  TooLarge { addr: u16, size: usize }, // Program does not fit between its address and 0xFFFF
+  Jammed(u16),                         // A JAM opcode halted the CPU at this address
+  NoTrap(usize),                       // Still running after this many instructions
+}
+
+impl fmt::Display for ProgramError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      ProgramError::TooLarge { addr, size } => write!(
+        f,
+        "A program of {:#X} bytes does not fit in memory at {:#06X}",
+        size, addr
+      ),
+      ProgramError::Jammed(addr) => write!(f, "CPU jammed at ${:04X}", addr),
+      ProgramError::NoTrap(instructions) => {
+        write!(f, "No trap after {} instructions", instructions)
+      }
+    }
+  }
+}
+
+impl error::Error for ProgramError {}
+
+// Where a program stopped changing pc
+#[derive(Clone, Debug, PartialEq)]
+pub struct Trap {
+  pub addr: u16,
+  pub instructions: usize,
+  pub cycles: usize,
+}
+
+pub struct Program {
+  cpu: Cpu,
+}
+
+impl Program {
+  // All 64 KB are RAM, the program is copied to addr and runs from start
+  pub fn load(
+    bytes: &[u8],
+    addr: u16,
+    start: u16,
+    variant: Variant,
+  ) -> Result<Program, ProgramError> {
+    if addr as usize + bytes.len() > MEMORY_SIZE {
+      return Err(ProgramError::TooLarge {
+        addr,
+        size: bytes.len(),
+      });
+    }
+    let mut cpu = Cpu::with_variant(Bus::flat(), variant);
+    for (offset, byte) in bytes.iter().enumerate() {
+      cpu.write_addr(addr + offset as u16, *byte);
+    }
+    cpu.set_pc(start);
+    Ok(Program { cpu })
+  }
+
+  #[cfg(test)]
+  pub fn get_cpu(&self) -> &Cpu {
+    &self.cpu
+  }
+
+  // Steps until an instruction leaves pc where it started
+  pub fn run_until_trap(&mut self, instruction_limit: usize) -> Result<Trap, ProgramError> {
+    let start_tick = *self.cpu.get_current_tick();
+    for instructions in 1..=instruction_limit {
+      self.cpu.step();
+      if self.cpu.is_jammed() {
+        return Err(ProgramError::Jammed(*self.cpu.get_ppc()));
+      }
+      if self.cpu.get_pc() == self.cpu.get_ppc() {
+        return Ok(Trap {
+          addr: *self.cpu.get_pc(),
+          instructions,
+          cycles: *self.cpu.get_current_tick() - start_tick,
+        });
+      }
+    }
+    Err(ProgramError::NoTrap(instruction_limit))
+  }
+}
@@ -0,0 +1,119 @@
+// Source: https://github.com/Klaus2m5/6502_65C02_functional_tests
+// The assembled binary is not checked in, copy it to roms/ to run the suite
+
+#[cfg(test)]
+use cpu::Variant;
+
+#[cfg(test)]
+use data_flow::ReadWrite;
+
+#[cfg(test)]
+use program::{Program, ProgramError, Trap};
+
+#[cfg(test)]
+use std::fs;
+
+#[cfg(test)]
+use std::path::Path;
+
+#[cfg(test)]
+const PROGRAM_ADDR: u16 = 0x0200;
+
+#[cfg(test)]
+fn run(program: &[u8], limit: usize) -> Result<Trap, ProgramError> {
+  Program::load(program, PROGRAM_ADDR, PROGRAM_ADDR, Variant::Nmos6502)
+    .unwrap()
+    .run_until_trap(limit)
+}
+
+#[test]
+fn traps_on_jump_to_itself() {
+  // INX, JMP $0201
+  let trap = run(&[0xE8, 0x4C, 0x01, 0x02], 100).unwrap();
+  assert_eq!(
+    trap,
+    Trap {
+      addr: 0x0201,
+      instructions: 2,
+      cycles: 5,
+    }
+  );
+}
+
+#[test]
+fn traps_on_branch_to_itself() {
+  // LDA #$00, BEQ -2
+  assert_eq!(run(&[0xA9, 0x00, 0xF0, 0xFE], 100).unwrap().addr, 0x0202);
+}
+
+#[test]
+fn loops_that_change_pc_are_not_traps() {
+  // DEX, BNE -3, JMP $0203
+  let trap = run(&[0xCA, 0xD0, 0xFD, 0x4C, 0x03, 0x02], 1000).unwrap();
+  assert_eq!(trap.addr, 0x0203);
+  assert_eq!(trap.instructions, 2 * 256 + 1);
+}
+
+#[test]
+fn runs_from_start_address() {
+  // Program at $1000 starting at its second instruction: JMP $1000, JMP $1003
+  let mut program = Program::load(
+    &[0x4C, 0x00, 0x10, 0x4C, 0x03, 0x10],
+    0x1000,
+    0x1003,
+    Variant::Nmos6502,
+  )
+  .unwrap();
+  assert_eq!(program.run_until_trap(10).unwrap().addr, 0x1003);
+}
+
+#[test]
+fn reports_running_past_the_limit() {
+  // DEX, BNE -3, JMP $0203
+  assert_eq!(
+    run(&[0xCA, 0xD0, 0xFD, 0x4C, 0x03, 0x02], 10),
+    Err(ProgramError::NoTrap(10))
+  );
+}
+
+#[test]
+fn reports_jam() {
+  assert_eq!(run(&[0xEA, 0x02], 10), Err(ProgramError::Jammed(0x0201)));
+  assert_eq!(
+    ProgramError::Jammed(0x0201).to_string(),
+    "CPU jammed at $0201"
+  );
+}
+
+#[test]
+fn rejects_program_past_end_of_memory() {
+  assert_eq!(
+    Program::load(&[0xEA; 4], 0xFFFE, 0xFFFE, Variant::Nmos6502).err(),
+    Some(ProgramError::TooLarge {
+      addr: 0xFFFE,
+      size: 4,
+    })
+  );
+}
+
+// Fills all of memory and traps at 0x3469 once every test passed, anywhere else on a failure
+#[test]
+fn functional_test() {
+  let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("roms/6502_functional_test.bin");
+  let binary = match fs::read(&path) {
+    Ok(binary) => binary,
+    Err(_) => {
+      println!("Skipping functional test, {} not found", path.display());
+      return;
+    }
+  };
+  let mut program = Program::load(&binary, 0x0000, 0x0400, Variant::Nmos6502).unwrap();
+  let trap = program.run_until_trap(100_000_000).unwrap();
+  assert_eq!(
+    trap.addr,
+    0x3469,
+    "Trapped at {:#06X}, test case {:02X}",
+    trap.addr,
+    program.get_cpu().read_addr(0x0200)
+  );
+}